@@ -0,0 +1,111 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+"""
+fuzz_read_trades.py
+
+Fuzzing entry point for the trade file readers.
+
+Trade files are chosen by the user, so the readers must reject
+anything that is not a list of trades with ValueError and never
+fail in any other way.
+
+Each input is read as a csv file, as JSON text, both an array of
+numbers and an array of objects, as a Parquet and an Arrow IPC
+file, and as the standard input of a pipeline.  Without pyarrow,
+the Parquet and Arrow readers only refuse every input.
+
+With atheris installed (pip3 install atheris), run
+    python3 fuzz_read_trades.py
+to fuzz with coverage guidance.
+Without it, the same command feeds random byte strings to the
+readers for a fixed number of rounds.
+
+License:  MIT
+"""
+
+import io
+import os
+import random
+import sys
+import tempfile
+
+from risk_normalization import ARROW_FILE_FORMATS
+from risk_normalization import parse_trades_from_arrow
+from risk_normalization import parse_trades_from_json
+from risk_normalization import read_trades_from_csv
+from risk_normalization import read_trades_from_stream
+
+number_rounds = 10000
+
+
+def TestOneInput(data):
+
+    """
+    Read data as trades with every reader.
+    Any exception other than ValueError is a failure.
+    """
+
+    handle, path = tempfile.mkstemp(suffix = '.csv')
+    try:
+        with os.fdopen(handle, 'wb') as f:
+            f.write(data)
+        try:
+            read_trades_from_csv(path)
+        except ValueError:
+            pass
+    finally:
+        os.remove(path)
+
+    text = data.decode('utf-8', errors = 'replace')
+    for field in (None, 'gain'):
+        try:
+            parse_trades_from_json(text, field = field)
+        except ValueError:
+            pass
+
+    for file_format in ARROW_FILE_FORMATS:
+        try:
+            parse_trades_from_arrow(data, file_format = file_format)
+        except ValueError:
+            pass
+
+    try:
+        read_trades_from_stream(io.StringIO(text))
+    except ValueError:
+        pass
+
+
+def random_input():
+
+    """
+    Random bytes, mixed with fragments of real trade files so that
+    some inputs get past the first line.
+    """
+
+    fragments = [b'-0.0254\n', b'0.0100,x\n', b'trades\n', b'\n', b',',
+                 b'nan\n', b'1e999\n', b'\xef\xbb\xbf', b'\r\n', b'1_0\n',
+                 b'[', b']', b'{"gain": ', b'}', b'true', b'null',
+                 b'1' + b'0' * 400, b'[' * 5000, b'PAR1', b'ARROW1']
+    parts = []
+    for i in range(random.randint(0, 20)):
+        if random.random() < 0.5:
+            parts.append(random.choice(fragments))
+        else:
+            parts.append(bytes(random.randint(0, 255)
+                               for j in range(random.randint(0, 16))))
+    return b''.join(parts)
+
+
+if __name__ == '__main__':
+    try:
+        import atheris
+    except ImportError:
+        atheris = None
+
+    if atheris is not None:
+        atheris.Setup(sys.argv, TestOneInput)
+        atheris.Fuzz()
+    else:
+        for i in range(number_rounds):
+            TestOneInput(random_input())
+        print(f'{number_rounds} random inputs read without failure')
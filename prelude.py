@@ -0,0 +1,198 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+"""
+prelude.py
+
+The public interface of risk normalization in one place.  A program
+that needs more than one or two names imports them from here:
+
+    from prelude import *
+
+rather than from risk_normalization, result_writer, and sweep
+separately.  Everything named here is supported; the other functions
+of those modules are the workings of the engine and may change.
+
+Engine:
+    risk_normalization_with_config and its variants, the inverse
+    solvers, the studies built on them, and sharding for running
+    on several machines.
+
+Config:
+    RiskNormalizationConfig and the objects its options take.
+
+Samplers:
+    The ResamplingMethod of each way of drawing trades.
+
+Metrics:
+    The statistics computed from equity sequences.
+
+Readers:
+    Reading and checking trades from files, text, and streams.
+
+Results:
+    The objects returned, and writing and reading them.
+
+Errors and warnings:
+    RiskNormalizationError, the base of every error raised, its
+    subclasses, and RiskNormalizationWarning.
+
+License:  MIT
+"""
+
+#  Engine
+from risk_normalization import analyze_portfolio
+from risk_normalization import analyze_prop_firm_evaluation
+from risk_normalization import analyze_sample_size_variability
+from risk_normalization import analyze_update_frequency
+from risk_normalization import compare_trade_sets
+from risk_normalization import format_ranking
+from risk_normalization import merge_repetition_shards
+from risk_normalization import rank_strategies
+from risk_normalization import replay_equity_sequence
+from risk_normalization import required_capital_for
+from risk_normalization import risk_normalization
+from risk_normalization import risk_normalization_async
+from risk_normalization import risk_normalization_dynamic
+from risk_normalization import risk_normalization_in_shards
+from risk_normalization import risk_normalization_with_config
+from risk_normalization import run_repetition_shard
+from risk_normalization import solve_mean_improvement_for_CAR25
+from risk_normalization import solve_tolerance_for_fraction
+from sweep import parameter_range
+from sweep import sweep
+
+#  Config
+from risk_normalization import CONFIDENCE_METHODS
+from risk_normalization import MAX_FRACTION
+from risk_normalization import PERCENTILE_METHODS
+from risk_normalization import RANDOM_ALGORITHMS
+from risk_normalization import SIMULATION_BACKENDS
+from risk_normalization import TRADING_DAYS_PER_YEAR
+from risk_normalization import VARIANCE_REDUCTIONS
+from risk_normalization import CashFlowSchedule
+from risk_normalization import CostModel
+from risk_normalization import ExecutionNoise
+from risk_normalization import FixedContractsSizing
+from risk_normalization import FixedDollarSizing
+from risk_normalization import FixedFractionSizing
+from risk_normalization import FunctionTransform
+from risk_normalization import HaircutTransform
+from risk_normalization import PeriodicRebalanceSizing
+from risk_normalization import PositionSizing
+from risk_normalization import PrintProgress
+from risk_normalization import ProgressSink
+from risk_normalization import PropFirmRules
+from risk_normalization import Regime
+from risk_normalization import RiskNormalizationConfig
+from risk_normalization import Scenario
+from risk_normalization import StrategyInfo
+from risk_normalization import TradeTransform
+from risk_normalization import VolatilityScaledSizing
+from risk_normalization import load_scenarios
+from risk_normalization import parse_scenarios
+
+#  Samplers
+from risk_normalization import AntitheticResampling
+from risk_normalization import CircularBlockResampling
+from risk_normalization import IIDResampling
+from risk_normalization import MovingBlockResampling
+from risk_normalization import ResamplingMethod
+from risk_normalization import StationaryBootstrapResampling
+from risk_normalization import WeightedResampling
+from risk_normalization import blend_trade_samples
+
+#  Metrics
+from risk_normalization import StreamingQuantileBands
+from risk_normalization import compute_CAR
+from risk_normalization import compute_confidence_interval
+from risk_normalization import compute_histogram
+from risk_normalization import compute_path_benchmark_metrics
+from risk_normalization import compute_path_streaks
+from risk_normalization import compute_percentile
+from risk_normalization import compute_statistics
+from risk_normalization import hash_trades
+
+#  Readers
+from risk_normalization import ARROW_FILE_FORMATS
+from risk_normalization import TRADE_FILE_SUFFIXES
+from risk_normalization import CsvOptions
+from risk_normalization import Trade
+from risk_normalization import TradeFileCache
+from risk_normalization import iterate_trades_from_csv
+from risk_normalization import list_trade_files
+from risk_normalization import parse_trade_records
+from risk_normalization import parse_trades
+from risk_normalization import parse_trades_from_arrow
+from risk_normalization import parse_trades_from_json
+from risk_normalization import parse_trades_of_any_format
+from risk_normalization import preview_trades
+from risk_normalization import read_trade_records_from_csv
+from risk_normalization import read_trades
+from risk_normalization import read_trades_from_csv
+from risk_normalization import read_trades_from_ipc
+from risk_normalization import read_trades_from_json
+from risk_normalization import read_trades_from_parquet
+from risk_normalization import read_trades_from_stream
+from risk_normalization import read_trades_in_chunks
+from risk_normalization import reservoir_sample_trades
+from risk_normalization import select_trade_records
+from risk_normalization import transform_trades
+from risk_normalization import validate_trades
+
+#  Results
+from risk_normalization import RANKING_FORMATS
+from risk_normalization import SESSION_VERSION
+from risk_normalization import AccountSizePlan
+from risk_normalization import BenchmarkStatistics
+from risk_normalization import ComputeUsage
+from risk_normalization import ConfidenceInterval
+from risk_normalization import DrawdownProfile
+from risk_normalization import DynamicSizingResult
+from risk_normalization import EquityBands
+from risk_normalization import EquityCurve
+from risk_normalization import GoalProbability
+from risk_normalization import Histogram
+from risk_normalization import PortfolioResult
+from risk_normalization import PropFirmOutcome
+from risk_normalization import RepetitionResult
+from risk_normalization import RiskNormalizationResult
+from risk_normalization import SampleSizeAdvisory
+from risk_normalization import SearchPass
+from risk_normalization import Session
+from risk_normalization import SimulationSeed
+from risk_normalization import StrategyRanking
+from risk_normalization import StreakStatistics
+from risk_normalization import SummaryStats
+from risk_normalization import TradePreview
+from risk_normalization import TradeSetComparison
+from risk_normalization import UpdateFrequencyOutcome
+from risk_normalization import ValueAtRisk
+from risk_normalization import environment_info
+from risk_normalization import read_session
+from risk_normalization import record_session
+from risk_normalization import replay_session
+from risk_normalization import select_results
+from risk_normalization import write_CAR_distributions
+from result_writer import SCHEMA_VERSION
+from result_writer import ResultWriter
+from result_writer import read_json
+from result_writer import read_trade_indices
+from sweep import SweepGrid
+
+#  Errors and warnings
+from risk_normalization import ArrowParseError
+from risk_normalization import CsvParseError
+from risk_normalization import EmptyTradesError
+from risk_normalization import InvalidParameterError
+from risk_normalization import JsonParseError
+from risk_normalization import ResourceLimitError
+from risk_normalization import RiskNormalizationError
+from risk_normalization import RiskNormalizationWarning
+from risk_normalization import RunCancelledError
+from risk_normalization import ScenarioParseError
+from risk_normalization import SearchDidNotConvergeError
+from risk_normalization import SessionFileError
+from risk_normalization import TooFewTradesError
+
+
+__all__ = [name for name in dir() if not name.startswith('_')]
@@ -0,0 +1,575 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+"""
+reproducibility.py
+
+Checks that risk_normalization gives the same answers from run to
+run, and that its engines agree with each other.
+
+Use these before accepting a change that is meant to make the
+calculations faster without changing the results.
+
+check_same_seed_is_repeatable:
+    The same config and seed, run twice with the same engine,
+    must give identical results.
+
+check_worker_counts_agree:
+    The same config and seed, with number_candidates fixed, run
+    with several numbers of worker processes, must give
+    identical results.  At least one of the numbers must really
+    start worker processes on this machine.
+
+check_engines_agree:
+    The same config is run across several seeds with each engine,
+    sequential and worker processes.  The means of safe-f and
+    CAR25 from each engine must agree within the sampling error
+    of the seeds.  The engines draw different random numbers,
+    so only the distributions are expected to agree.  Unless
+    the number of workers really starts worker processes on
+    this machine, the check fails.
+
+Each check raises AssertionError describing the first
+disagreement found, and returns the results it compared.
+
+make_parity_report:
+    Runs the checks for every config of a matrix, and compares the
+    distributions of safe-f and CAR25 from the two engines across
+    the seeds with a two sample Kolmogorov-Smirnov test.  Returns a
+    report that records every check, passed or not, and can be
+    written as JSON.  Run it before every release:
+
+        python3 reproducibility.py --trades generated_normal_trades.csv \
+            --report parity.json
+
+run_soak:
+    Runs jobs on synthetic trades, one after another, for hours,
+    and checks that every job repeats the result of its first run
+    and that the memory held by the process does not grow.  For
+    validating a machine before it is trusted with long runs; the
+    option is not listed by --help:
+
+        python3 reproducibility.py --soak 8 --report soak.json
+
+License:  MIT
+"""
+
+import argparse
+import gc
+import json
+import math
+import random
+import statistics
+import sys
+import time
+import tracemalloc
+
+from risk_normalization import CostModel
+from risk_normalization import MovingBlockResampling
+from risk_normalization import RiskNormalizationConfig
+from risk_normalization import count_available_processors
+from risk_normalization import count_fraction_processes
+from risk_normalization import read_trades_from_csv
+from risk_normalization import risk_normalization_with_config
+
+
+def run_engine_across_seeds(
+    trades,
+    config,
+    seeds,
+    number_workers ):
+
+    """
+    Run risk_normalization_with_config once for each seed.
+    
+    Parameters:
+    trades:          the set of trades to be analyzed.
+    config:          RiskNormalizationConfig.  seed and
+                     number_workers are replaced.
+    seeds:           list of seeds.
+    number_workers:  None for the sequential engine, or the number
+                     of worker processes.
+    
+    Returns:
+    results:  list of RiskNormalizationResult, one per seed.
+    """
+
+    results = []
+    for seed in seeds:
+        results.append(risk_normalization_with_config(
+                           trades,
+                           config.replace(seed = seed,
+                                          number_workers = number_workers)))
+
+    return results
+
+
+def check_same_seed_is_repeatable(
+    trades,
+    config,
+    seed,
+    number_workers = None ):
+
+    """
+    Run the same config and seed twice with one engine and
+    assert that every safe-f and CAR25 is identical.
+    """
+
+    first, second = run_engine_across_seeds(trades, config,
+                                            [seed, seed],
+                                            number_workers)
+
+    assert first.safe_f_list == second.safe_f_list, (
+        f'seed {seed}: safe-f differs between runs: '
+        f'{first.safe_f_list} and {second.safe_f_list}')
+    assert first.CAR25_list == second.CAR25_list, (
+        f'seed {seed}: CAR25 differs between runs: '
+        f'{first.CAR25_list} and {second.CAR25_list}')
+
+    return (first, second)
+
+
+def check_worker_counts_agree(
+    trades,
+    config,
+    seed,
+    worker_counts = (None, 3, 4),
+    number_candidates = 3   ):
+
+    """
+    Run the same config and seed with each number of workers,
+    evaluating number_candidates fractions per pass, and assert
+    that every safe-f and CAR25 is identical to the first run.
+    
+    With 2 or fewer worker processes, or on a machine with 2 or
+    fewer processors, the candidates are evaluated in this process,
+    as count_fraction_processes describes.  Unless one of the
+    worker counts starts worker processes, the check would compare
+    the engine with itself, so it fails.
+    
+    Returns:
+    results:  list of RiskNormalizationResult, one per worker count.
+    """
+
+    config = config.replace(number_candidates = number_candidates)
+    numbers_of_processes = [
+        count_fraction_processes(config.replace(number_workers =
+                                                    number_workers))
+        for number_workers in worker_counts]
+    assert any(numbers_of_processes), (
+        f'none of the worker counts {list(worker_counts)} starts worker '
+        f'processes on this machine, with {count_available_processors()} '
+        f'processors available')
+    results = [run_engine_across_seeds(trades, config, [seed],
+                                       number_workers)[0]
+               for number_workers in worker_counts]
+
+    first = results[0]
+    for number_workers, result in zip(worker_counts, results):
+        assert result.safe_f_list == first.safe_f_list, (
+            f'seed {seed}: safe-f with {number_workers} workers differs: '
+            f'{result.safe_f_list} and {first.safe_f_list}')
+        assert result.CAR25_list == first.CAR25_list, (
+            f'seed {seed}: CAR25 with {number_workers} workers differs: '
+            f'{result.CAR25_list} and {first.CAR25_list}')
+
+    return results
+
+
+def check_means_agree(
+    name,
+    values_a,
+    values_b,
+    number_stdevs,
+    absolute_tolerance ):
+
+    """
+    Assert that two samples have means within number_stdevs
+    standard errors of their difference, plus absolute_tolerance.
+    """
+
+    mean_a = statistics.mean(values_a)
+    mean_b = statistics.mean(values_b)
+    if len(values_a) > 1 and len(values_b) > 1:
+        standard_error = math.sqrt(
+                             statistics.variance(values_a) / len(values_a) +
+                             statistics.variance(values_b) / len(values_b))
+    else:
+        standard_error = 0.0
+    allowed = number_stdevs * standard_error + absolute_tolerance
+
+    assert abs(mean_a - mean_b) <= allowed, (
+        f'{name}: means {mean_a:0.4f} and {mean_b:0.4f} differ by '
+        f'more than {allowed:0.4f}')
+
+
+def check_engines_agree(
+    trades,
+    config,
+    seeds,
+    number_workers = 4,
+    number_stdevs = 4.0,
+    safe_f_tolerance = 0.01,
+    CAR25_tolerance = 0.25 ):
+
+    """
+    Run the same config across all seeds with the sequential
+    engine and with number_workers worker processes, and assert
+    that the mean safe-f and mean CAR25 agree.  On a machine where
+    number_workers evaluates the candidates in this process, as
+    count_fraction_processes describes, the check fails rather
+    than compare the sequential engine with itself.
+    
+    Parameters:
+    trades:            the set of trades to be analyzed.
+    config:            RiskNormalizationConfig.
+    seeds:             list of seeds.  10 or more give a
+                       useful test.
+    number_workers:    worker processes for the second engine.
+    number_stdevs:     allowed difference in standard errors.
+    safe_f_tolerance:  allowed difference of safe-f in addition.
+    CAR25_tolerance:   allowed difference of CAR25, in percent,
+                       in addition.
+    
+    Returns:
+    results:  dictionary of engine name to its list of results.
+    """
+
+    number_processes = count_fraction_processes(
+                           config.replace(number_workers = number_workers))
+    assert number_processes > 0, (
+        f'{number_workers} workers start no worker processes on this '
+        f'machine, with {count_available_processors()} processors '
+        f'available')
+    results = {
+        'sequential': run_engine_across_seeds(trades, config, seeds, None),
+        'workers': run_engine_across_seeds(trades, config, seeds,
+                                           number_workers),
+        }
+
+    safe_fs = {}
+    CAR25s = {}
+    for engine, engine_results in results.items():
+        safe_fs[engine] = [result.safe_f_mean for result in engine_results]
+        CAR25s[engine] = [result.CAR25_mean for result in engine_results]
+
+    check_means_agree('safe-f', safe_fs['sequential'], safe_fs['workers'],
+                      number_stdevs, safe_f_tolerance)
+    check_means_agree('CAR25', CAR25s['sequential'], CAR25s['workers'],
+                      number_stdevs, CAR25_tolerance)
+
+    return results
+
+
+def compute_ks_test(
+    values_a,
+    values_b    ):
+
+    """
+    The two sample Kolmogorov-Smirnov test of whether two samples
+    come from the same distribution.
+    
+    Returns:
+    statistic:  the largest distance between their empirical
+                distribution functions.
+    p_value:    the asymptotic probability of a distance at least
+                that large if they do.
+    """
+
+    sorted_a = sorted(values_a)
+    sorted_b = sorted(values_b)
+    number_a = len(sorted_a)
+    number_b = len(sorted_b)
+
+    statistic = 0.0
+    i = 0
+    j = 0
+    while i < number_a and j < number_b:
+        value = min(sorted_a[i], sorted_b[j])
+        while i < number_a and sorted_a[i] == value:
+            i = i + 1
+        while j < number_b and sorted_b[j] == value:
+            j = j + 1
+        statistic = max(statistic, abs(i / number_a - j / number_b))
+
+    effective_number = math.sqrt(number_a * number_b /
+                                 (number_a + number_b))
+    scale = (effective_number + 0.12 + 0.11 / effective_number) * statistic
+    if scale < 0.2:
+        return (statistic, 1.0)
+    p_value = 2.0 * sum((-1) ** (k - 1) * math.exp(-2.0 * k * k * scale ** 2)
+                        for k in range(1, 101))
+
+    return (statistic, min(max(p_value, 0.0), 1.0))
+
+
+def run_check(
+    name,
+    check,
+    *arguments  ):
+
+    """
+    Run one check, and record whether it passed.
+    
+    Returns:
+    dictionary with the check's name, passed, and the message of
+    its AssertionError, or None.
+    """
+
+    try:
+        check(*arguments)
+    except AssertionError as error:
+        return {'check': name, 'passed': False, 'message': str(error)}
+
+    return {'check': name, 'passed': True, 'message': None}
+
+
+def make_parity_report(
+    trades,
+    configs,
+    seeds,
+    number_workers = 4,
+    significance = 0.01 ):
+
+    """
+    Check every config of a matrix: that the same seed repeats,
+    that the number of workers does not change the results when
+    number_candidates is fixed, which is guaranteed, and that the
+    two engines give the same distributions of safe-f and CAR25
+    across the seeds, which is tested.  The engine checks record
+    the number of worker processes the second engine really
+    started, and fail if it started none, since it then evaluated
+    the candidates in this process, like the first.
+    
+    Parameters:
+    trades:          the set of trades to be analyzed.
+    configs:         dictionary from a name to each
+                     RiskNormalizationConfig.
+    seeds:           list of seeds.  10 or more give a useful test.
+    number_workers:  worker processes for the second engine.
+    significance:    a Kolmogorov-Smirnov test with a p-value below
+                     this fails.
+    
+    Returns:
+    dictionary with the seeds, number_workers, and significance,
+    configs, a list with the name and checks of each config, and
+    passed, True if every check passed.
+    """
+
+    entries = []
+    for name, config in configs.items():
+        checks = [run_check('same_seed_is_repeatable',
+                            check_same_seed_is_repeatable,
+                            trades, config, seeds[0]),
+                  run_check('worker_counts_agree',
+                            check_worker_counts_agree,
+                            trades, config, seeds[0],
+                            (None, number_workers))]
+
+        number_processes = count_fraction_processes(
+                               config.replace(number_workers =
+                                                  number_workers))
+        if number_processes > 0:
+            engine_message = None
+        else:
+            engine_message = (f'{number_workers} workers start no worker '
+                              f'processes on this machine, with '
+                              f'{count_available_processors()} processors '
+                              f'available, so both engines ran in this '
+                              f'process')
+        results = {
+            'sequential': run_engine_across_seeds(trades, config, seeds,
+                                                  None),
+            'workers': run_engine_across_seeds(trades, config, seeds,
+                                               number_workers),
+            }
+        for quantity in ('safe_f_mean', 'CAR25_mean'):
+            statistic, p_value = compute_ks_test(
+                [getattr(result, quantity)
+                 for result in results['sequential']],
+                [getattr(result, quantity)
+                 for result in results['workers']])
+            checks.append({'check': f'engines_agree_{quantity}',
+                           'passed': (number_processes > 0 and
+                                      p_value >= significance),
+                           'message': engine_message,
+                           'statistic': statistic,
+                           'p_value': p_value,
+                           'worker_processes': number_processes})
+
+        entries.append({'name': name,
+                        'config': repr(config),
+                        'checks': checks})
+
+    return {'seeds': list(seeds),
+            'number_workers': number_workers,
+            'significance': significance,
+            'configs': entries,
+            'passed': all(check['passed']
+                          for entry in entries
+                          for check in entry['checks'])}
+
+
+def make_synthetic_trades(
+    seed,
+    number_trades = 500 ):
+
+    """
+    A set of daily trades drawn from a Normal distribution whose
+    mean and standard deviation also depend on the seed, drawn with
+    a generator of its own so that numpy's global state is left
+    alone.
+    """
+
+    generator = random.Random(seed)
+    mean_gain = generator.uniform(0.0002, 0.0015)
+    std_dev_gain = generator.uniform(0.002, 0.012)
+
+    return [generator.gauss(mean_gain, std_dev_gain)
+            for _ in range(number_trades)]
+
+
+def run_soak(
+    duration_seconds,
+    configs,
+    number_distinct_jobs = 8,
+    number_workers = None,
+    max_memory_growth_bytes = 50000000 ):
+
+    """
+    Run jobs on synthetic trades until duration_seconds have
+    passed.  Job i runs the trades and seed of job
+    i % number_distinct_jobs with the configs in turn, so every
+    job after the first round repeats an earlier one and must give
+    identical results.  The memory held by the process is measured
+    after the first round, once every cache and pool has been
+    filled, and again at the end.
+    
+    Parameters:
+    duration_seconds:  how long to keep submitting jobs.  At least
+                     one round is always run.
+    configs:         dictionary from a name to each
+                     RiskNormalizationConfig.
+    number_distinct_jobs:  the number of different trade sets and
+                     seeds.
+    number_workers:  None for the sequential engine, or the number
+                     of worker processes.
+    max_memory_growth_bytes:  the soak fails if the memory held
+                     grows by more.
+    
+    Returns:
+    dictionary with the number of jobs run, the seconds they took,
+    the memory held after the first round and at the end, the
+    disagreements found, and passed, True if there were none and
+    the memory did not grow too much.
+    """
+
+    names = list(configs)
+    number_first_round = number_distinct_jobs * len(names)
+    first_results = {}
+    failures = []
+    memory_after_first_round = None
+
+    tracemalloc.start()
+    started = time.monotonic()
+    job = 0
+    while (job < number_first_round or
+           time.monotonic() - started < duration_seconds):
+        distinct_job = job % number_distinct_jobs
+        name = names[(job // number_distinct_jobs) % len(names)]
+        seed = distinct_job + 1
+        result = risk_normalization_with_config(
+                     make_synthetic_trades(seed),
+                     configs[name].replace(seed = seed,
+                                           number_workers = number_workers))
+        key = (name, distinct_job)
+        if key not in first_results:
+            first_results[key] = (result.safe_f_list, result.CAR25_list)
+        elif first_results[key] != (result.safe_f_list, result.CAR25_list):
+            failures.append(f'job {job}, {name} with seed {seed}: results '
+                            f'differ from its first run')
+        job = job + 1
+        if job == number_first_round:
+            gc.collect()
+            memory_after_first_round = tracemalloc.get_traced_memory()[0]
+
+    elapsed = time.monotonic() - started
+    gc.collect()
+    memory_at_end = tracemalloc.get_traced_memory()[0]
+    tracemalloc.stop()
+
+    memory_growth = memory_at_end - memory_after_first_round
+    if memory_growth > max_memory_growth_bytes:
+        failures.append(f'memory held grew by {memory_growth} bytes, more '
+                        f'than {max_memory_growth_bytes}')
+
+    return {'number_jobs': job,
+            'seconds': elapsed,
+            'number_workers': number_workers,
+            'memory_after_first_round': memory_after_first_round,
+            'memory_at_end': memory_at_end,
+            'failures': failures,
+            'passed': len(failures) == 0}
+
+
+def main(
+    arguments = None    ):
+
+    parser = argparse.ArgumentParser(
+                 description = 'Check that the engines of '
+                               'risk_normalization agree, and write a '
+                               'parity report.')
+    parser.add_argument('--trades', default = None,
+                        help = 'csv or txt file of trades')
+    parser.add_argument('--report', default = None, metavar = 'PATH',
+                        help = 'write the report to this JSON file')
+    parser.add_argument('--seeds', type = int, default = 10,
+                        help = 'number of seeds, default %(default)s')
+    parser.add_argument('--workers', type = int, default = 4,
+                        help = 'worker processes, default %(default)s')
+    #  for operators validating a machine, so not listed by --help
+    parser.add_argument('--soak', type = float, default = None,
+                        metavar = 'HOURS', help = argparse.SUPPRESS)
+    options = parser.parse_args(arguments)
+
+    config = RiskNormalizationConfig(number_equity_in_CDF = 100,
+                                     number_repetitions = 3)
+    configs = {
+        'default': config,
+        'moving_blocks': config.replace(
+                             resampling_method = MovingBlockResampling(5)),
+        'costs': config.replace(
+                     cost_model = CostModel(slippage = 0.0005)),
+        }
+
+    if options.soak is not None:
+        report = run_soak(options.soak * 3600.0, configs,
+                          number_workers = options.workers)
+        print(f"{report['number_jobs']} jobs in {report['seconds']:0.0f} "
+              f"seconds: {'passed' if report['passed'] else 'FAILED'}")
+        for failure in report['failures']:
+            print(failure)
+        if options.report is not None:
+            with open(options.report, 'w') as f:
+                json.dump(report, f, indent = 1)
+        return 0 if report['passed'] else 1
+
+    if options.trades is None:
+        parser.error('--trades is required')
+    trades = read_trades_from_csv(options.trades)
+    report = make_parity_report(trades, configs,
+                                list(range(1, options.seeds + 1)),
+                                options.workers)
+
+    for entry in report['configs']:
+        for check in entry['checks']:
+            print(f"{entry['name']}  {check['check']}: "
+                  f"{'passed' if check['passed'] else 'FAILED'}")
+    if options.report is not None:
+        with open(options.report, 'w') as f:
+            json.dump(report, f, indent = 1)
+
+    return 0 if report['passed'] else 1
+
+
+if __name__ == '__main__':
+    sys.exit(main())
@@ -0,0 +1,450 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+"""
+result_writer.py
+
+Writes the results of risk_normalization to files, so that runs
+can be archived and compared across strategies without running
+them again.
+
+ResultWriter.write_json:
+    The whole result in one JSON file -- the summary values, the
+    list from each repetition, the CAR and drawdown distributions
+    and histograms, the percentile bands of equity, the losing
+    streaks and the comparison with a benchmark if they were kept,
+    the config and seed used, with how
+    the seed drives the random numbers and a digest of the trades,
+    the times the run started and finished, and the computing it
+    used.
+
+ResultWriter.write_csv:
+    One row per repetition, with safe-f, TWR25, CAR25, and the
+    drawdown profile at safe-f.
+
+ResultWriter.write_distributions_csv:
+    One row per equity curve at safe-f, with its CAR and maximum
+    drawdown.  Needs a run with keep_CAR_distributions = True.
+
+ResultWriter.write_trade_indices:
+    One row per equity curve at safe-f of the final repetition, with
+    the indices of the trades drawn for it, gzip compressed, for an
+    audit.  Needs a run with keep_trade_indices = True.
+
+Read a JSON file back with read_json, which returns the dictionary
+that was written, and trade indices with read_trade_indices.
+
+Every JSON file records the schema_version of its layout.  read_json
+upgrades files written by earlier versions to the current layout,
+one version at a time, so runs archived long ago still load.  When
+the layout changes, increase SCHEMA_VERSION and add to MIGRATIONS a
+function that upgrades a dictionary from the version before.
+
+License:  MIT
+"""
+
+import csv
+import datetime
+import gzip
+import json
+import math
+
+import numpy as np
+
+from risk_normalization import InvalidParameterError
+from risk_normalization import SummaryStats
+from risk_normalization import compute_statistics
+
+
+#  1 is every file written before schema_version was recorded
+SCHEMA_VERSION = 6
+
+
+def to_plain(
+    value   ):
+
+    """
+    Convert a value of a result or config to one that JSON can
+    represent: numbers, strings, lists, dictionaries, and None.
+    numpy values become numbers and lists, times become ISO 8601
+    strings, infinite numbers become strings, and any other object
+    becomes its repr.
+    """
+
+    if value is None or isinstance(value, (bool, str)):
+        return value
+    if isinstance(value, (int, np.integer)):
+        return int(value)
+    if isinstance(value, (float, np.floating)):
+        if math.isfinite(value):
+            return float(value)
+        return str(float(value))
+    if isinstance(value, datetime.datetime):
+        return value.isoformat()
+    if isinstance(value, dict):
+        return {str(key): to_plain(item) for key, item in value.items()}
+    if isinstance(value, (list, tuple, np.ndarray)):
+        return [to_plain(item) for item in value]
+
+    return repr(value)
+
+
+class ResultWriter:
+
+    """
+    Writes RiskNormalizationResult to JSON and csv files.
+
+    Parameters:
+    config:     the RiskNormalizationConfig the result was run with,
+                recorded in the JSON file.  Default = None, not
+                recorded.
+    """
+
+    def __init__(self, config = None):
+        self.config = config
+
+    def to_dict(self, result):
+
+        """
+        Returns:
+        dictionary holding everything write_json writes.
+        """
+
+        if self.config is not None:
+            config = {name: to_plain(value)
+                      for name, value in vars(self.config).items()}
+            seed = self.config.seed
+        else:
+            config = None
+            seed = None
+        if result.simulation_seed is not None:
+            seed = result.simulation_seed.seed
+            simulation_seed = to_plain(vars(result.simulation_seed))
+        else:
+            simulation_seed = None
+        if result.strategy is not None:
+            strategy = to_plain(vars(result.strategy))
+        else:
+            strategy = None
+        if result.streaks is not None:
+            streaks = {name: to_plain(vars(value))
+                             if isinstance(value, SummaryStats)
+                             else to_plain(value)
+                       for name, value in vars(result.streaks).items()}
+        else:
+            streaks = None
+        if result.benchmark_metrics is not None:
+            benchmark_metrics = {name: to_plain(vars(value))
+                                 for name, value
+                                 in vars(result.benchmark_metrics).items()}
+        else:
+            benchmark_metrics = None
+
+        return {
+            'schema_version': SCHEMA_VERSION,
+            'strategy': strategy,
+            'tags': to_plain(result.tags),
+            'scenario': result.scenario,
+            'trade_transforms': result.trade_transforms,
+            'seed': seed,
+            'simulation_seed': simulation_seed,
+            'trades_sha256': result.trades_sha256,
+            'compute_usage': to_plain(vars(result.compute_usage))
+                             if result.compute_usage is not None else None,
+            'started_at': to_plain(result.started_at),
+            'finished_at': to_plain(result.finished_at),
+            'written_at': to_plain(
+                              datetime.datetime.now(datetime.timezone.utc)),
+            'safe_f_mean': to_plain(result.safe_f_mean),
+            'safe_f_stdev': to_plain(result.safe_f_stdev),
+            'CAR25_mean': to_plain(result.CAR25_mean),
+            'CAR25_stdev': to_plain(result.CAR25_stdev),
+            'safe_f_interval': to_plain(vars(result.safe_f_interval))
+                               if result.safe_f_interval is not None
+                               else None,
+            'CAR25_interval': to_plain(vars(result.CAR25_interval))
+                              if result.CAR25_interval is not None
+                              else None,
+            'safe_f_stats': to_plain(vars(result.safe_f_stats))
+                            if result.safe_f_stats is not None else None,
+            'TWR25_stats': to_plain(vars(result.TWR25_stats))
+                           if result.TWR25_stats is not None else None,
+            'CAR25_stats': to_plain(vars(result.CAR25_stats))
+                           if result.CAR25_stats is not None else None,
+            'safe_f_capped': result.safe_f_capped,
+            'safe_sizing': to_plain(result.safe_sizing),
+            'CAR_percentiles': to_plain(result.CAR_percentiles),
+            'probability_of_ruin': to_plain(result.probability_of_ruin),
+            'safe_f_list': to_plain(result.safe_f_list),
+            'TWR25_list': to_plain(result.TWR25_list),
+            'CAR25_list': to_plain(result.CAR25_list),
+            'drawdown_profiles': [to_plain(vars(profile))
+                                  for profile in result.drawdown_profiles],
+            'value_at_risk': [to_plain(vars(value))
+                              for value in result.value_at_risk],
+            'goal_probabilities': [to_plain(vars(goal))
+                                   for goal in result.goal_probabilities],
+            'ratio_percentiles': to_plain(result.ratio_percentiles),
+            'CAR_distributions': to_plain(result.CAR_distributions),
+            'drawdown_distributions': to_plain(result.drawdown_distributions),
+            'CAR_histograms': [to_plain(vars(histogram))
+                               for histogram in result.CAR_histograms],
+            'drawdown_histograms': [to_plain(vars(histogram))
+                                    for histogram
+                                    in result.drawdown_histograms],
+            'equity_bands': [to_plain(vars(bands))
+                             for bands in result.equity_bands],
+            'streaks': streaks,
+            'benchmark_metrics': benchmark_metrics,
+            'warnings': [{'code': warning.code, 'message': warning.message}
+                         for warning in result.warnings],
+            'config': config}
+
+    def write_json(self, result, path):
+
+        """
+        Write the result, and the config, to a JSON file.
+        """
+
+        with open(path, 'w') as f:
+            json.dump(self.to_dict(result), f, indent = 1)
+
+    def write_csv(self, result, path):
+
+        """
+        Write one row per repetition, with columns repetition,
+        counting from 0, safe_f, TWR25, CAR25, and the mean, median,
+        and 95th percentile of maximum drawdown at safe-f.
+        """
+
+        with open(path, 'w', newline = '') as f:
+            writer = csv.writer(f)
+            writer.writerow(['repetition', 'safe_f', 'TWR25', 'CAR25',
+                             'drawdown_mean', 'drawdown_median',
+                             'drawdown_95'])
+            for repetition, (safe_f, TWR25, CAR25, profile) in enumerate(
+                    zip(result.safe_f_list, result.TWR25_list,
+                        result.CAR25_list, result.drawdown_profiles)):
+                writer.writerow([repetition, safe_f, TWR25, CAR25,
+                                 profile.mean, profile.median,
+                                 profile.percentile_95])
+
+    def write_distributions_csv(self, result, path):
+
+        """
+        Write one row per equity curve at safe-f, with columns
+        repetition, counting from 0, CAR, in percent, and
+        max_drawdown, as a proportion.  Within each repetition both
+        columns are sorted, so a row's CAR and drawdown need not
+        come from the same equity curve.
+        """
+
+        if result.CAR_distributions is None:
+            raise InvalidParameterError('result.CAR_distributions', None,
+                      'run with keep_CAR_distributions = True to export them')
+
+        with open(path, 'w', newline = '') as f:
+            writer = csv.writer(f)
+            writer.writerow(['repetition', 'CAR', 'max_drawdown'])
+            for repetition, (CARs, drawdowns) in enumerate(
+                    zip(result.CAR_distributions,
+                        result.drawdown_distributions)):
+                for CAR, drawdown in zip(CARs, drawdowns):
+                    writer.writerow([repetition, float(CAR),
+                                     float(drawdown)])
+
+    def write_trade_indices(self, result, path):
+
+        """
+        Write one row per equity curve at safe-f of the final
+        repetition, gzip compressed, with columns sequence,
+        counting from 0, trade_indices, the indices of its trades
+        separated by spaces, and size_multipliers, the same for its
+        execution noise, or empty.  With the trades and the result,
+        replay_equity_sequence rebuilds each curve.
+        """
+
+        if result.trade_indices is None:
+            raise InvalidParameterError('result.trade_indices', None,
+                      'run with keep_trade_indices = True to export them')
+
+        with gzip.open(path, 'wt', newline = '') as f:
+            writer = csv.writer(f)
+            writer.writerow(['sequence', 'trade_indices',
+                             'size_multipliers'])
+            for sequence, trade_indices in enumerate(result.trade_indices):
+                if result.size_multipliers is not None:
+                    size_multipliers = ' '.join(
+                        repr(float(multiplier)) for multiplier
+                        in result.size_multipliers[sequence])
+                else:
+                    size_multipliers = ''
+                writer.writerow([sequence,
+                                 ' '.join(str(int(index))
+                                          for index in trade_indices),
+                                 size_multipliers])
+
+
+def upgrade_from_version_1(
+    dictionary  ):
+
+    """
+    Files of version 1 were written before the seed's random number
+    generator, the digest of the trades, the trade transforms, the
+    histograms, the computing used, the confidence intervals, and
+    the summary statistics were recorded.  Give them the values of
+    a run that recorded none of them, except the summary statistics,
+    which are computed from the lists of each repetition.
+    """
+
+    for key in ('simulation_seed', 'trades_sha256', 'compute_usage',
+                'safe_f_interval', 'CAR25_interval'):
+        dictionary.setdefault(key, None)
+    for key in ('trade_transforms', 'CAR_histograms',
+                'drawdown_histograms'):
+        dictionary.setdefault(key, [])
+    for name in ('safe_f', 'TWR25', 'CAR25'):
+        values = dictionary.get(f'{name}_list')
+        if values:
+            dictionary.setdefault(f'{name}_stats',
+                                  vars(compute_statistics(values)))
+        else:
+            dictionary.setdefault(f'{name}_stats', None)
+    dictionary['schema_version'] = 2
+
+    return dictionary
+
+
+def upgrade_from_version_2(
+    dictionary  ):
+
+    """
+    Files of version 2 were written before the percentile bands of
+    equity were recorded.
+    """
+
+    dictionary.setdefault('equity_bands', [])
+    dictionary['schema_version'] = 3
+
+    return dictionary
+
+
+def upgrade_from_version_3(
+    dictionary  ):
+
+    """
+    Files of version 3 were written before losing streaks were
+    recorded.
+    """
+
+    dictionary.setdefault('streaks', None)
+    dictionary['schema_version'] = 4
+
+    return dictionary
+
+
+def upgrade_from_version_4(
+    dictionary  ):
+
+    """
+    Files of version 4 were written before equity curves could be
+    compared with a benchmark.
+    """
+
+    dictionary.setdefault('benchmark_metrics', None)
+    dictionary['schema_version'] = 5
+
+    return dictionary
+
+
+def upgrade_from_version_5(
+    dictionary  ):
+
+    """
+    Files of version 5 were written before safe-f could be held at
+    a limit on the fraction.
+    """
+
+    dictionary.setdefault('safe_f_capped', False)
+    dictionary['schema_version'] = 6
+
+    return dictionary
+
+
+#  from each schema_version to the function that upgrades it to the next
+MIGRATIONS = {1: upgrade_from_version_1,
+              2: upgrade_from_version_2,
+              3: upgrade_from_version_3,
+              4: upgrade_from_version_4,
+              5: upgrade_from_version_5}
+
+
+def migrate(
+    dictionary  ):
+
+    """
+    Upgrade the dictionary of a JSON file of any earlier
+    schema_version to SCHEMA_VERSION.
+
+    Returns:
+    the upgraded dictionary.
+
+    Raises InvalidParameterError if the file was written by a newer
+    version of this program.
+    """
+
+    version = dictionary.get('schema_version', 1)
+    if not (isinstance(version, int) and not isinstance(version, bool) and
+            1 <= version <= SCHEMA_VERSION):
+        raise InvalidParameterError('schema_version', version,
+                                    f'must be a whole number from 1 to '
+                                    f'{SCHEMA_VERSION}; the file may be '
+                                    f'from a newer version')
+    while version < SCHEMA_VERSION:
+        dictionary = MIGRATIONS[version](dictionary)
+        version = dictionary['schema_version']
+
+    return dictionary
+
+
+def read_json(
+    path    ):
+
+    """
+    Read a file written by ResultWriter.write_json, of this or any
+    earlier schema_version.
+
+    Returns:
+    the dictionary that was written, upgraded by migrate to
+    SCHEMA_VERSION.
+    """
+
+    with open(path) as f:
+        return migrate(json.load(f))
+
+
+def read_trade_indices(
+    path    ):
+
+    """
+    Read a file written by ResultWriter.write_trade_indices.
+    
+    Returns:
+    trade_indices:     numpy array with a row of trade indices for
+                       each equity curve.
+    size_multipliers:  numpy array of the same shape, or None if
+                       the run had no execution noise.
+    """
+
+    trade_indices = []
+    size_multipliers = []
+    with gzip.open(path, 'rt', newline = '') as f:
+        for row in csv.DictReader(f):
+            trade_indices.append([int(index)
+                                  for index in row['trade_indices'].split()])
+            size_multipliers.append([float(multiplier) for multiplier
+                                     in row['size_multipliers'].split()])
+
+    if all(len(multipliers) == 0 for multipliers in size_multipliers):
+        return (np.array(trade_indices), None)
+
+    return (np.array(trade_indices), np.array(size_multipliers))
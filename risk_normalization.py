@@ -13,15 +13,33 @@
 #    pip3 install risk-normalization
 
 
+import argparse
+import asyncio
+import collections
+import collections.abc
+import concurrent.futures
+import csv
+import datetime
+import gzip
+import hashlib
+import io
+import json
 import math
 import matplotlib as plt
+import os
 import numpy as np
 import pandas as pd
+import pickle
+import platform
 import random
 #import risk_normalization
 import sklearn as skl
 import statistics
+import sys
+import threading
 import statsmodels as st
+import time
+import warnings
 
 #  These do not have a __version__ method
 #print (f'math version:               {math.__version__}')
@@ -124,7 +142,9 @@ Assumptions
     close.
 A gain of 1% is represented as 0.0100
 A day where the position is flat has a gain of 0.0000
-There are about 252 trades per year
+There are about 252 trades per year for stocks and futures,
+  365 for crypto, and 260 for forex.  Set trading_days_per_year
+  to match.
 The account is marked to market daily.
 The account is managed daily.
 The trader is able and willing to change position daily.
@@ -140,6 +160,15 @@ Use:
                     number_equity_in_CDF,
                     number_repetitions
                   )
+or, with the parameters given by name:
+  config = RiskNormalizationConfig(
+               number_days_in_forecast = 504,
+               number_trades_in_forecast = 84,
+               drawdown_tolerance = 0.10)
+  safe-f, CAR25 = risk_normalization_with_config(trades, config)
+Parameters not given to RiskNormalizationConfig take the typical
+values below, except number_trades_in_forecast which defaults to
+number_days_in_forecast.
 
 Parameters:
   trades:  The set of trades to evaluate.
@@ -147,6 +176,7 @@ Parameters:
   number_days_in_forecast:  the forecast period.
       Typical = 504 for a 2 year forecast
       Typical = 252 for a 1 year forecast
+      These are trading days.  See trading_days_per_year.
   number_trades_in_forecast:
       The number of trades to draw for each equity sequence.
       If each trade represents one daya of market-to-market result:
@@ -169,16 +199,319 @@ Parameters:
       to compute a single CDF.  
       Typical = 1000
   number_repetitions:  The number of replications of calculation of 
-      safe-f and CAR25 to compute the mean and standard deviation.  
+      safe-f and CAR25 to compute the mean and standard deviation.
       Typical = 10
+  drawdown_limit_multiple:  Optional.  While searching for safe-f,
+      stop each equity sequence once its drawdown exceeds this
+      multiple of drawdown_tolerance.  safe-f is still a Monte
+      Carlo estimate within desired_accuracy of the tolerance, but
+      passes far from it may steer the search differently, so the
+      value found may differ slightly from that of None.
+      None draws every trade.
+      Default = 2.0
+  max_fraction:  Optional.  The largest fraction the search for
+      safe-f considers.  A fraction is the value of the positions
+      as a proportion of the account, so 2.0 is leverage of two to
+      one.  If the drawdown tolerance would allow more, safe-f is
+      held at the limit and the result's safe_f_capped is True.
+      Default = None, MAX_FRACTION, 10.0
+  initial_margin:  Optional.  The broker's initial margin, as a
+      proportion of the value of a position, such as 0.5 for stocks
+      bought on margin.  The account can carry positions of at most
+      1 / initial_margin, so that is the largest fraction considered.
+      With max_fraction also given, the smaller limit applies.
+      Default = None, no limit
+  sample_size_schedule:  Optional.  List of (distance, number_equity)
+      pairs, largest distance first.  While the tail risk is farther
+      than distance from drawdown_tolerance, the search for safe-f
+      uses only number_equity curves.  safe-f is always confirmed
+      with number_equity_in_CDF curves.
+      Example = [(0.05, 100), (0.01, 500)]
+      Default = None, every pass uses number_equity_in_CDF
+  number_workers:  Optional.  The number of worker processes.  Each
+      pass of the search for safe-f evaluates this many candidate
+      fractions at the same time.  The calling program must protect
+      its main code with  if __name__ == '__main__':
+      No more processes are started than there are processors
+      available, and none on a machine with 2 or fewer, where the
+      candidates are evaluated here, one after another.  The
+      results are the same either way.
+      Default = None, a single fraction per pass in this process
+  number_candidates:  Optional.  The number of candidate fractions
+      each pass of the search for safe-f evaluates.  Every candidate
+      has its own seed, derived from the repetition, the pass, and
+      its place in the pass, so with number_candidates fixed the
+      results are identical for any number_workers.
+      Default = None, number_workers
+  cancel_event:  Optional.  A threading.Event, or any object with an
+      is_set method.  Once it is set, the run stops at the next pass
+      of the search for safe-f and raises RunCancelledError, which
+      carries the results of the repetitions already completed.
+      Repetitions sent to another process by the executor are not
+      stopped, but those not yet started are cancelled.
+      Default = None, the run cannot be cancelled
+  scenario:  Optional.  A Scenario applied to the trades before the
+      run, such as one from load_scenarios.  The result's scenario
+      is its name.  Default = None, the trades as given
+  trade_transforms:  Optional.  A list of TradeTransform applied to
+      the trades, in order, after they are read and before the
+      scenario, such as HaircutTransform(haircut = 0.0005), or a
+      FunctionTransform of the trader's own.  The result's
+      trade_transforms records them.  Default = (), none
+  cash_flows:  Optional.  A CashFlowSchedule of deposits or
+      withdrawals applied inside every equity sequence.  safe-f
+      then limits the drawdown under that spending plan, and the
+      result's probability_of_ruin is the proportion of equity
+      curves at safe-f that run out of money.
+      Default = None, no cash flows
+  wealth_targets:  Optional.  Account values, in dollars, whose
+      probability of being reached at safe-f is reported in the
+      result's goal_probabilities.  Compare runs with and without
+      cash_flows to see the effect of contributions.
+      Default = (), none
+  equity_curve_percentiles:  Optional.  Percentiles of terminal
+      equity, such as (5, 25, 50, 75, 95), whose equity curves at
+      safe-f the result carries in equity_curves, for fan charts.
+      Default = (), none
+  equity_curve_points:  Optional.  The number of points to which
+      each of those curves is thinned.  Default = 100
+  equity_band_percentiles:  Optional.  Percentiles, such as
+      (5, 25, 50, 75, 95), of equity at each point of the forecast,
+      across the equity curves at safe-f, that the result carries
+      in equity_bands, for fan charts of percentile bands.  They are
+      estimated as the curves are formed, without keeping them, so
+      memory does not grow with number_equity_in_CDF.  Each curve
+      is thinned to equity_curve_points.
+      Default = (), none
+  VaR_confidence_levels:  Optional.  The confidence levels at which
+      the Value-at-Risk and Conditional Value-at-Risk of terminal
+      wealth at safe-f are reported in the result's value_at_risk.
+      Default = (0.95, 0.99)
+  language:  Optional.  The language of the messages of warnings and
+      of the sample size advisory: 'en' English, 'es' Spanish, or
+      'zh' Chinese.  Warning codes are the same in every language.
+      Default = 'en'
+  random_algorithm:  Optional.  The random number generator, one of
+      RANDOM_ALGORITHMS: 'MT19937', numpy's RandomState generator,
+      whose sequence for a seed numpy keeps the same from release to
+      release, or the numpy bit generators 'PCG64', 'Philox', or
+      'SFC64', which need numpy 1.17 or later.  Each algorithm gives
+      different, equally valid, results for the same seed.
+      Default = 'MT19937'
+  backend:  Optional.  How the equity sequences are computed, one of
+      SIMULATION_BACKENDS: 'loop', one trade after another; 'array',
+      all the sequences of a distribution at once as numpy arrays;
+      or 'gpu', the same on the graphics processor with CuPy, or with
+      numpy, and a warning, if CuPy or a GPU is not available.  The
+      trades drawn are the same, and the results agree with 'loop' to
+      rounding.  Sequences with cash flows, costs, position sizing
+      other than a fixed fraction, or path ratios are always computed
+      one by one.
+      Default = 'loop'
+  variance_reduction:  Optional.  A list of the techniques, from
+      VARIANCE_REDUCTIONS, that make the Monte Carlo estimates less
+      noisy for the same number of equity curves.
+      'antithetic' pairs each equity sequence with its mirror image,
+      drawing the trade of opposite rank wherever the first draws a
+      trade, as AntitheticResampling does.  Needs IIDResampling,
+      and a scenario without regimes.
+      'common_random_numbers' evaluates every candidate fraction of
+      the search with the same draws, so that tail risk rises
+      smoothly with the fraction and the search does not wobble.
+      Default = (), plain Monte Carlo
+  confidence_level:  Optional.  The coverage of the confidence
+      intervals of safe-f and CAR25 in the result's safe_f_interval
+      and CAR25_interval.  Default = 0.95
+  confidence_method:  Optional.  How the intervals are found, one of
+      CONFIDENCE_METHODS.  'repetitions' bootstraps the mean of the
+      repetitions, and shows how precisely the simulation has
+      estimated safe-f and CAR25 for these trades.  'nested' takes
+      the percentiles of safe-f and CAR25 over the resampled sets of
+      trades of sample_size_bootstraps, which must be 2 or more, and
+      shows how much they depend on which trades happened to be
+      observed.  Default = 'repetitions'
+  confidence_bootstraps:  Optional.  The number of times the
+      repetitions are resampled for 'repetitions'.  Default = 1000
+  cost_model:  Optional.  A CostModel of commissions, slippage, and
+      fees subtracted from every trade in the equity sequences, so
+      that safe-f and CAR25 reflect net performance.
+      Default = None, the trades are net already
+  trailing_drawdown_dollars:  Optional.  Search for the fraction
+      whose tail risk of a drawdown of this many dollars from the
+      highest equity to date equals tail_percentile, the trailing
+      drawdown rule of funded-account programs, in place of
+      drawdown_tolerance.  The tail risks of the search, in
+      tail_risk_curve and search traces, are then dollar drawdowns
+      as a proportion of initial_capital.
+      Default = None, use drawdown_tolerance
+  position_sizing:  Optional.  A PositionSizing that sets the size
+      of each position from the fraction: FixedFractionSizing,
+      FixedDollarSizing, FixedContractsSizing(contract_value), or
+      VolatilityScaledSizing(target_volatility).  safe-f is then the
+      fraction of that sizing, and the result's safe_sizing is the
+      safe value of its parameter, such as the number of contracts.
+      Default = None, FixedFractionSizing
+  execution_noise:  Optional.  An ExecutionNoise that perturbs the
+      size of each simulated position, for partial fills and fills
+      that do not exactly match the fraction.
+      Default = None, every position is exactly as sized
+  keep_search_trace:  Optional.  If True, the result carries, for
+      each repetition, a list of SearchPass describing every pass
+      of the search for safe-f, to check how it converged.
+      Default = False
+  keep_CAR_distributions:  Optional.  If True, the result carries the
+      compound annual rate of return, and the maximum drawdown, of
+      every equity curve used to compute CAR25, for each repetition.
+      Default = False
+  keep_histograms:  Optional.  If True, the result carries a
+      Histogram of the CAR, in percent, and of the maximum drawdown,
+      as a proportion, of the equity curves at safe-f, for each
+      repetition, for charts that need not carry every value.
+      Default = False
+  histogram_bins:  Optional.  The number of bins of those
+      histograms.  Default = None, chosen for each by the
+      Freedman-Diaconis rule
+  keep_trade_indices:  Optional.  If True, the result carries the
+      indices of the trades drawn for every equity curve at safe-f
+      of the final repetition, and the size multipliers of the
+      execution_noise, if any, so that an auditor can rebuild any
+      of those curves from the trades with replay_equity_sequence.
+      ResultWriter.write_trade_indices writes them compressed.
+      Default = False
+  seed:  Optional.  Seed for the random number generator, so that
+      a run can be repeated exactly.
+      Default = None, a different sequence every run.  The seed
+      drawn for the run is recorded in the result's simulation_seed,
+      and result.reproduce(trades) runs it again.
+  progress_sink:  Optional.  A ProgressSink that is told as each
+      repetition completes, and as each pass of the search for
+      safe-f completes when the repetitions run in this process.
+      Use PrintProgress to print CAR25 for each repetition.
+      Default = None, no reports
+  minimum_number_trades:  Optional.  The fewest trades that will be
+      analyzed.  Resampling fewer trades than this gives results that
+      look precise but are mostly noise, so TooFewTradesError is
+      raised instead.  Set to 1 to analyze any number of trades.
+      Default = 20
+  sample_size_bootstraps:  Optional.  The number of times to resample
+      the trades themselves, to see whether there are enough of them
+      for stable results.  Each costs about one repetition.  The
+      result carries a SampleSizeAdvisory, and a warning is issued
+      if the results are not stable.  20 is a useful number.
+      Default = 0, no check
+  resampling_method:  Optional.  How trades are drawn to form each
+      equity sequence.  IIDResampling draws each trade independently.
+      MovingBlockResampling(block_length),
+      CircularBlockResampling(block_length), and
+      StationaryBootstrapResampling(mean_block_length) draw blocks of
+      consecutive trades, preserving streaks of wins and losses.
+      The block methods expect the trades in the order they occurred.
+      WeightedResampling(weights) draws each trade with its own
+      probability; blend_trade_samples makes one that weights
+      out-of-sample trades above in-sample trades.
+      Default = None, IIDResampling
+  CAR_percentiles:  Optional.  The percentiles of terminal equity at
+      which to compute the compound annual rate of return, in
+      addition to the 25th.  The result carries CAR5, CAR50, and so
+      on in CAR_percentiles.
+      Default = (5, 25, 50, 75, 95)
+  executor:  Optional.  A concurrent.futures.Executor that runs the
+      repetitions, such as ProcessPoolExecutor to run them in
+      parallel, or an executor that sends them to other machines.
+      Results do not depend on the executor.
+      Default = None, SequentialExecutor, one after another here
+  percentile_method:  Optional.  How percentiles of the drawdowns
+      and of terminal equity are estimated from the equity curves.
+      'linear' interpolates between the two nearest values, as numpy
+      and Excel PERCENTILE.INC do.  'nearest_rank' takes the value at
+      rank ceil(p*n), without interpolation.  'hazen' interpolates
+      with the ranks at the middle of each step of the CDF.
+      'weibull' interpolates with ranks of p*(n+1), as Excel
+      PERCENTILE.EXC does.  The choice matters most when
+      number_equity_in_CDF is small.
+      Default = 'linear'
+  max_memory_bytes:  Optional.  Refuse runs whose estimated memory
+      use, from estimate_memory_bytes, is larger.  If the run fits
+      without keeping the CAR distributions, they are dropped with
+      a warning instead.
+      Default = None, no limit
+  max_paths:  Optional.  Refuse runs that would form more than this
+      many equity curves at safe-f, number_equity_in_CDF times
+      number_repetitions.
+      Default = None, no limit
+  compute_ratios:  Optional.  If True, the result carries the
+      annualized Sharpe, Sortino, and Calmar ratios, the Ulcer Index,
+      and the Martin ratio of the equity curves at safe-f, at each of
+      the CAR_percentiles, in ratio_percentiles.
+      Default = False
+  compute_streaks:  Optional.  If True, the result carries in
+      streaks the distributions, over the equity curves at safe-f,
+      of the longest losing streak, in trades and in days, and of
+      the days taken to recover from the maximum drawdown, to set
+      expectations of how trading at safe-f will feel.
+      Default = False
+  benchmark:  Optional.  The returns of a benchmark, such as the
+      daily returns of SPY, as proportions, one for each trade and
+      over the same period.  Each equity curve at safe-f draws the
+      benchmark's return along with each trade, and the result
+      carries in benchmark_metrics the distributions of annualized
+      alpha, beta, and maximum drawdown relative to the benchmark.
+      Default = None, none
+  strategy:  Optional.  A StrategyInfo describing the trading system
+      that produced the trades.  It is carried in the result.
+      Default = None
+  tags:  Optional.  Dictionary of text keys and values describing
+      the run, such as {'variant': 'bollinger_v3',
+      'data': '2010-2024'}.  It is carried in the result.  Use
+      select_results to find the results of a parameter sweep or a
+      family of strategies by their tags.
+      Default = None, no tags
+  trading_days_per_year:  Optional.  The number of trading days in a
+      year, used to annualize the rate of return and the ratios.
+      TRADING_DAYS_PER_YEAR has the usual values: 'futures' 252,
+      'crypto' 365, 'forex' 260.  Any other whole number may be used.
+      Default = 252
+  drawdown_levels:  Optional.  The drawdowns, as proportions, whose
+      probability of being exceeded at safe-f is reported in each
+      repetition's DrawdownProfile.
+      Default = (0.05, 0.10, 0.20)
+  desired_accuracy:  Optional.  The search for safe-f ends when the
+      tail risk is within this distance of drawdown_tolerance.
+      Default = 0.003
+  max_iterations:  Optional.  The most passes the search for safe-f
+      may make before raising SearchDidNotConvergeError.
+      Default = 1000
+
+Errors:
+  All errors raised are subclasses of RiskNormalizationError:
+  EmptyTradesError, TooFewTradesError, CsvParseError,
+  JsonParseError, ArrowParseError, SessionFileError,
+  InvalidParameterError,
+  ResourceLimitError,
+  RunCancelledError if the config's cancel_event is set, and
+  SearchDidNotConvergeError if safe-f is not found within
+  max_iterations passes.  Trades none of which is a loss are
+  not an error: safe-f is reported as MAX_FRACTION, or as the
+  config's fraction_limit, with a 'no_drawdown_risk' warning.  Nor are trades that are all losses:
+  safe-f is reported as 0.0, with an 'all_losing_trades' warning.
+  Other errors, such as ZeroDivisionError or IndexError, are
+  not raised for any trades or parameters.  An option name that is
+  not a parameter raises InvalidParameterError, not TypeError, so
+  a caller need only catch RiskNormalizationError.  Reading a file
+  that cannot be opened raises OSError.
 
 Returns:
+  A RiskNormalizationResult, which unpacks as:
   safe_f_mean:  The fraction of the trading account that will be
                 used for each trade.
   safe_f_stdev:  standard deviation of safe_f calculations.     
   CAR25_mean:  The compound annual rate of return for the given
                  set of trades and position size.
   CAR25_stdev:  standard deviation of CAR25 calculations.     
+  It also carries tail_risk_curve, the (fraction, tail_risk)
+  pairs evaluated while searching for safe-f, and the lists of
+  safe-f, TWR25, and CAR25 from each repetition, and warnings, a
+  list of any RiskNormalizationWarning issued during the run.
 
 
 definitions of variables
@@ -194,286 +527,9029 @@ fraction:                 during calculations, the then current estimate
 """
 
 
-def make_one_equity_sequence(
-    trades,
-    fraction,
-    number_days_in_forecast,
-    number_trades_in_forecast,
-    initial_capital         ):
+class RiskNormalizationError(Exception):
 
     """
-    Given a set of trades, draw a random sequence of trades
-    and form an equity sequence.
+    Base class of the errors raised by risk_normalization.
+    Catch this to handle all of them.
+    """
+
+
+class EmptyTradesError(RiskNormalizationError, ValueError):
+
+    """
+    There are no trades to analyze.
     
-    Parameters:
-    trades:           the set of trades to be analyzed
-    fraction:         the proportion of the trading account
-                      to be used for each trade.
-    number_days_in_forecast:    Length of forecast in days.                 
-    number_trades_in_forecast:  Length of forecast in trades.
-    initial_capital:  Starting value of the trading account.
+    Attributes:
+    source:  where the trades came from, such as the file name.
+    """
+
+    def __init__(self, source):
+        self.source = source
+        super().__init__(f'{source}: contains no trades')
+
+
+class TooFewTradesError(RiskNormalizationError, ValueError):
+
+    """
+    There are too few trades for resampling to say anything useful.
+    Resampling a handful of trades gives confident looking
+    numbers that are mostly noise.
     
-    Returns:  
-    Two scalars:
-    equity:  The equity at the end of the sequence in dollars.
-    max_drawdown:  The maximum drawdown experienced in the sequence
-            as a proportion of highest equity marked to market
-            after each trade.
+    Attributes:
+    number_trades:   the number of trades given.
+    minimum_number_trades:  the number required.
     """
 
-    #  initialize sequence
+    def __init__(self, number_trades, minimum_number_trades):
+        self.number_trades = number_trades
+        self.minimum_number_trades = minimum_number_trades
+        super().__init__(f'only {number_trades} trades, at least '
+                         f'{minimum_number_trades} are needed for a '
+                         f'meaningful safe-f and CAR25.  Set '
+                         f'minimum_number_trades to override.')
 
-    equity = initial_capital
-    max_equity = equity
-    drawdown = 0.0
-    max_drawdown = 0.0
 
-    #  form the equity curve to display, if desired
-    daily_equity = np.zeros(number_days_in_forecast)
+class CsvParseError(RiskNormalizationError, ValueError):
 
-    #  form sequence
+    """
+    A line of a trade file could not be read as a trade.
+    
+    Attributes:
+    source:  where the trades came from, such as the file name.
+    line:    line number, counting from 1.
+    column:  field number within the line, counting from 1.
+    text:    the text that could not be read.
+    reason:  why it could not be read.
+    """
 
-    for i in range(number_trades_in_forecast):
-        trade_index = random.randint(0, len(trades) - 1)
-        trade = trades[trade_index]
-        trade_dollars = equity * fraction * trade
-        equity = equity + trade_dollars
-        daily_equity[i] = equity
-        max_equity = max(equity, max_equity)
-        drawdown = (max_equity - equity) / max_equity
-        max_drawdown = max(drawdown, max_drawdown)
-    #  if necessary, fill remaining days    
-    for i in range(number_trades_in_forecast,number_days_in_forecast):
-        daily_equity[i] = equity
-   
-#    plt.plot(daily_equity)
-#    plt.show()
+    def __init__(self, source, line, column, text, reason):
+        self.source = source
+        self.line = line
+        self.column = column
+        self.text = text
+        self.reason = reason
+        super().__init__(f'{source} line {line} column {column}: '
+                         f'{reason}: {text[:40]!r}')
 
-    return (equity, max_drawdown)
 
+class JsonParseError(RiskNormalizationError, ValueError):
 
-def analyze_distribution_of_drawdown(
-    trades,
-    fraction,
-    number_days_in_forecast,
-    number_trades_in_forecast,
-    initial_capital,
-    tail_percentile,
-    number_equity_in_CDF   ):
+    """
+    A JSON trade file could not be read as a list of trades.
+    
+    Attributes:
+    source:    where the trades came from, such as the file name.
+    location:  where in the file, such as 'line 3 column 7'
+               or 'item 12'.
+    reason:    why it could not be read.
+    """
+
+    def __init__(self, source, location, reason):
+        self.source = source
+        self.location = location
+        self.reason = reason
+        super().__init__(f'{source} {location}: {reason}')
+
+
+class ArrowParseError(RiskNormalizationError, ValueError):
 
     """
-    Returns:
-    tail_risk:  The maximum drawdown at the tail_percentile
-                    of the distribution using the 
-                    current value of the position size.
+    A Parquet or Arrow trade file could not be read as a column of
+    trades.
+    
+    Attributes:
+    source:    where the trades came from, such as the file name.
+    reason:    why they could not be read.
     """
-    equity_list = []
-    max_dd_list = []
 
-    for i in range(number_equity_in_CDF):
-        equity, max_drawdown = make_one_equity_sequence(
-                                trades, 
-                                fraction, 
-                                number_days_in_forecast,
-                                number_trades_in_forecast,
-                                initial_capital)
-        equity_list.append(equity)
-        max_dd_list.append(max_drawdown)
+    def __init__(self, source, reason):
+        self.source = source
+        self.reason = reason
+        super().__init__(f'{source}: {reason}')
 
-    sorted_max_dd = np.sort(max_dd_list)
-#    plt.plot(sorted_max_dd)
-#    plt.show()
-    tail_risk = np.percentile(sorted_max_dd, 100 - tail_percentile)
 
-    return tail_risk
+class SessionFileError(RiskNormalizationError, ValueError):
 
+    """
+    A file could not be read as a session written by record_session.
+    
+    Attributes:
+    source:    the file name.
+    reason:    why it could not be read.
+    """
 
-def form_distribution_of_equity(
-    trades,
-    fraction,
-    number_days_in_forecast,
-    number_trades_in_forecast,
-    initial_capital,
-    number_equity_in_CDF       ):
+    def __init__(self, source, reason):
+        self.source = source
+        self.reason = reason
+        super().__init__(f'{source}: {reason}')
+
+
+class ScenarioParseError(RiskNormalizationError, ValueError):
+
+    """
+    A scenario file could not be read as a library of scenarios.
     
-#    plt.hist(trades,bins=50)
-#    plt.show()
-    equity_list = []
-    max_dd_list = []
+    Attributes:
+    source:    where the scenarios came from, such as the file name.
+    reason:    why they could not be read.
+    """
 
-    for i in range(number_equity_in_CDF):
-        equity, max_drawdown = make_one_equity_sequence(
-                                trades, 
-                                fraction, 
-                                number_days_in_forecast,
-                                number_trades_in_forecast,
-                                initial_capital)
-        equity_list.append(equity)
-        max_dd_list.append(max_drawdown)
+    def __init__(self, source, reason):
+        self.source = source
+        self.reason = reason
+        super().__init__(f'{source}: {reason}')
 
-    sorted_equity = np.sort(equity_list)
-#    plt.plot(sorted_equity)
-#    plt.show()
 
-    return sorted_equity
+class RunCancelledError(RiskNormalizationError):
 
+    """
+    The run was stopped because its cancel_event was set.
+    
+    Attributes:
+    repetitions:     list of RepetitionResult, one for each
+                     repetition completed before the run stopped.
+    partial_result:  None, or a RiskNormalizationResult computed
+                     from the completed repetitions alone.
+    """
 
-def risk_normalization(
-        trades, 
-        number_days_in_forecast, 
-        number_trades_in_forecast,
-        initial_capital, 
-        tail_percentile, 
-        drawdown_tolerance, 
-        number_equity_in_CDF,
-        number_repetitions
-        ):
+    def __init__(self, repetitions = None):
+        if repetitions is None:
+            repetitions = []
+        self.repetitions = repetitions
+        self.partial_result = None
+        super().__init__(f'the run was cancelled after '
+                         f'{len(repetitions)} repetitions')
 
-    safe_fs = []
-    TWR25s = []
-    CAR25s = []
+
+class ResourceLimitError(RiskNormalizationError):
+
+    """
+    A run would use more than the config's max_memory_bytes or
+    max_paths.
+    
+    Attributes:
+    name:       'max_memory_bytes' or 'max_paths'.
+    estimate:   what the run would use.
+    limit:      the limit.
+    """
+
+    def __init__(self, name, estimate, limit):
+        self.name = name
+        self.estimate = estimate
+        self.limit = limit
+        super().__init__(f'the run would need {estimate}, more than '
+                         f'{name} = {limit}')
+
+
+class SearchDidNotConvergeError(RiskNormalizationError):
+
+    """
+    The search for safe-f did not bring the tail risk within the
+    desired accuracy of the drawdown tolerance.
     
-    desired_accuracy = 0.003
+    Attributes:
+    iterations:      the number of passes made.
+    last_fraction:   the fraction evaluated on the last pass.
+    last_tail_risk:  its tail risk.
+    reason:          None, or why the search was stopped early.
+    """
+
+    def __init__(self, iterations, last_fraction, last_tail_risk,
+                 reason = None):
+        self.iterations = iterations
+        self.last_fraction = last_fraction
+        self.last_tail_risk = last_tail_risk
+        self.reason = reason
+        message = (f'safe-f search did not converge in '
+                   f'{iterations} passes: last fraction '
+                   f'{last_fraction:0.4f} has tail risk '
+                   f'{last_tail_risk:0.4f}')
+        if reason is not None:
+            message = message + f': {reason}'
+        super().__init__(message)
+
+
+class InvalidParameterError(RiskNormalizationError, ValueError):
+
+    """
+    A parameter of risk_normalization is not sensible.
     
-    for rep in range(number_repetitions):
+    Attributes:
+    name:    the name of the parameter.
+    value:   the value given.
+    reason:  what is required of it.
+    """
+
+    def __init__(self, name, value, reason):
+        self.name = name
+        self.value = value
+        self.reason = reason
+        super().__init__(f'{name} = {value!r}: {reason}')
+
+
+class RiskNormalizationWarning(UserWarning):
+
+    """
+    A problem that did not stop the run, but that the results
+    should be read in light of.  Each is issued with warnings.warn
+    and also carried in the result's warnings list.
     
-        #  Fraction is initially set to use all available funds
-        #  It will be adjusted in response to the risk of drawdown.
-        #  The final value of fraction is safe-f
-        
-        fraction = 1.0
-        done = False
-        while not done:
-            # print(f"fraction this pass:  {fraction:0.3f}")
-            tail_risk = analyze_distribution_of_drawdown(
-                            trades, 
-                            fraction,
-                            number_days_in_forecast,
-                            number_trades_in_forecast,
-                            initial_capital,
-                            tail_percentile,
-                            number_equity_in_CDF)
-        
-            # print(f"tail_risk this pass: {tail_risk:0.3f}")
-            if abs(tail_risk - drawdown_tolerance) < desired_accuracy:
-                done = True
-            else:
-                fraction = fraction * drawdown_tolerance / tail_risk
-        
-        #  print(f'final value: safe_f: {fraction:0.3f}')
-        
-        #  Compute CAR25
-        #  fraction == safe_f
-        #  Compute CDF of equity
-        #  TWR25 is 25th percentile
-        #  CAR25 is 25th percentile
+    Attributes:
+    code:     short name for the kind of problem, one of
+              'few_trades', 'ruined_equity_curves',
+              'CAR_distributions_dropped', 'unstable_sample_size',
+              'gpu_unavailable', 'no_drawdown_risk',
+              'all_losing_trades', 'safe_f_capped', or, from
+              preview_trades, 'no_losing_trades' and
+              'no_winning_trades'.
+    message:  description of the problem, in the language of the
+              config.
+    """
+
+    def __init__(self, code, message):
+        self.code = code
+        self.message = message
+        super().__init__(message)
+
+
+#  the sentences of warnings, advisories, and chart descriptions,
+#  by language and key, for str.format with the values named in
+#  braces
+MESSAGES = {
+    'en': {
+        'few_trades':
+            'each forecast draws {number_trades_in_forecast} trades from '
+            'only {number_trades}, so the same trades recur within every '
+            'equity curve',
+        'no_losing_trades':
+            'none of the {number_trades} trades is a loss, so no fraction '
+            'reaches the drawdown tolerance',
+        'no_winning_trades':
+            'none of the {number_trades} trades is a gain, so every '
+            'fraction loses money',
+        'no_drawdown_risk':
+            'none of the {number_trades} trades is a loss, so no equity '
+            'curve draws down at any fraction: safe-f is reported as '
+            '{max_fraction:g}, the largest fraction considered',
+        'safe_f_capped':
+            'safe-f of {number_capped} of {number_repetitions} '
+            'repetitions was held at {max_fraction:g}, the largest '
+            'fraction considered or that the account allows, short of '
+            'the drawdown tolerance',
+        'all_losing_trades':
+            'none of the {number_trades} trades is a gain, so every '
+            'position loses money: safe-f is 0 and CAR25 is that of not '
+            'trading',
+        'ruined_equity_curves':
+            '{number_ruined} of {number_equity_curves} equity curves at '
+            'safe-f lost the whole account',
+        'CAR_distributions_dropped':
+            'the run would need {memory_bytes} bytes, more than '
+            'max_memory_bytes = {max_memory_bytes}, so the CAR '
+            'distributions were not kept',
+        'gpu_unavailable':
+            'backend = gpu needs CuPy and a GPU, which were not found, '
+            'so the equity sequences were computed with numpy',
+        'stable_sample_size':
+            '{number_trades} trades are enough for stable results: CAR25 '
+            'from {CAR25_low:0.2f}% to {CAR25_high:0.2f}% under resampling '
+            'of the trades',
+        'unstable_sample_size':
+            '{number_trades} trades may be too few for stable results: '
+            'under resampling of the trades safe-f varies by '
+            '{variation:0.0f}% and CAR25 ranges from {CAR25_low:0.2f}% to '
+            '{CAR25_high:0.2f}%',
+        'sets_without_safe_f':
+            ', and {number_failed} of {number_bootstraps} resampled sets '
+            'had no safe-f',
+        'fan_chart':
+            'Fan chart of {number_curves} equity curves at safe-f over '
+            '{number_days} days, starting at ${starting_equity:,.0f}.  '
+            'Final equity by percentile: {endings}.  The lowest equity '
+            'charted is ${lowest:,.0f}, on the curve at percentile '
+            '{lowest_percentile:g}.',
+        'fan_chart_ending':
+            '{percentile:g}: ${equity:,.0f}',
+        'separator':
+            '; ',
+        'histogram':
+            'Histogram of {number_values} values of {quantity}, from '
+            '{low:0.2f}% to {high:0.2f}%.  Median {median:0.2f}%, mean '
+            '{mean:0.2f}%.  5 percent of the values are below '
+            '{tail_low:0.2f}% and 5 percent above {tail_high:0.2f}%.',
+        'CAR_quantity':
+            'compound annual return at safe-f',
+        'drawdown_quantity':
+            'maximum drawdown at safe-f'},
+    'es': {
+        'few_trades':
+            'cada pronóstico extrae {number_trades_in_forecast} operaciones '
+            'de solo {number_trades}, por lo que las mismas operaciones se '
+            'repiten en cada curva de capital',
+        'no_losing_trades':
+            'ninguna de las {number_trades} operaciones es una pérdida, por '
+            'lo que ninguna fracción alcanza la tolerancia de reducción',
+        'no_winning_trades':
+            'ninguna de las {number_trades} operaciones es una ganancia, '
+            'por lo que toda fracción pierde dinero',
+        'no_drawdown_risk':
+            'ninguna de las {number_trades} operaciones es una pérdida, '
+            'por lo que ninguna curva de capital muestra reducción con '
+            'ninguna fracción: safe-f se reporta como {max_fraction:g}, '
+            'la mayor fracción considerada',
+        'safe_f_capped':
+            'safe-f de {number_capped} de {number_repetitions} '
+            'repeticiones se limitó a {max_fraction:g}, la mayor fracción '
+            'considerada o que permite la cuenta, sin llegar a la '
+            'tolerancia de reducción',
+        'all_losing_trades':
+            'ninguna de las {number_trades} operaciones es una ganancia, '
+            'por lo que toda posición pierde dinero: safe-f es 0 y CAR25 '
+            'es el de no operar',
+        'ruined_equity_curves':
+            '{number_ruined} de {number_equity_curves} curvas de capital con '
+            'safe-f perdieron toda la cuenta',
+        'CAR_distributions_dropped':
+            'la ejecución necesitaría {memory_bytes} bytes, más que '
+            'max_memory_bytes = {max_memory_bytes}, por lo que no se '
+            'conservaron las distribuciones de CAR',
+        'gpu_unavailable':
+            'backend = gpu necesita CuPy y una GPU, y no se '
+            'encontraron, por lo que las secuencias de capital se '
+            'calcularon con numpy',
+        'stable_sample_size':
+            '{number_trades} operaciones bastan para obtener resultados '
+            'estables: CAR25 de {CAR25_low:0.2f}% a {CAR25_high:0.2f}% al '
+            'remuestrear las operaciones',
+        'unstable_sample_size':
+            '{number_trades} operaciones pueden ser muy pocas para obtener '
+            'resultados estables: al remuestrear las operaciones, safe-f '
+            'varía un {variation:0.0f}% y CAR25 va de {CAR25_low:0.2f}% a '
+            '{CAR25_high:0.2f}%',
+        'sets_without_safe_f':
+            ', y {number_failed} de {number_bootstraps} conjuntos '
+            'remuestreados no tuvieron safe-f',
+        'fan_chart':
+            'Gráfico de abanico de {number_curves} curvas de capital con '
+            'safe-f durante {number_days} días, partiendo de '
+            '${starting_equity:,.0f}.  Capital final por percentil: '
+            '{endings}.  El capital más bajo del gráfico es '
+            '${lowest:,.0f}, en la curva del percentil '
+            '{lowest_percentile:g}.',
+        'fan_chart_ending':
+            '{percentile:g}: ${equity:,.0f}',
+        'separator':
+            '; ',
+        'histogram':
+            'Histograma de {number_values} valores de {quantity}, de '
+            '{low:0.2f}% a {high:0.2f}%.  Mediana {median:0.2f}%, media '
+            '{mean:0.2f}%.  El 5 por ciento de los valores está por debajo '
+            'de {tail_low:0.2f}% y el 5 por ciento por encima de '
+            '{tail_high:0.2f}%.',
+        'CAR_quantity':
+            'la tasa de rendimiento anual compuesta con safe-f',
+        'drawdown_quantity':
+            'la reducción máxima con safe-f'},
+    'zh': {
+        'few_trades':
+            '每次预测需抽取 {number_trades_in_forecast} 笔交易，但只有 '
+            '{number_trades} 笔，因此同样的交易会在每条权益曲线中重复出现',
+        'no_losing_trades':
+            '{number_trades} 笔交易中没有一笔亏损，因此任何仓位比例都达不到'
+            '回撤容忍度',
+        'no_winning_trades':
+            '{number_trades} 笔交易中没有一笔盈利，因此任何仓位比例都会亏损',
+        'no_drawdown_risk':
+            '{number_trades} 笔交易中没有一笔亏损，因此在任何仓位比例下'
+            '权益曲线都不会回撤：safe-f 报告为所考虑的最大比例 '
+            '{max_fraction:g}',
+        'safe_f_capped':
+            '{number_repetitions} 次重复中有 {number_capped} 次的 safe-f '
+            '被限制为所考虑或账户允许的最大比例 {max_fraction:g}，'
+            '未达到回撤容忍度',
+        'all_losing_trades':
+            '{number_trades} 笔交易中没有一笔盈利，因此任何仓位都会亏损：'
+            'safe-f 为 0，CAR25 为不交易时的数值',
+        'ruined_equity_curves':
+            '在 safe-f 下，{number_equity_curves} 条权益曲线中有 '
+            '{number_ruined} 条亏光了整个账户',
+        'CAR_distributions_dropped':
+            '本次运行需要 {memory_bytes} 字节，超过 max_memory_bytes = '
+            '{max_memory_bytes}，因此未保留 CAR 分布',
+        'gpu_unavailable':
+            'backend = gpu 需要 CuPy 和 GPU，但未找到，因此权益序列'
+            '改用 numpy 计算',
+        'stable_sample_size':
+            '{number_trades} 笔交易足以得到稳定的结果：对交易重新抽样时，'
+            'CAR25 介于 {CAR25_low:0.2f}% 与 {CAR25_high:0.2f}% 之间',
+        'unstable_sample_size':
+            '{number_trades} 笔交易可能太少，难以得到稳定的结果：对交易'
+            '重新抽样时，safe-f 的变动为 {variation:0.0f}%，CAR25 介于 '
+            '{CAR25_low:0.2f}% 与 {CAR25_high:0.2f}% 之间',
+        'sets_without_safe_f':
+            '，且 {number_bootstraps} 个重新抽样的交易集中有 '
+            '{number_failed} 个找不到 safe-f',
+        'fan_chart':
+            '扇形图：safe-f 下的 {number_curves} 条权益曲线，共 '
+            '{number_days} 天，起始权益 ${starting_equity:,.0f}。'
+            '各百分位的最终权益：{endings}。图中最低的权益为 '
+            '${lowest:,.0f}，出现在百分位 {lowest_percentile:g} 的曲线上。',
+        'fan_chart_ending':
+            '{percentile:g}：${equity:,.0f}',
+        'separator':
+            '；',
+        'histogram':
+            '直方图：{quantity}的 {number_values} 个数值，从 '
+            '{low:0.2f}% 到 {high:0.2f}%。中位数 {median:0.2f}%，平均值 '
+            '{mean:0.2f}%。5% 的数值低于 {tail_low:0.2f}%，5% 的数值高于 '
+            '{tail_high:0.2f}%。',
+        'CAR_quantity':
+            'safe-f 下的复合年收益率',
+        'drawdown_quantity':
+            'safe-f 下的最大回撤'}}
+
+
+def format_message(
+    key,
+    language = 'en',
+    **values    ):
+
+    """
+    The sentence for key in language, one of the keys of MESSAGES,
+    with the values filled in.
+    """
+
+    return MESSAGES[language][key].format(**values)
+
+
+def describe_fan_chart(
+    equity_curves,
+    language = 'en' ):
+
+    """
+    A few sentences describing a fan chart of equity curves, for
+    readers who cannot see the chart, such as users of screen
+    readers: the length of the forecast, the final equity of each
+    curve, and the lowest equity charted.
+    
+    Parameters:
+    equity_curves:  list of EquityCurve, such as one repetition of
+                    a result's equity_curves.
+    language:       one of the keys of MESSAGES.
+    """
+
+    endings = MESSAGES[language]['separator'].join(
+                  format_message('fan_chart_ending', language,
+                                 percentile = curve.percentile,
+                                 equity = curve.equity[-1])
+                  for curve in equity_curves)
+    lowest_curve = min(equity_curves, key = lambda curve: min(curve.equity))
+
+    return format_message('fan_chart', language,
+                          number_curves = len(equity_curves),
+                          number_days = int(equity_curves[0].days[-1]),
+                          starting_equity = equity_curves[0].equity[0],
+                          endings = endings,
+                          lowest = min(lowest_curve.equity),
+                          lowest_percentile = lowest_curve.percentile)
+
+
+def describe_histogram(
+    values,
+    quantity,
+    language = 'en' ):
+
+    """
+    A few sentences describing a histogram, for readers who cannot
+    see the chart: the range, the median and mean, and the tails.
+    
+    Parameters:
+    values:     the values charted, in percent.
+    quantity:   the key in MESSAGES of the name of the values, such
+                as 'CAR_quantity'.
+    language:   one of the keys of MESSAGES.
+    """
+
+    sorted_values = np.sort(values)
+
+    return format_message('histogram', language,
+                          number_values = len(sorted_values),
+                          quantity = MESSAGES[language][quantity],
+                          low = sorted_values[0],
+                          high = sorted_values[-1],
+                          median = compute_percentile(sorted_values, 50),
+                          mean = statistics.mean(sorted_values),
+                          tail_low = compute_percentile(sorted_values, 5),
+                          tail_high = compute_percentile(sorted_values, 95))
+
+
+class RiskNormalizationResult:
+
+    """
+    The results of risk_normalization.
+    
+    Unpacks as the four summary values, so that
+        safe_f_mean, safe_f_stdev, CAR25_mean, CAR25_stdev = \
+            risk_normalization(...)
+    continues to work.
+    
+    Attributes:
+    safe_f_mean, safe_f_stdev, CAR25_mean, CAR25_stdev:
+                  as described for risk_normalization.
+    tail_risk_curve:  list of (fraction, tail_risk) pairs, one for
+                  every fraction evaluated while searching for
+                  safe-f, in the order they were evaluated, for
+                  all repetitions.  Use it to see how steeply the
+                  tail risk changes with fraction near safe-f.
+    safe_f_list:  safe-f from each repetition.
+    TWR25_list:   terminal wealth at the 25th percentile
+                  from each repetition.
+    CAR25_list:   CAR25 from each repetition.
+    CAR_distributions:  None, unless the config asked to
+                  keep_CAR_distributions.  Then a list with one
+                  sorted numpy array per repetition, holding the
+                  compound annual rate of return of every equity
+                  curve used to compute that repetition's CAR25.
+    drawdown_distributions:  None, unless the config asked to
+                  keep_CAR_distributions.  Then a list with one
+                  sorted numpy array per repetition, holding the
+                  maximum drawdown of the same equity curves.
+    sample_size_advisory:  None, unless the config asked for
+                  sample_size_bootstraps.  Then a SampleSizeAdvisory
+                  describing how much safe-f and CAR25 vary when
+                  the trades themselves are resampled.
+    CAR_percentile_lists:  dictionary from each of the config's
+                  CAR_percentiles to a list with the compound annual
+                  rate of return at that percentile of terminal
+                  equity, from each repetition.
+    CAR_percentiles:  dictionary from each of the config's
+                  CAR_percentiles to the mean of its list.
+                  CAR_percentiles[25] is CAR25_mean.
+    warnings:     list of RiskNormalizationWarning, empty if the run
+                  had no problems.
+    ratio_percentiles:  None, unless the config asked to
+                  compute_ratios.  Then a dictionary from 'Sharpe',
+                  'Sortino', 'Calmar', 'Ulcer', and 'Martin' to a
+                  dictionary from each of the CAR_percentiles to the
+                  mean, over the repetitions, of that percentile of
+                  the ratio across the equity curves at safe-f.
+    strategy:     None, or the config's StrategyInfo.
+    tags:         the config's dictionary of tags.
+    drawdown_profiles:  list with the DrawdownProfile of the equity
+                  curves at safe-f from each repetition.
+    probability_of_ruin:  the proportion of equity curves at safe-f,
+                  over all repetitions, that lost the whole account.
+    search_traces:  None, unless the config asked to
+                  keep_search_trace.  Then a list with one list of
+                  SearchPass per repetition.
+    goal_probabilities:  list with a GoalProbability for each of the
+                  config's wealth_targets.
+    safe_sizing:  the value of the config's position_sizing
+                  parameter at safe_f_mean, such as dollars or
+                  contracts.  safe_f_mean for fixed fraction sizing.
+    scenario:     None, or the name of the config's Scenario.
+    trade_transforms:  list with the repr of each of the config's
+                  trade_transforms, in the order applied.
+    value_at_risk:  list with a ValueAtRisk for each of the config's
+                  VaR_confidence_levels.
+    equity_curves:  list with one list per repetition of the
+                  EquityCurve at each of the config's
+                  equity_curve_percentiles.
+    started_at, finished_at:  None, or the times, in UTC, at which
+                  risk_normalization_with_config started and
+                  finished the run.
+    CAR_histograms, drawdown_histograms:  lists with a Histogram
+                  of the CAR and of the maximum drawdown at safe-f
+                  for each repetition, if the config asked to
+                  keep_histograms, otherwise empty.
+    trade_indices:  None, unless the config asked to
+                  keep_trade_indices.  Then a numpy array of the
+                  indices of the trades drawn, with a row for each
+                  equity curve at safe-f of the final repetition.
+    size_multipliers:  None, or for a config with execution_noise,
+                  the size multipliers drawn for those curves, of
+                  the same shape.
+    simulation_seed:  None, or the SimulationSeed of the run.
+    trades_sha256:  None, or the SHA-256 digest of the trades
+                  analyzed, after validation and before any
+                  trade_transforms.
+    config:       None, or the RiskNormalizationConfig of the run.
+                  With simulation_seed, enough to reproduce it.
+    compute_usage:  None, or the ComputeUsage of the repetitions.
+    safe_f_interval, CAR25_interval:  None, or the ConfidenceInterval
+                  of safe-f and of CAR25 at the config's
+                  confidence_level.  None with a single repetition.
+    safe_f_stats, TWR25_stats, CAR25_stats:  None, or the
+                  SummaryStats of safe_f_list, TWR25_list, and
+                  CAR25_list.
+    equity_bands:  list with the EquityBands at safe-f of each
+                  repetition, if the config asked for
+                  equity_band_percentiles, otherwise empty.
+    streaks:      None, unless the config asked to compute_streaks.
+                  Then the StreakStatistics of the equity curves at
+                  safe-f.
+    benchmark_metrics:  None, unless the config has a benchmark.  Then
+                  the BenchmarkStatistics of the equity curves at
+                  safe-f.
+    safe_f_capped:  True if the safe-f of any repetition was held at
+                  the config's max_fraction or initial_margin limit,
+                  or at MAX_FRACTION, so that trading at safe-f draws
+                  down less than the drawdown tolerance.
+    """
+
+    def __init__(self,
+                 safe_f_mean,
+                 safe_f_stdev,
+                 CAR25_mean,
+                 CAR25_stdev,
+                 tail_risk_curve,
+                 safe_f_list = None,
+                 TWR25_list = None,
+                 CAR25_list = None,
+                 CAR_distributions = None,
+                 sample_size_advisory = None,
+                 CAR_percentile_lists = None,
+                 warnings = None,
+                 ratio_percentiles = None,
+                 strategy = None,
+                 tags = None,
+                 drawdown_profiles = None,
+                 probability_of_ruin = 0.0,
+                 search_traces = None,
+                 goal_probabilities = None,
+                 safe_sizing = None,
+                 scenario = None,
+                 trade_transforms = None,
+                 value_at_risk = None,
+                 drawdown_distributions = None,
+                 equity_curves = None,
+                 started_at = None,
+                 finished_at = None,
+                 CAR_histograms = None,
+                 drawdown_histograms = None,
+                 trade_indices = None,
+                 size_multipliers = None,
+                 simulation_seed = None,
+                 trades_sha256 = None,
+                 config = None,
+                 compute_usage = None,
+                 safe_f_interval = None,
+                 CAR25_interval = None,
+                 safe_f_stats = None,
+                 TWR25_stats = None,
+                 CAR25_stats = None,
+                 equity_bands = None,
+                 streaks = None,
+                 benchmark_metrics = None,
+                 safe_f_capped = False):
+        self.safe_f_mean = safe_f_mean
+        self.safe_f_stdev = safe_f_stdev
+        self.CAR25_mean = CAR25_mean
+        self.CAR25_stdev = CAR25_stdev
+        self.tail_risk_curve = tail_risk_curve
+        self.safe_f_list = safe_f_list
+        self.TWR25_list = TWR25_list
+        self.CAR25_list = CAR25_list
+        self.CAR_distributions = CAR_distributions
+        self.sample_size_advisory = sample_size_advisory
+        self.CAR_percentile_lists = CAR_percentile_lists
+        if CAR_percentile_lists is None:
+            self.CAR_percentiles = None
+        else:
+            self.CAR_percentiles = {
+                percentile: statistics.mean(CARs)
+                for percentile, CARs in CAR_percentile_lists.items()}
+        if warnings is None:
+            self.warnings = []
+        else:
+            self.warnings = warnings
+        self.ratio_percentiles = ratio_percentiles
+        self.strategy = strategy
+        if tags is None:
+            self.tags = {}
+        else:
+            self.tags = tags
+        self.drawdown_profiles = drawdown_profiles
+        self.probability_of_ruin = probability_of_ruin
+        self.search_traces = search_traces
+        if goal_probabilities is None:
+            self.goal_probabilities = []
+        else:
+            self.goal_probabilities = goal_probabilities
+        if safe_sizing is None:
+            self.safe_sizing = safe_f_mean
+        else:
+            self.safe_sizing = safe_sizing
+        self.scenario = scenario
+        if trade_transforms is None:
+            self.trade_transforms = []
+        else:
+            self.trade_transforms = trade_transforms
+        if value_at_risk is None:
+            self.value_at_risk = []
+        else:
+            self.value_at_risk = value_at_risk
+        self.drawdown_distributions = drawdown_distributions
+        if equity_curves is None:
+            self.equity_curves = []
+        else:
+            self.equity_curves = equity_curves
+        self.started_at = started_at
+        self.finished_at = finished_at
+        if CAR_histograms is None:
+            self.CAR_histograms = []
+        else:
+            self.CAR_histograms = CAR_histograms
+        if drawdown_histograms is None:
+            self.drawdown_histograms = []
+        else:
+            self.drawdown_histograms = drawdown_histograms
+        self.trade_indices = trade_indices
+        self.size_multipliers = size_multipliers
+        self.simulation_seed = simulation_seed
+        self.trades_sha256 = trades_sha256
+        self.config = config
+        self.compute_usage = compute_usage
+        self.safe_f_interval = safe_f_interval
+        self.CAR25_interval = CAR25_interval
+        self.safe_f_stats = safe_f_stats
+        self.TWR25_stats = TWR25_stats
+        self.CAR25_stats = CAR25_stats
+        if equity_bands is None:
+            self.equity_bands = []
+        else:
+            self.equity_bands = equity_bands
+        self.streaks = streaks
+        self.benchmark_metrics = benchmark_metrics
+        self.safe_f_capped = safe_f_capped
+
+    def __iter__(self):
+        return iter((self.safe_f_mean, self.safe_f_stdev,
+                     self.CAR25_mean, self.CAR25_stdev))
+
+    def describe_charts(self, language = 'en'):
+
+        """
+        Text alternatives to the charts of the result, for readers
+        who cannot see them.
         
-        CDF_equity = form_distribution_of_equity(
-                         trades, 
-                         fraction,
-                         number_days_in_forecast,
-                         number_trades_in_forecast,
-                         initial_capital,
-                         number_equity_in_CDF)
+        Returns:
+        dictionary with 'fan_charts', a description of the equity
+        curves of each repetition, if the config asked for
+        equity_curve_percentiles, and 'CAR_histogram' and
+        'drawdown_histogram', descriptions of the CAR and maximum
+        drawdown of every equity curve of every repetition, if it
+        asked to keep_CAR_distributions.
+        """
+
+        descriptions = {}
+        if any(self.equity_curves):
+            descriptions['fan_charts'] = [
+                describe_fan_chart(curves, language)
+                for curves in self.equity_curves]
+        if self.CAR_distributions is not None:
+            descriptions['CAR_histogram'] = describe_histogram(
+                np.concatenate(self.CAR_distributions),
+                'CAR_quantity', language)
+            descriptions['drawdown_histogram'] = describe_histogram(
+                100.0 * np.concatenate(self.drawdown_distributions),
+                'drawdown_quantity', language)
+
+        return descriptions
+
+    def reproduce(self, trades):
+
+        """
+        Run the computation of this result again, from its config
+        and simulation_seed, such as to verify a result written
+        long ago.
         
-        TWR25 = np.percentile(CDF_equity, 25)
-        # print(f'terminal wealth: {TWR25:9.0f}')
+        Parameters:
+        trades:     the trades the result was computed from.
         
-        CAR25 = 100.0 * (math.exp((252.0 / number_days_in_forecast) * 
-                                 math.log(TWR25/initial_capital)) - 1.0)
+        Returns:
+        RiskNormalizationResult, with the same values as this one
+        if nothing has changed.
         
-        print(f'Compound Annual Return: {CAR25:0.3f}%')
+        Raises InvalidParameterError if the trades are not those of
+        the result, or if the result does not carry its config and
+        seed.
+        """
+
+        if (self.config is None or self.simulation_seed is None or
+                self.simulation_seed.seed is None):
+            raise InvalidParameterError('result', self,
+                                        'does not carry its config and '
+                                        'seed, so cannot be reproduced')
+        if hash_trades(trades) != self.trades_sha256:
+            raise InvalidParameterError('trades', len(trades),
+                                        'are not the trades of the result')
+
+        return risk_normalization_with_config(
+                   trades,
+                   self.config.replace(seed = self.simulation_seed.seed))
+
+    def __repr__(self):
+        if self.strategy is not None and self.strategy.name is not None:
+            strategy = f'strategy={self.strategy.name!r}, '
+        else:
+            strategy = ''
+        return (f'RiskNormalizationResult({strategy}'
+                f'safe_f_mean={self.safe_f_mean:0.3f}, '
+                f'safe_f_stdev={self.safe_f_stdev:0.3f}, '
+                f'CAR25_mean={self.CAR25_mean:0.3f}, '
+                f'CAR25_stdev={self.CAR25_stdev:0.3f})')
+
+
+class SampleSizeAdvisory:
+
+    """
+    How much safe-f and CAR25 would change if a different set of
+    trades of the same size had been observed.
     
-        safe_fs.append(fraction)
-        TWR25s.append(TWR25)
-        CAR25s.append(CAR25)
+    Each bootstrap resamples the trades, with replacement, to a new
+    set of the same size and computes safe-f and CAR25 from it.
+    The spread of those values shows whether the number of trades
+    is enough for stable conclusions.
     
-    #  end of rep loop
-       
-    # print(safe_fs)
-    # print(TWR25s)
-    # print(CAR25s)
+    Attributes:
+    number_trades:      the number of trades analyzed.
+    number_bootstraps:  the number of resampled sets computed.
+    number_failed:      resampled sets for which safe-f could not
+                        be found within max_iterations passes.
+    safe_f_stdev:       standard deviation of safe-f.
+    safe_f_variation:   safe_f_stdev as a proportion of mean safe-f.
+    CAR25_low, CAR25_high:  5th and 95th percentiles of CAR25.
+    safe_f_list, CAR25_list:  safe-f and CAR25 of each resampled set
+                        for which safe-f was found.
+    is_stable:          False if safe-f varies by more than 25 percent
+                        of its mean, if CAR25 could be either a gain
+                        or a loss, or if any set failed.
+    message:            a sentence describing the result, in the
+                        language of the config.
+    """
+
+    def __init__(self,
+                 number_trades,
+                 number_bootstraps,
+                 number_failed,
+                 safe_f_stdev,
+                 safe_f_variation,
+                 CAR25_low,
+                 CAR25_high,
+                 language = 'en',
+                 safe_f_list = None,
+                 CAR25_list = None):
+        self.number_trades = number_trades
+        self.number_bootstraps = number_bootstraps
+        self.number_failed = number_failed
+        self.safe_f_stdev = safe_f_stdev
+        self.safe_f_variation = safe_f_variation
+        self.CAR25_low = CAR25_low
+        self.CAR25_high = CAR25_high
+        if safe_f_list is None:
+            self.safe_f_list = []
+        else:
+            self.safe_f_list = safe_f_list
+        if CAR25_list is None:
+            self.CAR25_list = []
+        else:
+            self.CAR25_list = CAR25_list
+        self.is_stable = (number_failed == 0 and
+                          safe_f_variation <= 0.25 and
+                          (CAR25_low > 0.0 or CAR25_high < 0.0))
+        if self.is_stable:
+            self.message = format_message('stable_sample_size', language,
+                                          number_trades = number_trades,
+                                          CAR25_low = CAR25_low,
+                                          CAR25_high = CAR25_high)
+        else:
+            self.message = format_message('unstable_sample_size', language,
+                                          number_trades = number_trades,
+                                          variation =
+                                              100.0 * safe_f_variation,
+                                          CAR25_low = CAR25_low,
+                                          CAR25_high = CAR25_high)
+            if number_failed > 0:
+                self.message = (self.message +
+                                format_message('sets_without_safe_f',
+                                               language,
+                                               number_failed = number_failed,
+                                               number_bootstraps =
+                                                   number_bootstraps))
+
+    def __repr__(self):
+        return f'SampleSizeAdvisory({self.message})'
+
+
+class TradeSetComparison:
+
+    """
+    The differences between the results of two sets of trades, such
+    as the 'before' and 'after' versions of a system, run with the
+    same config and seed.  See compare_trade_sets.
     
-    # print (f'mean and standard deviation are based on {number_repetitions}'
-    #        ' calculations')    
-    safe_f_mean = statistics.mean(safe_fs)
-    # print (f'safe_f_mean:   {safe_f_mean:0.3f}')
-    if number_repetitions > 2:
-        safe_f_stdev = statistics.stdev(safe_fs)
-    #     print (f'safe_f_stdev:  {safe_f_stdev:0.3f}')
-    else:
-        safe_f_stdev = 0.0
-    #     print ('standard deviation calculation is not meaningful')
+    Attributes:
+    result_a, result_b:  RiskNormalizationResult of each set.
+    safe_f_delta:        safe_f_mean of b less that of a.
+    CAR25_delta:         CAR25_mean of b less that of a.
+    drawdown_deltas:     dictionary from 'mean', 'median', and
+                         'percentile_95' to that statistic of maximum
+                         drawdown at safe-f, averaged over the
+                         repetitions, of b less that of a.
+    number_bootstraps:   the number of resampled pairs of sets.
+    CAR25_delta_low, CAR25_delta_high:  5th and 95th percentiles of
+                         CAR25_delta over the resampled pairs.
+    p_value:             two sided bootstrap p-value of the hypothesis
+                         that CAR25 is the same for both sets.
+                         None if there were no bootstraps.
+    """
+
+    def __init__(self,
+                 result_a,
+                 result_b,
+                 number_bootstraps,
+                 CAR25_delta_low,
+                 CAR25_delta_high,
+                 p_value):
+        self.result_a = result_a
+        self.result_b = result_b
+        self.safe_f_delta = result_b.safe_f_mean - result_a.safe_f_mean
+        self.CAR25_delta = result_b.CAR25_mean - result_a.CAR25_mean
+        self.drawdown_deltas = {
+            name: (statistics.mean(getattr(profile, name)
+                                   for profile in result_b.drawdown_profiles) -
+                   statistics.mean(getattr(profile, name)
+                                   for profile in result_a.drawdown_profiles))
+            for name in ('mean', 'median', 'percentile_95')}
+        self.number_bootstraps = number_bootstraps
+        self.CAR25_delta_low = CAR25_delta_low
+        self.CAR25_delta_high = CAR25_delta_high
+        self.p_value = p_value
+
+    def __repr__(self):
+        return (f'TradeSetComparison(safe_f_delta={self.safe_f_delta:0.3f}, '
+                f'CAR25_delta={self.CAR25_delta:0.3f}, '
+                f'p_value={self.p_value!r})')
+
+
+class StrategyRanking:
+
+    """
+    The place of one set of trades among several run with the same
+    config and seed.  See rank_strategies.
     
-    TWR25_mean = statistics.mean(TWR25s)
-    # print (f'TWR25_mean:   {TWR25_mean:0.0f}')
-    if number_repetitions > 2:
-        TWR25_stdev = statistics.stdev(TWR25s)
-    #     print (f'TWR25_stdev:  {TWR25_stdev:0.3f}')
-    else:
-        TWR25_stdev = 0.0
-    #     print ('standard deviation calculation is not meaningful')
+    Attributes:
+    rank:     1 for the best, by CAR25_mean, then safe_f_mean.
+    name:     the name of the strategy, the file name without its
+              extension.
+    path:     the file the trades were read from.
+    result:   its RiskNormalizationResult.
+    """
+
+    def __init__(self,
+                 rank,
+                 name,
+                 path,
+                 result):
+        self.rank = rank
+        self.name = name
+        self.path = path
+        self.result = result
+
+    def __repr__(self):
+        return (f'StrategyRanking(rank={self.rank}, name={self.name!r}, '
+                f'CAR25_mean={self.result.CAR25_mean:0.3f}, '
+                f'safe_f_mean={self.result.safe_f_mean:0.3f})')
+
+
+class StrategyInfo:
+
+    """
+    Describes the trading system that produced the trades, so
+    that results can be told apart once saved or compared.
+    Every field is optional, keyword only, and free text.
     
-    CAR25_mean = statistics.mean(CAR25s)
-    # print (f'CAR25_mean:   {CAR25_mean:0.3f}%')
-    if number_repetitions > 2:
-        CAR25_stdev = statistics.stdev(CAR25s)
-    #     print (f'CAR25_stdev:  {CAR25_stdev:0.3f}%')
-    else:
-        CAR25_stdev = 0.0
-    #     print ('standard deviation calculation is not meaningful')
+    Attributes:
+    name:         such as 'RSI2 mean reversion'.
+    description:  anything else worth recording.
+    instrument:   such as 'SPY' or 'ES futures'.
+    timeframe:    such as 'daily' or '15 minute bars'.
+    """
+
+    def __init__(self,
+                 *,
+                 name = None,
+                 description = None,
+                 instrument = None,
+                 timeframe = None):
+        self.name = name
+        self.description = description
+        self.instrument = instrument
+        self.timeframe = timeframe
+
+    def __repr__(self):
+        values = ', '.join(f'{name}={value!r}'
+                           for name, value in vars(self).items()
+                           if value is not None)
+        return f'StrategyInfo({values})'
+
+
+class DrawdownProfile:
+
+    """
+    The distribution of maximum drawdown of the equity curves at
+    safe-f in one repetition, the same curves that give CAR25.
+    Drawdowns are proportions of the highest equity.
     
-    return (safe_f_mean, safe_f_stdev, CAR25_mean, CAR25_stdev)
+    Attributes:
+    mean:           mean maximum drawdown.
+    median:         median maximum drawdown.
+    percentile_95:  maximum drawdown at the 95th percentile.
+    probability_of_exceeding:  dictionary from each of the config's
+                    drawdown_levels to the proportion of equity
+                    curves whose maximum drawdown is greater.
+    """
 
-#-----------------------------------------------------
+    def __init__(self,
+                 mean,
+                 median,
+                 percentile_95,
+                 probability_of_exceeding):
+        self.mean = mean
+        self.median = median
+        self.percentile_95 = percentile_95
+        self.probability_of_exceeding = probability_of_exceeding
 
-newline = '\n'
+    def __repr__(self):
+        return (f'DrawdownProfile(mean={self.mean:0.3f}, '
+                f'median={self.median:0.3f}, '
+                f'percentile_95={self.percentile_95:0.3f})')
 
-#  Estimate safe-f and CAR25 for a given csv file
 
-file_name = 'RSIDailyGains.csv'
+class StreakStatistics:
 
-#  This file covers about 28 years of daily trading.
-#  It contains 1185 trades.
-#  A 2 year forecast will have about 42 trades. 
+    """
+    The losing streaks and recoveries of the equity curves at
+    safe-f, over all repetitions.  A losing trade is one after which
+    the equity is lower than before it.
+    
+    Attributes:
+    losing_trades:  SummaryStats of the longest run of losing trades
+                    of each curve.
+    losing_days:    SummaryStats of the same runs in days.
+    recovery_days:  SummaryStats of the days from the lowest point of
+                    each curve's maximum drawdown until the equity
+                    is back at the high before it, over the curves
+                    that recovered before the end of the forecast.
+                    None if none did.
+    proportion_unrecovered:  the proportion of curves that had not
+                    recovered from their maximum drawdown by the end
+                    of the forecast.
+    """
 
-#base_path_to_trades = "/home/howard/ML4TProjects/CAR25onCSV/data/"
-base_path_to_trades = "./data/"
-path_to_trades = base_path_to_trades + file_name 
-print (f'{newline}The data file being processed is: {path_to_trades}')
+    def __init__(self,
+                 losing_trades,
+                 losing_days,
+                 recovery_days,
+                 proportion_unrecovered):
+        self.losing_trades = losing_trades
+        self.losing_days = losing_days
+        self.recovery_days = recovery_days
+        self.proportion_unrecovered = proportion_unrecovered
 
-trades = np.loadtxt(path_to_trades)
-print (f'There are {len(trades)} marked-to-market daily trades in the file')
-print ('Here are the first 10 trades:')
-print (trades[0:10])
+    def __repr__(self):
+        return (f'StreakStatistics('
+                f'losing_trades_max={self.losing_trades.max}, '
+                f'losing_trades_median={self.losing_trades.median:g}, '
+                f'proportion_unrecovered='
+                f'{self.proportion_unrecovered:0.3f})')
 
-number_days_in_forecast = 504 # 2 years
-number_trades_in_forecast = 84 # 2 * (1185 / 28)
-initial_capital = 100000.0
-tail_percentile = 5
-drawdown_tolerance = 0.10
-number_equity_in_CDF = 100
-number_repetitions = 5
 
-safe_f_mean,safe_f_stdev,CAR25_mean,CAR25_stdev = risk_normalization(
-    trades,
-    number_days_in_forecast,
-    number_trades_in_forecast,
-    initial_capital,
-    tail_percentile,
-    drawdown_tolerance,
-    number_equity_in_CDF,
-    number_repetitions)
+class BenchmarkStatistics:
 
+    """
+    The equity curves at safe-f compared with a benchmark, over all
+    repetitions.  See compute_path_benchmark_metrics.
+    
+    Attributes:
+    alpha:              SummaryStats of the annualized alpha of each
+                        curve, as a proportion.
+    beta:               SummaryStats of the beta of each curve.
+    relative_drawdown:  SummaryStats of the maximum drawdown of each
+                        curve relative to the benchmark.
+    """
+
+    def __init__(self,
+                 alpha,
+                 beta,
+                 relative_drawdown):
+        self.alpha = alpha
+        self.beta = beta
+        self.relative_drawdown = relative_drawdown
+
+    def __repr__(self):
+        return (f'BenchmarkStatistics(alpha_mean={self.alpha.mean:0.4f}, '
+                f'beta_mean={self.beta.mean:0.3f}, '
+                f'relative_drawdown_median='
+                f'{self.relative_drawdown.median:0.3f})')
+
+
+class ComputeUsage:
+
+    """
+    The computing a run used, so that the cost of large runs and
+    sweeps can be attributed to strategies or users.
+    
+    Attributes:
+    cpu_seconds:   processor time of the repetitions, in seconds,
+                   including the worker processes that evaluated
+                   candidate fractions.
+    number_paths:  the number of equity sequences simulated, in the
+                   search for safe-f and at safe-f.
+    """
+
+    def __init__(self,
+                 cpu_seconds = 0.0,
+                 number_paths = 0):
+        self.cpu_seconds = cpu_seconds
+        self.number_paths = number_paths
+
+    def __repr__(self):
+        return (f'ComputeUsage(cpu_seconds={self.cpu_seconds:0.2f}, '
+                f'number_paths={self.number_paths})')
+
+
+class SearchPass:
+
+    """
+    One pass of the search for safe-f, kept when the config asks to
+    keep_search_trace.
+    
+    Attributes:
+    pass_number:      counting from 1.
+    number_equity:    equity curves used for each candidate.
+    fractions:        candidate fractions evaluated.
+    tail_risks:       the tail risk of each.
+    lower_bound:      the largest fraction evaluated so far in this
+                      repetition whose tail risk was below the
+                      drawdown tolerance, or None.
+    upper_bound:      the smallest fraction evaluated so far in this
+                      repetition whose tail risk was above it, or None.
+    next_fraction:    the estimate the next pass starts from, or
+                      safe-f if the search ended with this pass.
+    
+    Tail risks are estimated from random equity curves, so the
+    bounds may cross when they are close together.
+    """
+
+    def __init__(self,
+                 pass_number,
+                 number_equity,
+                 fractions,
+                 tail_risks,
+                 lower_bound,
+                 upper_bound,
+                 next_fraction):
+        self.pass_number = pass_number
+        self.number_equity = number_equity
+        self.fractions = fractions
+        self.tail_risks = tail_risks
+        self.lower_bound = lower_bound
+        self.upper_bound = upper_bound
+        self.next_fraction = next_fraction
+
+    def __repr__(self):
+        values = ', '.join(f'{name}={value!r}'
+                           for name, value in vars(self).items())
+        return f'SearchPass({values})'
+
+
+class EquityCurve:
+
+    """
+    A representative equity curve at safe-f, for charting.
+    
+    Attributes:
+    percentile:   the percentile of terminal equity at which the
+                  curve ends.  The curve is the equity sequence
+                  whose terminal equity has that rank.
+    days:         numpy array of the day of each point, from 0 to
+                  number_days_in_forecast.
+    equity:       numpy array of the equity at each point.
+    """
+
+    def __init__(self,
+                 percentile,
+                 days,
+                 equity):
+        self.percentile = percentile
+        self.days = days
+        self.equity = equity
+
+    def __repr__(self):
+        return (f'EquityCurve(percentile={self.percentile!r}, '
+                f'points={len(self.equity)}, '
+                f'final_equity={self.equity[-1]:0.0f})')
+
+
+class EquityBands:
+
+    """
+    Percentile bands of equity at safe-f, for a fan chart.  At
+    each point, the band of a percentile is that percentile of the
+    equity of every curve at that point, so a band does not follow
+    any one curve.
+    
+    Attributes:
+    days:         numpy array of the day of each point, from 0 to
+                  number_days_in_forecast.
+    bands:        dictionary from each percentile to a numpy array
+                  of its equity at each point.
+    number_paths: the number of equity curves.
+    """
+
+    def __init__(self,
+                 days,
+                 bands,
+                 number_paths):
+        self.days = days
+        self.bands = bands
+        self.number_paths = number_paths
+
+    def __repr__(self):
+        return (f'EquityBands(percentiles={list(self.bands)!r}, '
+                f'points={len(self.days)}, '
+                f'number_paths={self.number_paths})')
+
+
+class SimulationSeed:
+
+    """
+    How the random numbers of a run were drawn, enough to draw them
+    again.
+    
+    Attributes:
+    seed:        the master seed, the config's seed, or for a config
+                 without one, the seed drawn for the run.  None for
+                 shards run without a seed.
+    algorithm:   the random number generator, the config's
+                 random_algorithm, such as numpy's 'MT19937'.
+    derivation:  how the seed of each repetition is derived from the
+                 master seed.
+    """
+
+    ALGORITHM = 'MT19937'
+    DERIVATION = ('seed_random_generator(seed, algorithm), then each '
+                  'repetition is given one of random_integers(0, '
+                  '2**31 - 1, size = number_repetitions), in order, and '
+                  'each task within it is seeded by derive_seed from '
+                  'that seed and its place in the repetition')
+
+    def __init__(self,
+                 seed,
+                 algorithm = ALGORITHM,
+                 derivation = DERIVATION):
+        self.seed = seed
+        self.algorithm = algorithm
+        self.derivation = derivation
+
+    def __repr__(self):
+        return (f'SimulationSeed(seed={self.seed!r}, '
+                f'algorithm={self.algorithm!r})')
+
+
+class GoalProbability:
+
+    """
+    How likely the account is to reach a wealth target at safe-f.
+    
+    Attributes:
+    target:       the wealth target, in dollars.
+    at_end:       the probability that the equity at the end of the
+                  forecast is at least target.
+    at_any_time:  the probability that the equity reaches target at
+                  any time during the forecast.
+    Both are means over the repetitions.
+    """
+
+    def __init__(self,
+                 target,
+                 at_end,
+                 at_any_time):
+        self.target = target
+        self.at_end = at_end
+        self.at_any_time = at_any_time
+
+    def __repr__(self):
+        return (f'GoalProbability(target={self.target!r}, '
+                f'at_end={self.at_end:0.3f}, '
+                f'at_any_time={self.at_any_time:0.3f})')
+
+
+class UpdateFrequencyOutcome:
+
+    """
+    The results of updating position size only every every_days
+    days.  See analyze_update_frequency.
+    
+    Attributes:
+    every_days:       days between updates of position size.
+    every_trades:     trades between updates.
+    tail_risk:        the maximum drawdown at the tail percentile
+                      when trading the safe-f of continuous updates,
+                      but updating every every_days.
+    CAR25_at_safe_f:  CAR25 when trading that way.
+    safe_f:           safe-f when updates are every every_days.
+    CAR25:            CAR25 at that safe-f.
+    CAR25_cost:       CAR25 with continuous updates less CAR25, the
+                      cost of updating less often.
+    """
+
+    def __init__(self,
+                 every_days,
+                 every_trades,
+                 tail_risk,
+                 CAR25_at_safe_f,
+                 safe_f,
+                 CAR25,
+                 CAR25_cost):
+        self.every_days = every_days
+        self.every_trades = every_trades
+        self.tail_risk = tail_risk
+        self.CAR25_at_safe_f = CAR25_at_safe_f
+        self.safe_f = safe_f
+        self.CAR25 = CAR25
+        self.CAR25_cost = CAR25_cost
+
+    def __repr__(self):
+        return (f'UpdateFrequencyOutcome(every_days={self.every_days!r}, '
+                f'every_trades={self.every_trades!r}, '
+                f'tail_risk={self.tail_risk:0.4f}, '
+                f'CAR25_at_safe_f={self.CAR25_at_safe_f:0.3f}, '
+                f'safe_f={self.safe_f:0.3f}, '
+                f'CAR25={self.CAR25:0.3f}, '
+                f'CAR25_cost={self.CAR25_cost:0.3f})')
+
+
+class DynamicSizingResult:
+
+    """
+    The results of trading the equity curve: recomputing safe-f
+    from the most recent trades as the forecast progresses, compared
+    with holding the static safe-f throughout.  Both are computed
+    from the same draws of trades.  See risk_normalization_dynamic.
+    
+    Attributes:
+    static_safe_f:    safe-f of all the trades, the fraction at the
+                      start of every forecast.
+    recompute_every_trades:  trades between recomputations.
+    window_trades:    the number of most recent trades safe-f is
+                      recomputed from.
+    CARs_static, CARs_dynamic:  numpy arrays of the compound annual
+                      rate of return, in percent, of each forecast.
+    drawdowns_static, drawdowns_dynamic:  numpy arrays of the maximum
+                      drawdown, as a proportion, of each forecast.
+    CAR25_static, CAR25_dynamic:  CAR at the 25th percentile.
+    tail_risk_static, tail_risk_dynamic:  maximum drawdown at the
+                      config's tail percentile.
+    fractions:        numpy array of the fraction traded by each
+                      forecast, one row per forecast and one column
+                      per recomputation, starting with static_safe_f.
+    """
+
+    def __init__(self,
+                 static_safe_f,
+                 recompute_every_trades,
+                 window_trades,
+                 CARs_static,
+                 CARs_dynamic,
+                 drawdowns_static,
+                 drawdowns_dynamic,
+                 CAR25_static,
+                 CAR25_dynamic,
+                 tail_risk_static,
+                 tail_risk_dynamic,
+                 fractions):
+        self.static_safe_f = static_safe_f
+        self.recompute_every_trades = recompute_every_trades
+        self.window_trades = window_trades
+        self.CARs_static = CARs_static
+        self.CARs_dynamic = CARs_dynamic
+        self.drawdowns_static = drawdowns_static
+        self.drawdowns_dynamic = drawdowns_dynamic
+        self.CAR25_static = CAR25_static
+        self.CAR25_dynamic = CAR25_dynamic
+        self.tail_risk_static = tail_risk_static
+        self.tail_risk_dynamic = tail_risk_dynamic
+        self.fractions = fractions
+
+    def __repr__(self):
+        return (f'DynamicSizingResult(static_safe_f={self.static_safe_f:0.3f}, '
+                f'recompute_every_trades={self.recompute_every_trades}, '
+                f'CAR25_static={self.CAR25_static:0.3f}, '
+                f'CAR25_dynamic={self.CAR25_dynamic:0.3f}, '
+                f'tail_risk_static={self.tail_risk_static:0.4f}, '
+                f'tail_risk_dynamic={self.tail_risk_dynamic:0.4f})')
+
+
+CONFIDENCE_METHODS = ('repetitions', 'nested')
+
+
+class ConfidenceInterval:
+
+    """
+    A confidence interval of safe-f or CAR25.
+    
+    Attributes:
+    level:    the coverage, such as 0.95.
+    low:      the lower end.
+    high:     the upper end.
+    method:   one of CONFIDENCE_METHODS, how it was found.
+    """
+
+    def __init__(self,
+                 level,
+                 low,
+                 high,
+                 method):
+        self.level = level
+        self.low = low
+        self.high = high
+        self.method = method
+
+    def __repr__(self):
+        return (f'ConfidenceInterval(level={self.level:g}, '
+                f'low={self.low:0.4f}, high={self.high:0.4f}, '
+                f'method={self.method!r})')
+
+
+def compute_confidence_interval(
+    values,
+    level,
+    method = 'repetitions',
+    number_bootstraps = 1000,
+    seed = None,
+    percentile_method = 'linear'    ):
+
+    """
+    A confidence interval from a list of estimates.
+    
+    Parameters:
+    values:    the estimates, such as the safe-f of each repetition.
+    level:     the coverage, such as 0.95.
+    method:    'repetitions', a percentile bootstrap of the mean of
+               the values, or 'nested', the percentiles of the
+               values themselves, each computed from a resampled
+               set of trades.
+    number_bootstraps:  the number of resamples of the values for
+               'repetitions'.
+    seed:      seed of the resamples.  They are drawn from a
+               generator of their own, so that the simulation's
+               random numbers are not disturbed.
+    
+    Returns:
+    ConfidenceInterval, or None with fewer than 2 values.
+    """
+
+    if len(values) < 2:
+        return None
+
+    if method == 'repetitions':
+        generator = random.Random(seed)
+        number_values = len(values)
+        estimates = [statistics.mean(generator.choices(values,
+                                                       k = number_values))
+                     for _ in range(number_bootstraps)]
+    else:
+        estimates = list(values)
+
+    tail = 100.0 * (1.0 - level) / 2.0
+
+    return ConfidenceInterval(level,
+                              compute_percentile(estimates, tail,
+                                                 percentile_method),
+                              compute_percentile(estimates, 100.0 - tail,
+                                                 percentile_method),
+                              method)
+
+
+class ValueAtRisk:
+
+    """
+    The Value-at-Risk and Conditional Value-at-Risk, or expected
+    shortfall, of the forecast at safe-f, at one confidence level.
+    Losses are positive.
+    
+    Attributes:
+    confidence_level:  such as 0.95.
+    VaR:          the loss of terminal wealth, in dollars, that is
+                  exceeded with probability 1 - confidence_level.
+    CVaR:         the mean loss of terminal wealth, in dollars, of
+                  the equity curves at or beyond VaR.
+    VaR_return:   VaR as a proportion of initial capital, the loss
+                  of the return over the forecast.
+    CVaR_return:  CVaR as a proportion of initial capital.
+    All are means over the repetitions.
+    """
+
+    def __init__(self,
+                 confidence_level,
+                 VaR,
+                 CVaR,
+                 VaR_return,
+                 CVaR_return):
+        self.confidence_level = confidence_level
+        self.VaR = VaR
+        self.CVaR = CVaR
+        self.VaR_return = VaR_return
+        self.CVaR_return = CVaR_return
+
+    def __repr__(self):
+        return (f'ValueAtRisk(confidence_level={self.confidence_level!r}, '
+                f'VaR={self.VaR:0.0f}, CVaR={self.CVaR:0.0f}, '
+                f'VaR_return={self.VaR_return:0.4f}, '
+                f'CVaR_return={self.CVaR_return:0.4f})')
+
+
+class Histogram:
+
+    """
+    The counts of a distribution in bins of equal width, for
+    charting without the values themselves.
+    
+    Attributes:
+    edges:    numpy array of the edges of the bins, one more than
+              there are bins.
+    counts:   numpy array of the number of values in each bin.  The
+              last bin includes its upper edge.
+    """
+
+    def __init__(self,
+                 edges,
+                 counts):
+        self.edges = edges
+        self.counts = counts
+
+    def __repr__(self):
+        return (f'Histogram(bins={len(self.counts)}, '
+                f'from={self.edges[0]:0.4f}, to={self.edges[-1]:0.4f})')
+
+
+class PropFirmRules:
+
+    """
+    The rules of a proprietary trading firm's evaluation account,
+    in dollars.  Every option is keyword only.  Each trade is taken
+    to be one day's result.
+    
+    Options:
+    daily_loss_limit:   the evaluation fails if one day loses more.
+                        Default = None, no limit.
+    trailing_drawdown:  the evaluation fails if the equity falls
+                        more than this below its highest value.
+                        Default = None, no limit.
+    profit_target:      the evaluation passes once the equity is
+                        this much above the initial capital.
+    """
+
+    def __init__(self,
+                 *,
+                 profit_target,
+                 daily_loss_limit = None,
+                 trailing_drawdown = None):
+        self.profit_target = profit_target
+        self.daily_loss_limit = daily_loss_limit
+        self.trailing_drawdown = trailing_drawdown
+
+        for name, value in vars(self).items():
+            if value is not None and not (isinstance(value, (int, float))
+                                          and value > 0.0):
+                raise InvalidParameterError(name, value,
+                                            'must be greater than 0')
+
+    def __repr__(self):
+        values = ', '.join(f'{name}={value!r}'
+                           for name, value in vars(self).items())
+        return f'PropFirmRules({values})'
+
+
+class PropFirmOutcome:
+
+    """
+    The chances of an evaluation account at one fraction.
+    
+    Attributes:
+    fraction:     the fraction of the account used for each trade.
+    probability_pass:     reaching the profit target first.
+    probability_fail:     breaking a loss rule first.
+    probability_neither:  neither, by the end of the evaluation.
+    """
+
+    def __init__(self,
+                 fraction,
+                 probability_pass,
+                 probability_fail,
+                 probability_neither):
+        self.fraction = fraction
+        self.probability_pass = probability_pass
+        self.probability_fail = probability_fail
+        self.probability_neither = probability_neither
+
+    def __repr__(self):
+        return (f'PropFirmOutcome(fraction={self.fraction:0.3f}, '
+                f'pass={self.probability_pass:0.3f}, '
+                f'fail={self.probability_fail:0.3f}, '
+                f'neither={self.probability_neither:0.3f})')
+
+
+class AccountSizePlan:
+
+    """
+    The account sizes at which one unit of a system can be traded
+    at safe-f without the drawdown, in dollars, exceeding a limit.
+    See required_capital_for.
+    
+    Attributes:
+    minimum_capital:  the smallest account for which safe-f of the
+                      account buys one unit, or math.inf if safe-f is
+                      0, when no account does.
+    maximum_capital:  the largest account whose drawdown at the 95th
+                      percentile stays within the dollar limit.
+    tail_dollar_drawdown:  the drawdown at the 95th percentile, in
+                      dollars, of an account of minimum_capital, or
+                      math.nan if there is no such account.
+    is_feasible:      True if minimum_capital is finite and no more
+                      than maximum_capital.
+    """
+
+    def __init__(self,
+                 minimum_capital,
+                 maximum_capital,
+                 tail_dollar_drawdown):
+        self.minimum_capital = minimum_capital
+        self.maximum_capital = maximum_capital
+        self.tail_dollar_drawdown = tail_dollar_drawdown
+        self.is_feasible = (math.isfinite(minimum_capital) and
+                            minimum_capital <= maximum_capital)
+
+    def __repr__(self):
+        return (f'AccountSizePlan(minimum_capital={self.minimum_capital:0.0f}, '
+                f'maximum_capital={self.maximum_capital:0.0f}, '
+                f'tail_dollar_drawdown={self.tail_dollar_drawdown:0.0f}, '
+                f'is_feasible={self.is_feasible})')
+
+
+class PortfolioResult:
+
+    """
+    The results of analyze_portfolio for several strategies traded
+    together in one account.
+    
+    Attributes:
+    result:       RiskNormalizationResult of the portfolio.  safe-f
+                  is the fraction of the account used by all the
+                  strategies together, divided among them by weights.
+    weights:      list of the capital allocation weights of the
+                  strategies, scaled to sum to 1.
+    risk_contributions:  list with the proportion of the variance of
+                  the portfolio's daily returns contributed by each
+                  strategy.  They sum to 1.
+    """
+
+    def __init__(self,
+                 result,
+                 weights,
+                 risk_contributions):
+        self.result = result
+        self.weights = weights
+        self.risk_contributions = risk_contributions
+
+    def __repr__(self):
+        return (f'PortfolioResult(result={self.result!r}, '
+                f'weights={self.weights!r}, '
+                f'risk_contributions={self.risk_contributions!r})')
+
+
+class CostModel:
+
+    """
+    The costs of each trade, subtracted from its gain.
+    Every option is keyword only.
+    
+    Options:
+    commission:        dollars per trade.  Default = 0.0
+    slippage:          proportion of the position lost to the
+                       spread and market impact on each trade, such
+                       as 0.0005 for 5 basis points.  Default = 0.0
+    fee_per_contract:  dollars per contract, or per share, traded.
+                       Default = 0.0
+    contract_value:    dollars of position per contract, or the
+                       price of one share.  Needed when
+                       fee_per_contract is given.  Default = None
+    
+    A trade of exactly 0.0 is a day when the system is flat, and
+    has no costs.
+    """
+
+    def __init__(self,
+                 *,
+                 commission = 0.0,
+                 slippage = 0.0,
+                 fee_per_contract = 0.0,
+                 contract_value = None):
+        self.commission = commission
+        self.slippage = slippage
+        self.fee_per_contract = fee_per_contract
+        self.contract_value = contract_value
+
+        for name in ('commission', 'slippage', 'fee_per_contract'):
+            value = getattr(self, name)
+            if not (isinstance(value, (int, float)) and
+                    math.isfinite(value) and value >= 0.0):
+                raise InvalidParameterError(name, value,
+                                            'must be a number, 0 or more')
+        if fee_per_contract > 0.0 and not (
+                isinstance(contract_value, (int, float)) and
+                contract_value > 0.0):
+            raise InvalidParameterError('contract_value', contract_value,
+                      'must be greater than 0 when there is a fee_per_contract')
+
+        #  costs that scale with the size of the position
+        self.proportional_cost = slippage
+        if fee_per_contract > 0.0:
+            self.proportional_cost = (self.proportional_cost +
+                                      fee_per_contract / contract_value)
+
+    def net_gain(self, equity, fraction, trade):
+
+        """
+        Return the gain, in dollars, of a trade with a position of
+        fraction times equity, after costs.
+        """
+
+        position = equity * fraction
+        if trade == 0.0 or position == 0.0:
+            return 0.0
+
+        return (position * (trade - self.proportional_cost) -
+                self.commission)
+
+    def __repr__(self):
+        values = ', '.join(f'{name}={value!r}'
+                           for name, value in vars(self).items()
+                           if name != 'proportional_cost')
+        return f'CostModel({values})'
+
+
+class ExecutionNoise:
+
+    """
+    Random differences between the position the fraction calls for
+    and the position actually filled on each trade.
+    Every option is keyword only.
+    
+    Options:
+    size_stdev:       standard deviation of the proportional error in
+                      each position's size, such as 0.05 for fills
+                      typically within 5% of the intended size.
+                      Default = 0.0
+    partial_fill_probability:  probability that a trade is only
+                      partly filled.  Default = 0.0
+    minimum_fill:     the smallest proportion of a partly filled
+                      position that is filled.  Partial fills are
+                      uniform from minimum_fill to 1.  Default = 0.5
+    """
+
+    def __init__(self,
+                 *,
+                 size_stdev = 0.0,
+                 partial_fill_probability = 0.0,
+                 minimum_fill = 0.5):
+        if not (isinstance(size_stdev, (int, float)) and size_stdev >= 0.0):
+            raise InvalidParameterError('size_stdev', size_stdev,
+                                        'must be a number, 0 or more')
+        if not (isinstance(partial_fill_probability, (int, float)) and
+                0.0 <= partial_fill_probability <= 1.0):
+            raise InvalidParameterError('partial_fill_probability',
+                                        partial_fill_probability,
+                                        'must be from 0 to 1')
+        if not (isinstance(minimum_fill, (int, float)) and
+                0.0 <= minimum_fill <= 1.0):
+            raise InvalidParameterError('minimum_fill', minimum_fill,
+                                        'must be from 0 to 1')
+        self.size_stdev = size_stdev
+        self.partial_fill_probability = partial_fill_probability
+        self.minimum_fill = minimum_fill
+
+    def draw_multipliers(self,
+                         number_sequences,
+                         number_trades_in_forecast):
+
+        """
+        Returns:
+        multipliers:  numpy array with one row per equity sequence
+                      and one column per trade, of the proportion of
+                      each intended position that is filled.
+        """
+
+        shape = (number_sequences, number_trades_in_forecast)
+        multipliers = (1.0 + self.size_stdev *
+                       current_random_generator().normal(size=shape))
+        multipliers = np.where(multipliers < 0.0, 0.0, multipliers)
+        partial = (random_floats(shape) <
+                   self.partial_fill_probability)
+        fills = current_random_generator().uniform(self.minimum_fill, 1.0,
+                                                   size=shape)
+
+        return np.where(partial, multipliers * fills, multipliers)
+
+    def __repr__(self):
+        values = ', '.join(f'{name}={value!r}'
+                           for name, value in vars(self).items())
+        return f'ExecutionNoise({values})'
+
+
+class Regime:
+
+    """
+    One market regime of a Scenario's regime mix.
+    Every option is keyword only.
+    
+    Options:
+    weight:                 the share of trades drawn from the regime,
+                            relative to the other regimes.
+    volatility_multiplier:  Default = 1.0
+    mean_shift:             Default = 0.0
+    See Scenario.
+    """
+
+    def __init__(self,
+                 *,
+                 weight,
+                 volatility_multiplier = 1.0,
+                 mean_shift = 0.0):
+        if not (isinstance(weight, (int, float)) and weight > 0.0):
+            raise InvalidParameterError('weight', weight,
+                                        'must be greater than 0')
+        if not (isinstance(volatility_multiplier, (int, float)) and
+                volatility_multiplier >= 0.0):
+            raise InvalidParameterError('volatility_multiplier',
+                                        volatility_multiplier,
+                                        'must be a number, 0 or more')
+        if not (isinstance(mean_shift, (int, float)) and
+                math.isfinite(mean_shift)):
+            raise InvalidParameterError('mean_shift', mean_shift,
+                                        'must be a number')
+        self.weight = weight
+        self.volatility_multiplier = volatility_multiplier
+        self.mean_shift = mean_shift
+
+    def stress(self, trades):
+
+        """
+        Returns:
+        the trades with their deviations from their mean multiplied
+        by volatility_multiplier, then mean_shift added to each.
+        """
+
+        mean = statistics.mean(trades)
+        return np.array([mean + (trade - mean) * self.volatility_multiplier +
+                         self.mean_shift
+                         for trade in trades])
+
+    def __repr__(self):
+        values = ', '.join(f'{name}={value!r}'
+                           for name, value in vars(self).items())
+        return f'Regime({values})'
+
+
+class Scenario:
+
+    """
+    A named stress scenario, applied to the trades before a run.
+    Every option is keyword only.
+    
+    Options:
+    name:                   the name of the scenario, carried in the
+                            results.
+    volatility_multiplier:  how much more, or less, the trades vary
+                            about their mean.  Default = 1.0
+    mean_shift:             added to every trade, such as -0.0005
+                            for a smaller edge.  Default = 0.0
+    regimes:                Optional.  A list of Regime.  Each trade
+                            is drawn from one of the regimes, in
+                            proportion to their weights, in place of
+                            volatility_multiplier and mean_shift.
+                            Trades are then drawn independently, as
+                            by WeightedResampling, whatever the
+                            config's resampling_method, so regimes
+                            cannot be used with the variance
+                            reduction 'antithetic'.
+                            Default = None
+    
+    Load a library of scenarios from a TOML file with load_scenarios.
+    """
+
+    def __init__(self,
+                 *,
+                 name,
+                 volatility_multiplier = 1.0,
+                 mean_shift = 0.0,
+                 regimes = None):
+        if not isinstance(name, str) or name.strip() == '':
+            raise InvalidParameterError('name', name,
+                                        'must be a name')
+        if regimes is not None and not (
+                len(regimes) > 0 and
+                all(isinstance(regime, Regime) for regime in regimes)):
+            raise InvalidParameterError('regimes', regimes,
+                                        'must be None or a list of Regime')
+        self.name = name
+        self.volatility_multiplier = volatility_multiplier
+        self.mean_shift = mean_shift
+        self.regimes = regimes
+        #  checks volatility_multiplier and mean_shift
+        self.regime = Regime(weight = 1.0,
+                             volatility_multiplier = volatility_multiplier,
+                             mean_shift = mean_shift)
+
+    def apply(self, trades, resampling_method = None):
+
+        """
+        Returns:
+        trades:             the stressed trades.
+        resampling_method:  the one given, or for a regime mix,
+                            a WeightedResampling of the regimes.
+        """
+
+        if self.regimes is None:
+            return (self.regime.stress(trades), resampling_method)
+
+        total_weight = sum(regime.weight for regime in self.regimes)
+        stressed_trades = np.concatenate([regime.stress(trades)
+                                          for regime in self.regimes])
+        weights = []
+        for regime in self.regimes:
+            weights.extend([regime.weight / total_weight / len(trades)] *
+                           len(trades))
+
+        return (stressed_trades, WeightedResampling(weights))
+
+    def __repr__(self):
+        values = ', '.join(f'{name}={value!r}'
+                           for name, value in vars(self).items()
+                           if name != 'regime')
+        return f'Scenario({values})'
+
+
+class TradeTransform:
+
+    """
+    Base class of the transforms in the config's trade_transforms,
+    applied to the trades, in order, after they are read and before
+    they are simulated, such as haircuts or filters.  Subclass it
+    and override apply, or wrap a function in FunctionTransform.
+    
+    The repr of each transform is carried in the result's
+    trade_transforms, so a subclass should give one that describes
+    its parameters.  To run in worker processes, a transform must
+    be picklable.
+    """
+
+    def apply(self, trades):
+
+        """
+        Returns:
+        the transformed trades, a list or numpy array of any length.
+        """
+
+        raise NotImplementedError
+
+
+class HaircutTransform(TradeTransform):
+
+    """
+    Make the trades less favorable than those observed, as a margin
+    of safety.  Every option is keyword only.
+    
+    Options:
+    haircut:         subtracted from every trade, such as 0.0005 for
+                     5 basis points.  Default = 0.0
+    win_multiplier:  the winning trades are multiplied by this,
+                     such as 0.8 to keep 80 percent of each gain,
+                     before the haircut.  Default = 1.0
+    """
+
+    def __init__(self,
+                 *,
+                 haircut = 0.0,
+                 win_multiplier = 1.0):
+        if not (isinstance(haircut, (int, float)) and
+                math.isfinite(haircut)):
+            raise InvalidParameterError('haircut', haircut,
+                                        'must be a number')
+        if not (isinstance(win_multiplier, (int, float)) and
+                win_multiplier >= 0.0):
+            raise InvalidParameterError('win_multiplier', win_multiplier,
+                                        'must be a number, 0 or more')
+        self.haircut = haircut
+        self.win_multiplier = win_multiplier
+
+    def apply(self, trades):
+        return np.array([(trade * self.win_multiplier if trade > 0.0
+                          else trade) - self.haircut
+                         for trade in trades])
+
+    def __repr__(self):
+        values = ', '.join(f'{name}={value!r}'
+                           for name, value in vars(self).items())
+        return f'HaircutTransform({values})'
+
+
+class FunctionTransform(TradeTransform):
+
+    """
+    A TradeTransform that calls a function, such as one that keeps
+    only the trades of some weekdays, or scales them by a volatility
+    regime.  Every option is keyword only.
+    
+    Options:
+    name:      describes the transform in the result.
+    function:  called with the trades, returns the transformed
+               trades.  To run in worker processes it must be
+               defined at the top level of a module, not a lambda.
+    """
+
+    def __init__(self,
+                 *,
+                 name,
+                 function):
+        if not isinstance(name, str) or name.strip() == '':
+            raise InvalidParameterError('name', name,
+                                        'must be a name')
+        if not callable(function):
+            raise InvalidParameterError('function', function,
+                                        'must be callable')
+        self.name = name
+        self.function = function
+
+    def apply(self, trades):
+        return self.function(trades)
+
+    def __repr__(self):
+        return f'FunctionTransform(name={self.name!r})'
+
+
+def hash_trades(
+    trades  ):
+
+    """
+    Returns:
+    the SHA-256 digest, in hexadecimal, of the trades, after
+    validation.
+    """
+
+    text = ','.join(repr(float(trade)) for trade in validate_trades(trades))
+    return hashlib.sha256(text.encode('utf-8')).hexdigest()
+
+
+def transform_trades(
+    trades,
+    config  ):
+
+    """
+    Apply the config's trade_transforms, in order, to the trades.
+    
+    Returns:
+    numpy array of the transformed trades.
+    Raises EmptyTradesError if a transform leaves no trades.
+    """
+
+    trades = validate_trades(trades)
+    for transform in config.trade_transforms:
+        trades = validate_trades(transform.apply(trades))
+
+    return trades
+
+
+def replay_equity_sequence(
+    trades,
+    result,
+    sequence    ):
+
+    """
+    Rebuild one equity curve at safe-f of the final repetition of a
+    result, from the trades and the trade indices the result kept.
+    
+    Parameters:
+    trades:     the trades the result was computed from.
+    result:     RiskNormalizationResult of a run that asked to
+                keep_trade_indices.
+    sequence:   the number of the equity curve, counting from 0, a
+                row of result.trade_indices.
+    
+    Returns:
+    numpy array of the equity before the first trade and after
+    each trade.  A ruined curve stays at 0 to the end.
+    """
+
+    if result.trade_indices is None or result.config is None:
+        raise InvalidParameterError('result.trade_indices', None,
+                  'run with keep_trade_indices = True to replay them')
+    if hash_trades(trades) != result.trades_sha256:
+        raise InvalidParameterError('trades', len(trades),
+                                    'are not the trades of the result')
+
+    config = result.config
+    trades = transform_trades(trades, config)
+    if config.scenario is not None:
+        trades, _ = config.scenario.apply(trades, config.resampling_method)
+    if 'antithetic' in config.variance_reduction:
+        trades = np.sort(trades)
+    if result.size_multipliers is not None:
+        size_multipliers = result.size_multipliers[sequence]
+    else:
+        size_multipliers = None
+
+    equity_path = [config.initial_capital]
+    equity, _ = make_one_equity_sequence(trades,
+                                         result.safe_f_list[-1],
+                                         config.number_days_in_forecast,
+                                         config.number_trades_in_forecast,
+                                         config.initial_capital,
+                                         None,
+                                         result.trade_indices[sequence],
+                                         None,
+                                         config.cash_flows,
+                                         None,
+                                         config.cost_model,
+                                         False,
+                                         config.position_sizing,
+                                         size_multipliers,
+                                         equity_path)
+    equity_path.extend([equity] *
+                       (config.number_trades_in_forecast + 1 -
+                        len(equity_path)))
+
+    return np.array(equity_path)
+
+
+class CashFlowSchedule:
+
+    """
+    Regular deposits to, or withdrawals from, the trading account
+    during the forecast, such as a retiree's monthly spending.
+    Every option is keyword only.
+    
+    Options:
+    amount:       dollars added at each cash flow.  Negative for a
+                  withdrawal.  Default = 0.0
+    percent:      proportion of the equity added at each cash flow,
+                  such as -0.004 to withdraw 0.4 percent.
+                  Default = 0.0
+    every_days:   trading days between cash flows.  Default = 21,
+                  about a month.
+    
+    An account whose equity reaches 0 is ruined, and trades no
+    further.  Deposits count towards new highs of equity, so they
+    can hide drawdowns, and withdrawals count as drawdowns.
+    """
+
+    def __init__(self,
+                 *,
+                 amount = 0.0,
+                 percent = 0.0,
+                 every_days = 21):
+        self.amount = amount
+        self.percent = percent
+        self.every_days = every_days
+
+        if not (isinstance(every_days, (int, np.integer)) and
+                every_days > 0):
+            raise InvalidParameterError('every_days', every_days,
+                                        'must be a whole number greater than 0')
+        for name, value in (('amount', amount), ('percent', percent)):
+            if (not isinstance(value, (int, float)) or
+                    not math.isfinite(value)):
+                raise InvalidParameterError(name, value,
+                                            'must be a finite number')
+
+    def apply(self, equity):
+
+        """
+        Return the equity after one cash flow.
+        """
+
+        return equity + self.amount + equity * self.percent
+
+    def __repr__(self):
+        return (f'CashFlowSchedule(amount={self.amount!r}, '
+                f'percent={self.percent!r}, '
+                f'every_days={self.every_days!r})')
+
+
+class ProgressSink:
+
+    """
+    Receives progress reports from risk_normalization.
+    
+    risk_normalization does not print.  To follow its progress,
+    subclass ProgressSink, override the methods of interest, and
+    give an instance as progress_sink in the config.  The methods
+    here do nothing.
+    """
+
+    def on_repetition_complete(self, repetition, safe_f, CAR25):
+
+        """
+        Called as each repetition completes.
+        repetition counts from 0.
+        """
+
+        pass
+
+    def on_search_pass(self, repetition, iteration, fraction, tail_risk,
+                       percent_complete):
+
+        """
+        Called as each pass of the search for safe-f completes, with
+        the candidate fraction closest to the drawdown tolerance and
+        its tail risk.  iteration counts from 1.  percent_complete
+        estimates how much of the run, or of the shard, is done,
+        from the repetitions completed and how close the search is
+        to converging.  Not called when the repetitions run in the
+        config's executor.
+        """
+
+        pass
+
+
+class PrintProgress(ProgressSink):
+
+    """
+    Prints the CAR25 of each repetition as it completes.
+    """
+
+    def on_repetition_complete(self, repetition, safe_f, CAR25):
+        print(f'Compound Annual Return: {CAR25:0.3f}%')
+
+
+class RiskNormalizationConfig:
+
+    """
+    The parameters of risk_normalization, set by name.
+    
+    Every parameter is keyword only, so that the number of days
+    and the number of trades in the forecast cannot be swapped
+    by position.  Parameters not given take the typical values
+    described at the top of this file.  The values are checked
+    when the config is created and ValueError is raised for any
+    that are not sensible, as InvalidParameterError.
+    
+    Example:
+        config = RiskNormalizationConfig(
+                     number_days_in_forecast = 504,
+                     number_trades_in_forecast = 84,
+                     drawdown_tolerance = 0.10)
+        result = risk_normalization_with_config(trades, config)
+    
+    Use replace to make a copy with some values changed:
+        config_1_year = config.replace(number_days_in_forecast = 252,
+                                       number_trades_in_forecast = 42)
+    """
+
+    def __init__(self, *,
+                 number_days_in_forecast = 504,
+                 number_trades_in_forecast = None,
+                 initial_capital = 100000.0,
+                 tail_percentile = 5,
+                 drawdown_tolerance = 0.10,
+                 number_equity_in_CDF = 1000,
+                 number_repetitions = 10,
+                 drawdown_limit_multiple = 2.0,
+                 max_fraction = None,
+                 initial_margin = None,
+                 sample_size_schedule = None,
+                 number_workers = None,
+                 keep_CAR_distributions = False,
+                 seed = None,
+                 progress_sink = None,
+                 minimum_number_trades = 20,
+                 sample_size_bootstraps = 0,
+                 resampling_method = None,
+                 CAR_percentiles = (5, 25, 50, 75, 95),
+                 executor = None,
+                 percentile_method = 'linear',
+                 max_memory_bytes = None,
+                 max_paths = None,
+                 compute_ratios = False,
+                 compute_streaks = False,
+                 benchmark = None,
+                 strategy = None,
+                 tags = None,
+                 trading_days_per_year = 252,
+                 drawdown_levels = (0.05, 0.10, 0.20),
+                 number_candidates = None,
+                 cancel_event = None,
+                 cash_flows = None,
+                 cost_model = None,
+                 trailing_drawdown_dollars = None,
+                 position_sizing = None,
+                 scenario = None,
+                 trade_transforms = (),
+                 execution_noise = None,
+                 keep_search_trace = False,
+                 keep_trade_indices = False,
+                 keep_histograms = False,
+                 histogram_bins = None,
+                 wealth_targets = (),
+                 equity_curve_percentiles = (),
+                 equity_curve_points = 100,
+                 equity_band_percentiles = (),
+                 VaR_confidence_levels = (0.95, 0.99),
+                 language = 'en',
+                 random_algorithm = 'MT19937',
+                 backend = 'loop',
+                 variance_reduction = (),
+                 confidence_level = 0.95,
+                 confidence_method = 'repetitions',
+                 confidence_bootstraps = 1000,
+                 desired_accuracy = 0.003,
+                 max_iterations = 1000):
+
+        #  one trade per day unless stated otherwise
+        if number_trades_in_forecast is None:
+            number_trades_in_forecast = number_days_in_forecast
+
+        self.number_days_in_forecast = number_days_in_forecast
+        self.number_trades_in_forecast = number_trades_in_forecast
+        self.initial_capital = initial_capital
+        self.tail_percentile = tail_percentile
+        self.drawdown_tolerance = drawdown_tolerance
+        self.number_equity_in_CDF = number_equity_in_CDF
+        self.number_repetitions = number_repetitions
+        self.drawdown_limit_multiple = drawdown_limit_multiple
+        self.max_fraction = max_fraction
+        self.initial_margin = initial_margin
+        self.sample_size_schedule = sample_size_schedule
+        self.number_workers = number_workers
+        self.keep_CAR_distributions = keep_CAR_distributions
+        self.seed = seed
+        self.progress_sink = progress_sink
+        self.minimum_number_trades = minimum_number_trades
+        self.sample_size_bootstraps = sample_size_bootstraps
+        self.resampling_method = resampling_method
+        self.CAR_percentiles = CAR_percentiles
+        self.executor = executor
+        self.percentile_method = percentile_method
+        self.max_memory_bytes = max_memory_bytes
+        self.max_paths = max_paths
+        self.compute_ratios = compute_ratios
+        self.compute_streaks = compute_streaks
+        self.benchmark = benchmark
+        self.strategy = strategy
+        #  checked here, since it is copied before validate runs
+        if tags is None:
+            self.tags = {}
+        elif isinstance(tags, collections.abc.Mapping):
+            self.tags = dict(tags)
+        else:
+            raise InvalidParameterError('tags', tags,
+                                        'must be None or a dictionary '
+                                        'from text to text')
+        self.trading_days_per_year = trading_days_per_year
+        self.drawdown_levels = drawdown_levels
+        self.number_candidates = number_candidates
+        self.cancel_event = cancel_event
+        self.cash_flows = cash_flows
+        self.cost_model = cost_model
+        self.trailing_drawdown_dollars = trailing_drawdown_dollars
+        self.position_sizing = position_sizing
+        self.scenario = scenario
+        self.trade_transforms = trade_transforms
+        self.execution_noise = execution_noise
+        self.keep_search_trace = keep_search_trace
+        self.keep_trade_indices = keep_trade_indices
+        self.keep_histograms = keep_histograms
+        self.histogram_bins = histogram_bins
+        self.wealth_targets = wealth_targets
+        self.equity_curve_percentiles = equity_curve_percentiles
+        self.equity_curve_points = equity_curve_points
+        self.equity_band_percentiles = equity_band_percentiles
+        self.VaR_confidence_levels = VaR_confidence_levels
+        self.language = language
+        self.random_algorithm = random_algorithm
+        self.backend = backend
+        self.variance_reduction = variance_reduction
+        self.confidence_level = confidence_level
+        self.confidence_method = confidence_method
+        self.confidence_bootstraps = confidence_bootstraps
+        self.desired_accuracy = desired_accuracy
+        self.max_iterations = max_iterations
+
+        self.validate()
+
+    def validate(self):
+
+        """
+        Raise InvalidParameterError naming the first parameter
+        that is not sensible.
+        """
+
+        def check(name, is_valid, reason):
+            if not is_valid:
+                raise InvalidParameterError(name, getattr(self, name),
+                                            reason)
+
+        #  True and False are ints to Python, but not numbers here
+        def is_number(value):
+            return (isinstance(value, (int, float, np.integer, np.floating))
+                    and not isinstance(value, bool))
+
+        def is_integer(value):
+            return (isinstance(value, (int, np.integer)) and
+                    not isinstance(value, bool))
+
+        def is_whole(value):
+            return is_integer(value) and value > 0
+
+        def is_list(value):
+            return isinstance(value, (list, tuple, np.ndarray))
+
+        check('number_days_in_forecast',
+              is_whole(self.number_days_in_forecast),
+              'must be a whole number greater than 0')
+        check('number_trades_in_forecast',
+              is_whole(self.number_trades_in_forecast),
+              'must be a whole number greater than 0')
+        check('number_trades_in_forecast',
+              self.number_trades_in_forecast <= self.number_days_in_forecast,
+              'must not be more than number_days_in_forecast -- '
+              'were the two swapped?')
+        check('initial_capital',
+              is_number(self.initial_capital) and self.initial_capital > 0.0,
+              'must be greater than 0')
+        check('tail_percentile',
+              is_number(self.tail_percentile) and
+                  0.0 < self.tail_percentile < 100.0,
+              'must be between 0 and 100')
+        check('drawdown_tolerance',
+              is_number(self.drawdown_tolerance) and
+                  0.0 < self.drawdown_tolerance < 1.0,
+              'must be a proportion between 0 and 1')
+        check('number_equity_in_CDF',
+              is_whole(self.number_equity_in_CDF),
+              'must be a whole number greater than 0')
+        check('number_repetitions',
+              is_whole(self.number_repetitions),
+              'must be a whole number greater than 0')
+        check('drawdown_limit_multiple',
+              self.drawdown_limit_multiple is None or
+                  (is_number(self.drawdown_limit_multiple) and
+                   self.drawdown_limit_multiple > 1.0),
+              'must be None or greater than 1')
+        check('max_fraction',
+              self.max_fraction is None or
+                  (is_number(self.max_fraction) and
+                   self.max_fraction > 0.0),
+              'must be None or greater than 0')
+        check('initial_margin',
+              self.initial_margin is None or
+                  (is_number(self.initial_margin) and
+                   0.0 < self.initial_margin <= 1.0),
+              'must be None or greater than 0 and at most 1')
+        check('sample_size_schedule',
+              self.sample_size_schedule is None or
+                  (is_list(self.sample_size_schedule) and
+                   all(is_list(pair) and len(pair) == 2 and
+                           is_number(pair[0]) and pair[0] > 0.0 and
+                           is_whole(pair[1])
+                       for pair in self.sample_size_schedule)),
+              'must be None or a list of (distance, number_equity) '
+              'pairs with positive values')
+        check('number_workers',
+              self.number_workers is None or is_whole(self.number_workers),
+              'must be None or a whole number greater than 0')
+        check('histogram_bins',
+              self.histogram_bins is None or is_whole(self.histogram_bins),
+              'must be None or a whole number greater than 0')
+        check('seed',
+              self.seed is None or
+                  (is_integer(self.seed) and 0 <= self.seed < 2**32),
+              'must be None or a whole number from 0 to 2**32 - 1')
+        check('progress_sink',
+              self.progress_sink is None or
+                  isinstance(self.progress_sink, ProgressSink),
+              'must be None or a ProgressSink')
+        check('minimum_number_trades',
+              is_whole(self.minimum_number_trades),
+              'must be a whole number greater than 0')
+        check('sample_size_bootstraps',
+              is_integer(self.sample_size_bootstraps) and
+                  self.sample_size_bootstraps >= 0,
+              'must be a whole number, 0 for none')
+        check('resampling_method',
+              self.resampling_method is None or
+                  isinstance(self.resampling_method, ResamplingMethod),
+              'must be None or a ResamplingMethod')
+        check('CAR_percentiles',
+              is_list(self.CAR_percentiles) and
+                  all(is_number(percentile) and 0.0 <= percentile <= 100.0
+                      for percentile in self.CAR_percentiles),
+              'must be a list of percentiles from 0 to 100')
+        check('executor',
+              self.executor is None or
+                  isinstance(self.executor, concurrent.futures.Executor),
+              'must be None or a concurrent.futures.Executor')
+        check('percentile_method',
+              self.percentile_method in PERCENTILE_METHODS,
+              f'must be one of {", ".join(PERCENTILE_METHODS)}')
+        check('max_memory_bytes',
+              self.max_memory_bytes is None or
+                  is_whole(self.max_memory_bytes),
+              'must be None or a whole number greater than 0')
+        check('max_paths',
+              self.max_paths is None or is_whole(self.max_paths),
+              'must be None or a whole number greater than 0')
+        check('strategy',
+              self.strategy is None or
+                  isinstance(self.strategy, StrategyInfo),
+              'must be None or a StrategyInfo')
+        check('tags',
+              all(isinstance(key, str) and isinstance(value, str)
+                  for key, value in self.tags.items()),
+              'keys and values must be text')
+        check('trading_days_per_year',
+              is_whole(self.trading_days_per_year) and
+                  self.trading_days_per_year <= 366,
+              'must be a whole number of days from 1 to 366')
+        check('drawdown_levels',
+              is_list(self.drawdown_levels) and
+                  all(is_number(level) and level >= 0.0
+                      for level in self.drawdown_levels),
+              'must be a list of drawdowns, as proportions')
+        check('cost_model',
+              self.cost_model is None or
+                  isinstance(self.cost_model, CostModel),
+              'must be None or a CostModel')
+        check('position_sizing',
+              self.position_sizing is None or
+                  isinstance(self.position_sizing, PositionSizing),
+              'must be None or a PositionSizing')
+        check('scenario',
+              self.scenario is None or isinstance(self.scenario, Scenario),
+              'must be None or a Scenario')
+        check('trade_transforms',
+              isinstance(self.trade_transforms, (list, tuple)) and
+                  all(isinstance(transform, TradeTransform)
+                      for transform in self.trade_transforms),
+              'must be a list of TradeTransform')
+        check('execution_noise',
+              self.execution_noise is None or
+                  isinstance(self.execution_noise, ExecutionNoise),
+              'must be None or an ExecutionNoise')
+        check('cash_flows',
+              self.cash_flows is None or
+                  isinstance(self.cash_flows, CashFlowSchedule),
+              'must be None or a CashFlowSchedule')
+        check('benchmark',
+              self.benchmark is None or
+                  (is_list(self.benchmark) and
+                   all(is_number(value) and math.isfinite(value) and
+                           value > -1.0
+                       for value in self.benchmark)),
+              'must be None or a list of returns greater than -1')
+        check('wealth_targets',
+              is_list(self.wealth_targets) and
+                  all(is_number(target) and target > 0.0
+                      for target in self.wealth_targets),
+              'must be a list of account values greater than 0')
+        check('equity_curve_percentiles',
+              is_list(self.equity_curve_percentiles) and
+                  all(is_number(percentile) and 0 <= percentile <= 100
+                      for percentile in self.equity_curve_percentiles),
+              'must be a list of percentiles from 0 to 100')
+        check('equity_curve_points',
+              is_whole(self.equity_curve_points) and
+                  self.equity_curve_points >= 2,
+              'must be a whole number, 2 or more')
+        check('equity_band_percentiles',
+              is_list(self.equity_band_percentiles) and
+                  all(is_number(percentile) and 0 < percentile < 100
+                      for percentile in self.equity_band_percentiles),
+              'must be a list of percentiles between 0 and 100')
+        check('VaR_confidence_levels',
+              is_list(self.VaR_confidence_levels) and
+                  all(is_number(level) and 0.0 < level < 1.0
+                      for level in self.VaR_confidence_levels),
+              'must be a list of proportions between 0 and 1')
+        check('language',
+              isinstance(self.language, str) and self.language in MESSAGES,
+              f'must be one of {sorted(MESSAGES)}')
+        check('random_algorithm',
+              self.random_algorithm in RANDOM_ALGORITHMS,
+              f'must be one of {list(RANDOM_ALGORITHMS)}')
+        check('backend',
+              self.backend in SIMULATION_BACKENDS,
+              f'must be one of {list(SIMULATION_BACKENDS)}')
+        check('variance_reduction',
+              isinstance(self.variance_reduction, (list, tuple)) and
+                  all(technique in VARIANCE_REDUCTIONS
+                      for technique in self.variance_reduction),
+              f'must be a list of {list(VARIANCE_REDUCTIONS)}')
+        check('variance_reduction',
+              'antithetic' not in self.variance_reduction or
+                  self.resampling_method is None or
+                  type(self.resampling_method) in (IIDResampling,
+                                                   AntitheticResampling),
+              'antithetic needs IIDResampling')
+        check('variance_reduction',
+              'antithetic' not in self.variance_reduction or
+                  self.scenario is None or
+                  self.scenario.regimes is None,
+              'antithetic cannot be used with a scenario of regimes, '
+              'whose trades are drawn by their weights')
+        check('confidence_level',
+              is_number(self.confidence_level) and
+                  0.0 < self.confidence_level < 1.0,
+              'must be between 0 and 1')
+        check('confidence_method',
+              self.confidence_method in CONFIDENCE_METHODS,
+              f'must be one of {list(CONFIDENCE_METHODS)}')
+        check('confidence_method',
+              self.confidence_method != 'nested' or
+                  self.sample_size_bootstraps >= 2,
+              'nested needs sample_size_bootstraps of 2 or more')
+        check('confidence_bootstraps',
+              is_whole(self.confidence_bootstraps) and
+                  self.confidence_bootstraps >= 1,
+              'must be a whole number, 1 or more')
+        check('desired_accuracy',
+              is_number(self.desired_accuracy) and
+                  0.0 < self.desired_accuracy < self.drawdown_tolerance,
+              'must be greater than 0 and less than drawdown_tolerance')
+        check('trailing_drawdown_dollars',
+              self.trailing_drawdown_dollars is None or
+                  (is_number(self.trailing_drawdown_dollars) and
+                   self.desired_accuracy * self.initial_capital <
+                       self.trailing_drawdown_dollars <
+                       self.initial_capital),
+              'must be None, or dollars less than initial_capital and '
+              'greater than desired_accuracy times initial_capital')
+        check('max_iterations',
+              is_whole(self.max_iterations),
+              'must be a whole number greater than 0')
+        check('number_candidates',
+              self.number_candidates is None or
+                  is_whole(self.number_candidates),
+              'must be None or a whole number greater than 0')
+
+    @classmethod
+    def from_options(cls, **options):
+
+        """
+        Return a new config with the given values, raising
+        InvalidParameterError, rather than TypeError, for a name
+        that is not a parameter of the config.
+        """
+
+        known = vars(cls())
+        for name in options:
+            if name not in known:
+                raise InvalidParameterError(name, options[name],
+                                            'is not a parameter of '
+                                            'RiskNormalizationConfig')
+
+        return cls(**options)
+
+    def fraction_limit(self):
+
+        """
+        Returns:
+        The largest fraction the search for safe-f considers, from
+        max_fraction and initial_margin, or None if neither is set.
+        """
+
+        limits = []
+        if self.max_fraction is not None:
+            limits.append(self.max_fraction)
+        if self.initial_margin is not None:
+            limits.append(1.0 / self.initial_margin)
+
+        return min(limits) if limits else None
+
+    def replace(self, **changes):
+
+        """
+        Return a new config with the given values changed.
+        """
+
+        values = dict(vars(self))
+        values.update(changes)
+        return RiskNormalizationConfig.from_options(**values)
+
+    def __repr__(self):
+        values = ', '.join(f'{name}={value!r}'
+                           for name, value in vars(self).items())
+        return f'RiskNormalizationConfig({values})'
+
+
+class Trade:
+
+    """
+    One trade of a trade export, with its dates and metadata.
+    Every attribute is keyword only, and all but trade_return may
+    be None when the export does not have them.
+    
+    Attributes:
+    entry_date:    datetime.datetime the position was opened.
+    exit_date:     datetime.datetime the position was closed.
+    symbol:        the instrument traded, such as 'ES'.
+    quantity:      the number of contracts or shares.
+    pnl:           the profit or loss, in dollars.
+    trade_return:  the gain of the trade as a proportion of the
+                   capital, the value analyzed as a trade.
+    """
+
+    def __init__(self,
+                 *,
+                 trade_return,
+                 entry_date = None,
+                 exit_date = None,
+                 symbol = None,
+                 quantity = None,
+                 pnl = None):
+        self.trade_return = trade_return
+        self.entry_date = entry_date
+        self.exit_date = exit_date
+        self.symbol = symbol
+        self.quantity = quantity
+        self.pnl = pnl
+
+    def __repr__(self):
+        values = ', '.join(f'{name}={value!r}'
+                           for name, value in vars(self).items()
+                           if value is not None)
+        return f'Trade({values})'
+
+
+class CsvOptions:
+
+    """
+    How to read a csv file of trades exported by a broker or
+    backtester.  Every option is keyword only.  The defaults read
+    the files included with this program: one trade per line, with
+    an optional header line.
+    
+    Options:
+    has_header:  True if the first line names the columns, False if
+                 it does not.  None decides from the first line --
+                 it is a header if its trade column is not a number.
+                 Default = None
+    delimiter:   the character separating the columns.
+                 Default = ','
+    column:      the column holding the trades, either a number
+                 counting from 0 or the name in the header.
+                 Default = 0
+    date_column: None, or the column holding the date of each trade,
+                 a number or a name.  When given, the dates are read
+                 and the trades are put in date order.
+                 Default = None
+    date_format: the format of the dates, as for datetime.strptime.
+                 Default = '%Y-%m-%d'
+    thousands_separator:  None, or a character to remove from
+                 numbers, such as ',' in 1,234.50.  Numbers holding
+                 the delimiter must be quoted.
+                 Default = None
+    values_in_percent:  True if 1.5 means a gain of 1.5 percent.
+                 A number ending with % is always read as a percent.
+                 Default = False, 0.015 means a gain of 1.5 percent.
+    """
+
+    def __init__(self, *,
+                 has_header = None,
+                 delimiter = ',',
+                 column = 0,
+                 date_column = None,
+                 date_format = '%Y-%m-%d',
+                 thousands_separator = None,
+                 values_in_percent = False):
+        self.has_header = has_header
+        self.delimiter = delimiter
+        self.column = column
+        self.date_column = date_column
+        self.date_format = date_format
+        self.thousands_separator = thousands_separator
+        self.values_in_percent = values_in_percent
+
+        self.validate()
+
+    def validate(self):
+
+        """
+        Raise InvalidParameterError naming the first option
+        that is not sensible.
+        """
+
+        def check(name, is_valid, reason):
+            if not is_valid:
+                raise InvalidParameterError(name, getattr(self, name),
+                                            reason)
+
+        def is_column(value):
+            return ((isinstance(value, int) and value >= 0) or
+                    (isinstance(value, str) and value != ''))
+
+        check('has_header', self.has_header in (None, True, False),
+              'must be None, True, or False')
+        check('delimiter',
+              isinstance(self.delimiter, str) and len(self.delimiter) == 1,
+              'must be a single character')
+        check('column', is_column(self.column),
+              'must be a column number from 0 or a column name')
+        check('date_column',
+              self.date_column is None or is_column(self.date_column),
+              'must be None, a column number from 0, or a column name')
+        check('date_format', isinstance(self.date_format, str),
+              'must be a datetime.strptime format')
+        check('thousands_separator',
+              self.thousands_separator is None or
+                  (isinstance(self.thousands_separator, str) and
+                   len(self.thousands_separator) == 1 and
+                   self.thousands_separator not in '.0123456789'),
+              'must be None or a single character that is not '
+              'part of a number')
+        check('has_header',
+              self.has_header is not False or
+                  not (isinstance(self.column, str) or
+                       isinstance(self.date_column, str)),
+              'columns given by name need a header')
+
+
+def read_number(
+    field,
+    options ):
+
+    """
+    Read one trade from a field of a csv file, applying the
+    thousands separator and percent options.
+    Raises ValueError if it is not a number.
+    """
+
+    text = field.strip()
+    in_percent = options.values_in_percent
+    if text.endswith('%'):
+        text = text[:-1].strip()
+        in_percent = True
+    if options.thousands_separator is not None:
+        text = text.replace(options.thousands_separator, '')
+    value = float(text)
+    if in_percent:
+        value = value / 100.0
+
+    return value
+
+
+def find_column(
+    header,
+    column,
+    source  ):
+
+    """
+    Return the number of a column given by number or by name.
+    """
+
+    if isinstance(column, int):
+        return column
+    names = [name.strip() for name in header]
+    if column not in names:
+        raise CsvParseError(source, 1, 1, column,
+                            'column name is not in the header')
+
+    return names.index(column)
+
+
+def iterate_trade_rows(
+    lines,
+    source = '<text>',
+    options = None  ):
+
+    """
+    Read trades, and their dates if there is a date column, one
+    line of a csv file at a time.  See parse_trade_table.
+    
+    Parameters:
+    lines:    the lines of a csv or txt file, such as an open file.
+    source:   name used in error messages, such as the file name.
+    options:  CsvOptions.  Default = None, CsvOptions().
+    
+    Yields:
+    (trade, date) for each line with a trade, in the order of the
+    file.  date is None if there is no date column.
+    """
+
+    if options is None:
+        options = CsvOptions()
+
+    by_name = (isinstance(options.column, str) or
+               isinstance(options.date_column, str))
+    if options.has_header is None and not by_name:
+        header_state = 'maybe'
+    elif options.has_header is False:
+        header_state = 'none'
+    else:
+        header_state = 'expected'
+
+    trade_index = options.column
+    date_index = options.date_column
+
+    rows = csv.reader(lines, delimiter = options.delimiter)
+    line_number = 0
+    try:
+        for row in rows:
+            line_number = rows.line_num
+            if all(field.strip() == '' for field in row):
+                continue
+            if header_state == 'expected':
+                header_state = 'none'
+                trade_index = find_column(row, options.column, source)
+                if options.date_column is not None:
+                    date_index = find_column(row, options.date_column,
+                                             source)
+                continue
+            if trade_index >= len(row):
+                raise CsvParseError(source, line_number, trade_index + 1,
+                                    '', 'there is no trade column')
+            field = row[trade_index]
+            try:
+                trade = read_number(field, options)
+            except ValueError:
+                if header_state == 'maybe':
+                    header_state = 'none'
+                    if options.date_column is not None:
+                        date_index = find_column(row, options.date_column,
+                                                 source)
+                    continue
+                raise CsvParseError(source, line_number, trade_index + 1,
+                                    field, 'cannot read a trade')
+            header_state = 'none'
+            if not math.isfinite(trade):
+                raise CsvParseError(source, line_number, trade_index + 1,
+                                    field, 'trade is not a finite number')
+            date = None
+            if date_index is not None:
+                if date_index >= len(row):
+                    raise CsvParseError(source, line_number,
+                                        date_index + 1, '',
+                                        'there is no date column')
+                try:
+                    date = datetime.datetime.strptime(
+                               row[date_index].strip(),
+                               options.date_format)
+                except ValueError:
+                    raise CsvParseError(source, line_number,
+                                        date_index + 1, row[date_index],
+                                        'cannot read a date')
+            yield (trade, date)
+    except csv.Error as error:
+        raise CsvParseError(source, line_number + 1, 1, '', str(error))
+
+
+def parse_trade_table(
+    text,
+    source = '<text>',
+    options = None  ):
+
+    """
+    Read trades, and their dates if there is a date column, from
+    the text of a csv file.  Blank lines are skipped.
+    
+    Parameters:
+    text:     the contents of a csv or txt file.
+    source:   name used in error messages, such as the file name.
+    options:  CsvOptions.  Default = None, CsvOptions().
+    
+    Returns:
+    trades:   numpy array of trades, one dimension.
+    dates:    list of datetime.datetime, one per trade, or None if
+              there is no date column.  When there are dates, the
+              trades are in date order.
+    
+    Raises CsvParseError for a line that cannot be read as a
+    trade, including values that are not finite, and
+    EmptyTradesError for text with no trades at all.  Both are
+    ValueErrors, and nothing else is raised.
+    """
+
+    if options is None:
+        options = CsvOptions()
+
+    trades = []
+    dates = []
+    for trade, date in iterate_trade_rows(io.StringIO(text, newline = ''),
+                                          source, options):
+        trades.append(trade)
+        dates.append(date)
+
+    if len(trades) == 0:
+        raise EmptyTradesError(source)
+
+    if options.date_column is None:
+        return (np.array(trades), None)
+
+    order = sorted(range(len(trades)), key = lambda i: dates[i])
+    return (np.array([trades[i] for i in order]),
+            [dates[i] for i in order])
+
+
+def parse_trade_records(
+    text,
+    capital = None,
+    source = '<text>',
+    options = None,
+    *,
+    pnl_column = 'pnl',
+    return_column = None,
+    entry_date_column = 'entry_date',
+    exit_date_column = 'exit_date',
+    symbol_column = 'symbol',
+    quantity_column = 'quantity'    ):
+
+    """
+    Read dated trade records, such as a broker's trade export,
+    from the text of a csv file with a header.
+    
+    Parameters:
+    text:     the contents of a csv file.
+    capital:  the capital, in dollars, of the account the trades
+              were made in.  Each trade's return is its pnl over
+              capital.  Not needed when there is a return_column.
+    source:   name used in error messages, such as the file name.
+    options:  CsvOptions for the delimiter, thousands separator,
+              percent values, and date format.  Its column options
+              are not used.  Default = None, CsvOptions().
+    pnl_column, return_column, entry_date_column, exit_date_column,
+    symbol_column, quantity_column:  the names of the columns in
+              the header.  Either pnl_column or return_column must
+              be present.  The others are read if present.
+    
+    Returns:
+    records:  list of Trade, in order of exit date when there are
+              exit dates, and otherwise in the order of the file.
+    
+    Raises CsvParseError for a line that cannot be read, and
+    EmptyTradesError for text with no trades.
+    """
+
+    if options is None:
+        options = CsvOptions()
+
+    rows = csv.reader(io.StringIO(text, newline = ''),
+                      delimiter = options.delimiter)
+    header = None
+    records = []
+    line_number = 0
+    try:
+        for row in rows:
+            line_number = rows.line_num
+            if all(field.strip() == '' for field in row):
+                continue
+            if header is None:
+                header = [name.strip() for name in row]
+                if return_column is not None:
+                    value_name = return_column
+                elif capital is not None and capital > 0.0:
+                    value_name = pnl_column
+                else:
+                    raise InvalidParameterError('capital', capital,
+                              'must be greater than 0 to compute '
+                              'returns from pnl')
+                value_index = find_column(header, value_name, source)
+                columns = {name: header.index(column)
+                           for name, column in (
+                               ('pnl', pnl_column),
+                               ('entry_date', entry_date_column),
+                               ('exit_date', exit_date_column),
+                               ('symbol', symbol_column),
+                               ('quantity', quantity_column))
+                           if column in header}
+                continue
+
+            def field(index):
+                if index >= len(row):
+                    raise CsvParseError(source, line_number, index + 1,
+                                        '', 'the line has too few columns')
+                return row[index]
+
+            def number(index):
+                try:
+                    value = read_number(field(index), options)
+                except ValueError:
+                    raise CsvParseError(source, line_number, index + 1,
+                                        field(index), 'cannot read a number')
+                if not math.isfinite(value):
+                    raise CsvParseError(source, line_number, index + 1,
+                                        field(index), 'is not a finite number')
+                return value
+
+            def date(index):
+                try:
+                    return datetime.datetime.strptime(field(index).strip(),
+                                                      options.date_format)
+                except ValueError:
+                    raise CsvParseError(source, line_number, index + 1,
+                                        field(index), 'cannot read a date')
+
+            values = {}
+            if 'pnl' in columns:
+                values['pnl'] = number(columns['pnl'])
+            if 'quantity' in columns:
+                values['quantity'] = number(columns['quantity'])
+            for name in ('entry_date', 'exit_date'):
+                if name in columns:
+                    values[name] = date(columns[name])
+            if 'symbol' in columns:
+                values['symbol'] = field(columns['symbol']).strip()
+            if return_column is not None:
+                trade_return = number(value_index)
+            else:
+                trade_return = values['pnl'] / capital
+            records.append(Trade(trade_return = trade_return, **values))
+    except csv.Error as error:
+        raise CsvParseError(source, line_number + 1, 1, '', str(error))
+
+    if len(records) == 0:
+        raise EmptyTradesError(source)
+
+    if 'exit_date' in columns:
+        records.sort(key = lambda record: record.exit_date)
+
+    return records
+
+
+def read_trade_records_from_csv(
+    path,
+    capital = None,
+    options = None,
+    **columns   ):
+
+    """
+    Read dated trade records from a csv file.
+    See parse_trade_records.
+    """
+
+    return parse_trade_records(read_text_file(path), capital, path,
+                               options, **columns)
+
+
+def select_trade_records(
+    records,
+    start = None,
+    end = None,
+    symbols = None  ):
+
+    """
+    Select the trade records to simulate.
+    
+    Parameters:
+    records:  list of Trade.
+    start, end:  Optional.  datetime.datetime.  Keep only trades
+              whose exit date, or entry date if they have no exit
+              date, is from start up to and including end.
+              Default = None, no limit.
+    symbols:  Optional.  Keep only trades of these symbols.
+              Default = None, every symbol.
+    
+    Returns:
+    trades:   numpy array of the trade_return of each record kept,
+              ready for risk_normalization.
+    """
+
+    trades = []
+    for record in records:
+        when = (record.exit_date if record.exit_date is not None
+                else record.entry_date)
+        if start is not None and (when is None or when < start):
+            continue
+        if end is not None and (when is None or when > end):
+            continue
+        if symbols is not None and record.symbol not in symbols:
+            continue
+        trades.append(record.trade_return)
+
+    return np.array(trades)
+
+
+def parse_trades(
+    text,
+    source = '<text>',
+    options = None  ):
+
+    """
+    Read a list of trades from the text of a csv file.
+    See parse_trade_table.  With the default options, the first
+    comma separated field of each line is used, and the first line
+    is skipped if it is not a number -- a header.
+    
+    Returns:
+    trades:   numpy array of trades, one dimension.
+    """
+
+    trades, dates = parse_trade_table(text, source, options)
+
+    return trades
+
+
+def validate_trades(
+    trades  ):
+
+    """
+    Check that trades can be analyzed.
+    
+    Returns:
+    trades:   numpy array of floats, one dimension.
+    
+    Raises EmptyTradesError if there are none, and
+    InvalidParameterError if they are not a one dimensional list
+    of finite numbers.
+    """
+
+    try:
+        trades = np.asarray(trades, dtype = float)
+    except (TypeError, ValueError):
+        raise InvalidParameterError('trades', type(trades).__name__,
+                                    'must be a list of numbers')
+    if trades.ndim != 1:
+        raise InvalidParameterError('trades', trades.shape,
+                                    'must have one dimension')
+    if len(trades) == 0:
+        raise EmptyTradesError('trades')
+    if not np.all(np.isfinite(trades)):
+        raise InvalidParameterError('trades', 'not finite',
+                                    'every trade must be a finite number')
+
+    return trades
+
+
+def read_text_file(
+    path    ):
+
+    """
+    Read a text file as UTF-8.
+    Bytes that are not valid UTF-8 are replaced rather than
+    stopping the read, so they are reported as unreadable trades.
+    """
+
+    with open(path, 'rb') as f:
+        data = f.read()
+
+    return data.decode('utf-8-sig', errors = 'replace')
+
+
+def read_trades_from_csv(
+    path_to_trades,
+    options = None  ):
+
+    """
+    Read a csv or txt file of trades.  See parse_trade_table.
+    With the default options, one trade per line, with or
+    without a header line.
+    
+    Returns:
+    trades:   numpy array of trades, one dimension.
+    """
+
+    return parse_trades(read_text_file(path_to_trades),
+                        str(path_to_trades), options)
+
+
+def read_trade_table_from_csv(
+    path_to_trades,
+    options = None  ):
+
+    """
+    Read a csv file of trades and their dates.
+    See parse_trade_table.
+    
+    Returns:
+    trades:   numpy array of trades, one dimension.
+    dates:    list of datetime.datetime, or None.
+    """
+
+    return parse_trade_table(read_text_file(path_to_trades),
+                             str(path_to_trades), options)
+
+
+def iterate_trades_from_csv(
+    path_to_trades,
+    options = None  ):
+
+    """
+    Read a csv or txt file of trades one line at a time, for files
+    too large to hold in memory.  See parse_trade_table.
+    Dates, if any, are read and checked, but the trades are in the
+    order of the file.
+    
+    Yields:
+    trade:    each trade, a float.
+    
+    Raises CsvParseError for a line that cannot be read, when
+    that line is reached, and EmptyTradesError at the end of a
+    file with no trades.
+    """
+
+    number_trades = 0
+    with open(path_to_trades, encoding = 'utf-8-sig',
+              errors = 'replace', newline = '') as f:
+        for trade, date in iterate_trade_rows(f, str(path_to_trades),
+                                              options):
+            number_trades = number_trades + 1
+            yield trade
+
+    if number_trades == 0:
+        raise EmptyTradesError(str(path_to_trades))
+
+
+def read_trades_in_chunks(
+    path_to_trades,
+    chunk_size,
+    options = None  ):
+
+    """
+    Read a csv or txt file of trades chunk_size trades at a time.
+    See iterate_trades_from_csv.
+    
+    Yields:
+    trades:   numpy array of up to chunk_size trades.
+    """
+
+    chunk = []
+    for trade in iterate_trades_from_csv(path_to_trades, options):
+        chunk.append(trade)
+        if len(chunk) == chunk_size:
+            yield np.array(chunk)
+            chunk = []
+    if len(chunk) > 0:
+        yield np.array(chunk)
+
+
+def reservoir_sample_trades(
+    trades,
+    sample_size,
+    seed = None ):
+
+    """
+    Choose sample_size trades, each equally likely, from any number
+    of trades, holding only the sample in memory.
+    
+    Parameters:
+    trades:       iterable of trades, such as
+                  iterate_trades_from_csv(path).
+    sample_size:  the number of trades to keep.
+    seed:         Optional.  Seed for the choice.
+    
+    Returns:
+    trades:   numpy array of the sample, in their original order,
+              or all the trades if there are no more than
+              sample_size.
+    """
+
+    generator = random.Random(seed)
+    reservoir = []
+    for index, trade in enumerate(trades):
+        if index < sample_size:
+            reservoir.append((index, trade))
+        else:
+            slot = generator.randint(0, index)
+            if slot < sample_size:
+                reservoir[slot] = (index, trade)
+
+    #  keep the order of the trades, for the block resampling methods
+    reservoir.sort()
+    return np.array([trade for index, trade in reservoir])
+
+
+def parse_trades_from_json(
+    text,
+    source = '<text>',
+    field = None    ):
+
+    """
+    Read a list of trades from the text of a JSON file.
+    
+    Parameters:
+    text:     either an array of numbers, [0.012, -0.004, ...],
+              or an array of objects, each with the trade in field,
+              [{"gain": 0.012, ...}, ...].
+    source:   name used in error messages, such as the file name.
+    field:    None for an array of numbers, or the name of the
+              field holding the trade in each object.
+    
+    Returns:
+    trades:   numpy array of trades, one dimension.
+    
+    Raises JsonParseError if the text is not such an array.
+    """
+
+    try:
+        items = json.loads(text)
+    except json.JSONDecodeError as error:
+        raise JsonParseError(source,
+                             f'line {error.lineno} column {error.colno}',
+                             error.msg)
+    except RecursionError:
+        raise JsonParseError(source, 'top level',
+                             'arrays or objects are nested too deeply')
+    if not isinstance(items, list):
+        raise JsonParseError(source, 'top level', 'must be an array')
+
+    trades = []
+    for index, item in enumerate(items):
+        if field is not None:
+            if not isinstance(item, dict):
+                raise JsonParseError(source, f'item {index + 1}',
+                                     'must be an object')
+            if field not in item:
+                raise JsonParseError(source, f'item {index + 1}',
+                                     f'has no field {field!r}')
+            item = item[field]
+        #  bool is a subclass of int, but true is not a trade
+        if isinstance(item, bool) or not isinstance(item, (int, float)):
+            raise JsonParseError(source, f'item {index + 1}',
+                                 f'{item!r} is not a finite number')
+        #  JSON integers may be too large for a float
+        try:
+            is_finite = math.isfinite(item)
+        except OverflowError:
+            raise JsonParseError(source, f'item {index + 1}',
+                                 'is too large to be a trade')
+        if not is_finite:
+            raise JsonParseError(source, f'item {index + 1}',
+                                 f'{item!r} is not a finite number')
+        trades.append(float(item))
+
+    return np.array(trades)
+
+
+def read_trades_from_json(
+    path_to_trades,
+    field = None    ):
+
+    """
+    Read a JSON file of trades.  See parse_trades_from_json.
+    
+    Returns:
+    trades:   numpy array of trades, one dimension.
+    """
+
+    return parse_trades_from_json(read_text_file(path_to_trades),
+                                  str(path_to_trades), field)
+
+
+ARROW_FILE_FORMATS = ('parquet', 'ipc')
+
+
+def parse_trades_from_arrow(
+    data,
+    source = '<bytes>',
+    column = None,
+    file_format = 'parquet'  ):
+
+    """
+    Read a column of trades from the bytes of a Parquet file or an
+    Arrow IPC (Feather) file.  Needs the pyarrow package.
+    
+    Parameters:
+    data:     the contents of the file.
+    source:   name used in error messages, such as the file name.
+    column:   the name of the column holding the trades.  Only that
+              column is read.  Default = None, the only column of a
+              table that has one.
+    file_format:  one of ARROW_FILE_FORMATS.
+    
+    Returns:
+    trades:   numpy array of trades, one dimension.
+    
+    Raises ArrowParseError if the data is not such a table.
+    """
+
+    if file_format not in ARROW_FILE_FORMATS:
+        raise InvalidParameterError('file_format', file_format,
+                                    f'must be one of {ARROW_FILE_FORMATS}')
+
+    try:
+        import pyarrow
+        import pyarrow.feather
+        import pyarrow.parquet
+    except ImportError:
+        raise ArrowParseError(source, 'reading Parquet and Arrow files '
+                              'needs the pyarrow package')
+
+    columns = None if column is None else [column]
+    try:
+        if file_format == 'parquet':
+            table = pyarrow.parquet.read_table(pyarrow.BufferReader(data),
+                                               columns = columns)
+        else:
+            table = pyarrow.feather.read_table(pyarrow.BufferReader(data),
+                                               columns = columns)
+    except (pyarrow.ArrowException, KeyError, ValueError) as error:
+        raise ArrowParseError(source, str(error))
+
+    if table.num_columns != 1:
+        raise ArrowParseError(source, f'name the column of trades, one of '
+                              f'{table.column_names}')
+    values = table.column(0)
+    if not (pyarrow.types.is_integer(values.type) or
+            pyarrow.types.is_floating(values.type)):
+        raise ArrowParseError(source, f'column {table.column_names[0]!r} '
+                              f'of type {values.type} is not numbers')
+    if values.null_count > 0:
+        raise ArrowParseError(source, f'column {table.column_names[0]!r} '
+                              f'has {values.null_count} missing trades')
+
+    trades = np.array(values.to_pylist(), dtype = float)
+    if not np.all(np.isfinite(trades)):
+        raise ArrowParseError(source, f'column {table.column_names[0]!r} '
+                              f'has trades that are not finite')
+
+    return trades
+
+
+def read_trades_from_parquet(
+    path_to_trades,
+    column = None   ):
+
+    """
+    Read a Parquet file of trades.  See parse_trades_from_arrow.
+    
+    Returns:
+    trades:   numpy array of trades, one dimension.
+    """
+
+    with open(path_to_trades, 'rb') as f:
+        data = f.read()
+
+    return parse_trades_from_arrow(data, str(path_to_trades), column,
+                                   'parquet')
+
+
+def read_trades_from_ipc(
+    path_to_trades,
+    column = None   ):
+
+    """
+    Read an Arrow IPC file of trades, also known as Feather.  See
+    parse_trades_from_arrow.
+    
+    Returns:
+    trades:   numpy array of trades, one dimension.
+    """
+
+    with open(path_to_trades, 'rb') as f:
+        data = f.read()
+
+    return parse_trades_from_arrow(data, str(path_to_trades), column,
+                                   'ipc')
+
+
+def trade_file_format(
+    path_to_trades  ):
+
+    """
+    The format of a file of trades, from the end of its name:
+    'json' for .json, 'parquet' for .parquet, 'ipc' for .arrow,
+    .feather, and .ipc, and 'csv' for all others.
+    """
+
+    name = str(path_to_trades).lower()
+    if name.endswith('.json'):
+        return 'json'
+    if name.endswith('.parquet'):
+        return 'parquet'
+    if name.endswith(('.arrow', '.feather', '.ipc')):
+        return 'ipc'
+
+    return 'csv'
+
+
+def read_trades(
+    path_to_trades,
+    options = None,
+    field = None    ):
+
+    """
+    Read a file of trades in any format, as trade_file_format
+    names it.  JSON files are read by read_trades_from_json, with
+    field.  Parquet and Arrow files are read by
+    read_trades_from_parquet and read_trades_from_ipc, with field
+    as the column.  All others are read by read_trades_from_csv,
+    with options.
+    
+    Returns:
+    trades:   numpy array of trades, one dimension.
+    """
+
+    file_format = trade_file_format(path_to_trades)
+    if file_format == 'json':
+        return read_trades_from_json(path_to_trades, field)
+    if file_format == 'parquet':
+        return read_trades_from_parquet(path_to_trades, field)
+    if file_format == 'ipc':
+        return read_trades_from_ipc(path_to_trades, field)
+
+    return read_trades_from_csv(path_to_trades, options)
+
+
+def parse_trades_of_any_format(
+    text,
+    source = '<text>',
+    options = None,
+    field = None    ):
+
+    """
+    Read a list of trades from text whose format is not known.
+    Text that starts with [ is read as JSON by
+    parse_trades_from_json, with field.  All other text, a csv
+    table or one number per line, is read by parse_trades, with
+    options.
+    
+    Returns:
+    trades:   numpy array of trades, one dimension.
+    """
+
+    text = text.lstrip('\ufeff')
+    if text.lstrip().startswith('['):
+        return parse_trades_from_json(text, source, field)
+
+    return parse_trades(text, source, options)
+
+
+def read_trades_from_stream(
+    stream = None,
+    options = None,
+    field = None    ):
+
+    """
+    Read trades of any format from a stream of text, such as the
+    output of a backtester piped to this program.
+    See parse_trades_of_any_format.
+    
+    Parameters:
+    stream:   Optional.  Default = None, sys.stdin.
+    
+    Returns:
+    trades:   numpy array of trades, one dimension.
+    """
+
+    if stream is None:
+        stream = sys.stdin
+
+    return parse_trades_of_any_format(stream.read(), '<stdin>',
+                                      options, field)
+
+
+class TradePreview:
+
+    """
+    A look at a set of trades before a long run.
+    
+    Attributes:
+    number_trades:  the number of trades.
+    first_trades:   numpy array of the first trades, as many as were
+                    asked for.
+    last_trades:    numpy array of the last trades.
+    mean:           the mean trade.
+    stdev:          the standard deviation of the trades.  0.0 for
+                    fewer than 3 trades.
+    win_rate:       the proportion of trades that are gains.
+    largest_loss:   the most negative trade, or 0.0 if none is a
+                    loss.
+    warnings:       list of RiskNormalizationWarning about the
+                    trades, not issued with warnings.warn.
+    """
+
+    def __init__(self,
+                 number_trades,
+                 first_trades,
+                 last_trades,
+                 mean,
+                 stdev,
+                 win_rate,
+                 largest_loss,
+                 warnings = None):
+        self.number_trades = number_trades
+        self.first_trades = first_trades
+        self.last_trades = last_trades
+        self.mean = mean
+        self.stdev = stdev
+        self.win_rate = win_rate
+        self.largest_loss = largest_loss
+        if warnings is None:
+            self.warnings = []
+        else:
+            self.warnings = warnings
+
+    def __repr__(self):
+        return (f'TradePreview(number_trades={self.number_trades}, '
+                f'mean={self.mean:0.6f}, stdev={self.stdev:0.6f}, '
+                f'win_rate={self.win_rate:0.4f}, '
+                f'largest_loss={self.largest_loss:0.6f}, '
+                f'warnings={[warning.code for warning in self.warnings]})')
+
+
+def preview_trades(
+    path_to_trades = None,
+    text = None,
+    options = None,
+    field = None,
+    number_shown = 5,
+    config = None   ):
+
+    """
+    Read and check a set of trades, and summarize them, so that a
+    program can show them before running a long simulation.
+    
+    Parameters:
+    path_to_trades:  a file of trades in any format, read as
+                 read_trades reads it.
+    text:        Optional.  The contents of a file instead of its
+                 path, in any format, as parse_trades_of_any_format
+                 reads it.  Give one of path_to_trades and text.
+    options:     Optional.  CsvOptions for a csv file.
+    field:       Optional.  The field holding the trade, for a JSON
+                 array of objects.
+    number_shown:  Optional.  The number of trades shown from the
+                 start and from the end.  Default = 5
+    config:      Optional.  RiskNormalizationConfig the trades will
+                 be run with, for the warning that there are fewer
+                 trades than a forecast draws, and for the language
+                 of the warnings.  Default = None, no such warning,
+                 and English.
+    
+    Returns:
+    TradePreview
+    
+    Raises the errors of read_trades and validate_trades.
+    """
+
+    if (path_to_trades is None) == (text is None):
+        raise InvalidParameterError('text', text,
+                                    'give one of path_to_trades and text')
+    if not (isinstance(number_shown, int) and number_shown >= 0):
+        raise InvalidParameterError('number_shown', number_shown,
+                                    'must be a whole number, 0 or more')
+
+    if text is not None:
+        trades = parse_trades_of_any_format(text, options = options,
+                                            field = field)
+    else:
+        trades = read_trades(path_to_trades, options, field)
+    trades = validate_trades(trades)
+
+    language = 'en' if config is None else config.language
+    number_trades = len(trades)
+    mean, stdev = compute_statistics([float(trade) for trade in trades])
+    preview_warnings = []
+    if (config is not None and
+            number_trades < config.number_trades_in_forecast):
+        preview_warnings.append(RiskNormalizationWarning('few_trades',
+            format_message('few_trades', language,
+                           number_trades_in_forecast =
+                               config.number_trades_in_forecast,
+                           number_trades = number_trades)))
+    if np.all(trades >= 0.0):
+        preview_warnings.append(RiskNormalizationWarning('no_losing_trades',
+            format_message('no_losing_trades', language,
+                           number_trades = number_trades)))
+    if np.all(trades <= 0.0):
+        preview_warnings.append(RiskNormalizationWarning('no_winning_trades',
+            format_message('no_winning_trades', language,
+                           number_trades = number_trades)))
+
+    return TradePreview(number_trades,
+                        trades[:number_shown],
+                        trades[max(0, number_trades - number_shown):],
+                        mean,
+                        stdev,
+                        float(np.mean(trades > 0.0)),
+                        min(0.0, float(np.min(trades))),
+                        warnings = preview_warnings)
+
+
+class TradeFileCache:
+
+    """
+    Keeps the trades of recently read files, so that running again
+    with different parameters does not parse a large file again.
+    
+    Files are known by a hash of their contents, so a file that has
+    changed is read afresh, and the same contents under two names
+    are parsed once.  When more than max_entries files are kept,
+    the least recently read is dropped.
+    
+    Parameters:
+    max_entries:  Optional.  Default = 8
+    """
+
+    def __init__(self, max_entries = 8):
+        if not (isinstance(max_entries, int) and max_entries >= 1):
+            raise InvalidParameterError('max_entries', max_entries,
+                                        'must be a whole number, 1 or more')
+        self.max_entries = max_entries
+        self.entries = collections.OrderedDict()
+        self.lock = threading.Lock()
+
+    def read(self, path_to_trades, options = None, field = None):
+
+        """
+        Read a file of trades in any format, as read_trades does.
+        
+        Returns:
+        trades:   numpy array of trades, one dimension.  A copy, so
+                  changing it does not change the cache.
+        """
+
+        with open(path_to_trades, 'rb') as f:
+            data = f.read()
+        file_format = trade_file_format(path_to_trades)
+        key = (hashlib.sha256(data).hexdigest(),
+               file_format,
+               field if file_format != 'csv'
+               else repr(vars(options or CsvOptions())))
+
+        with self.lock:
+            if key in self.entries:
+                self.entries.move_to_end(key)
+                return self.entries[key].copy()
+
+        if file_format in ARROW_FILE_FORMATS:
+            trades = parse_trades_from_arrow(data, str(path_to_trades),
+                                             field, file_format)
+        elif file_format == 'json':
+            trades = parse_trades_from_json(
+                         data.decode('utf-8-sig', errors = 'replace'),
+                         path_to_trades, field)
+        else:
+            trades = parse_trades(
+                         data.decode('utf-8-sig', errors = 'replace'),
+                         path_to_trades, options)
+
+        with self.lock:
+            self.entries[key] = trades
+            while len(self.entries) > self.max_entries:
+                self.entries.popitem(last = False)
+
+        return trades.copy()
+
+    def clear(self):
+        with self.lock:
+            self.entries.clear()
+
+
+def parse_scenarios(
+    text,
+    source = '<text>'   ):
+
+    """
+    Read a library of scenarios from the text of a TOML file.
+    Each table is a scenario, named by its key, with the options of
+    Scenario.  A regime mix is an array of tables, regimes, with the
+    options of Regime:
+    
+        [crash]
+        volatility_multiplier = 2.5
+        mean_shift = -0.002
+    
+        [[choppy.regimes]]
+        weight = 0.7
+    
+        [[choppy.regimes]]
+        weight = 0.3
+        volatility_multiplier = 2.0
+    
+    Returns:
+    scenarios:  dictionary from each name to its Scenario.
+    
+    Raises ScenarioParseError if the text is not a library of
+    scenarios.
+    """
+
+    #  tomllib is in the standard library from Python 3.11
+    try:
+        import tomllib
+    except ImportError:
+        try:
+            import toml as tomllib
+        except ImportError:
+            raise ScenarioParseError(source, 'reading TOML needs '
+                                     'Python 3.11 or the toml package')
+
+    try:
+        tables = tomllib.loads(text)
+    except ValueError as error:
+        raise ScenarioParseError(source, str(error))
+
+    scenarios = {}
+    for name, table in tables.items():
+        if not isinstance(table, dict):
+            raise ScenarioParseError(source, f'{name}: must be a table')
+        options = dict(table)
+        try:
+            if 'regimes' in options:
+                options['regimes'] = [Regime(**regime)
+                                      for regime in options['regimes']]
+            scenarios[name] = Scenario(name = name, **options)
+        except (TypeError, InvalidParameterError) as error:
+            raise ScenarioParseError(source, f'{name}: {error}')
+
+    return scenarios
+
+
+def load_scenarios(
+    path    ):
+
+    """
+    Read a library of scenarios from a TOML file.
+    See parse_scenarios.
+    """
+
+    return parse_scenarios(read_text_file(path), path)
+
+
+class ResamplingMethod:
+
+    """
+    How trades are drawn from the best estimate set to form each
+    equity sequence.  Subclasses implement draw_indices.
+    """
+
+    def draw_indices(self,
+                     number_trades,
+                     number_sequences,
+                     number_trades_in_forecast):
+
+        """
+        Returns:
+        trade_indices:  numpy array of integers with one row per
+                        equity sequence and one column per trade.
+        """
+
+        raise NotImplementedError
+
+    def __repr__(self):
+        values = ', '.join(f'{name}={value!r}'
+                           for name, value in vars(self).items())
+        return f'{type(self).__name__}({values})'
+
+
+class IIDResampling(ResamplingMethod):
+
+    """
+    Each trade is drawn independently, with replacement.
+    Any ordering in the trades, such as streaks of wins or losses,
+    is lost.  This is the original method and the default.
+    """
+
+    def draw_indices(self,
+                     number_trades,
+                     number_sequences,
+                     number_trades_in_forecast):
+        return random_integers(0, number_trades,
+                   size=(number_sequences, number_trades_in_forecast))
+
+
+VARIANCE_REDUCTIONS = ('antithetic', 'common_random_numbers')
+
+
+class AntitheticResampling(ResamplingMethod):
+
+    """
+    As IIDResampling, except that the sequences come in pairs, the
+    second drawing trade number_trades - 1 - i wherever the first
+    draws trade i.  With the trades sorted, the pairs are mirror
+    images, a lucky sequence paired with an unlucky one, and the
+    spread of the estimates from a distribution is smaller.  With an
+    odd number of sequences the first of them is unpaired.
+    
+    Sorting does not change what IIDResampling draws, so a run with
+    variance_reduction 'antithetic' sorts the trades and uses this.
+    """
+
+    def draw_indices(self,
+                     number_trades,
+                     number_sequences,
+                     number_trades_in_forecast):
+        number_pairs = number_sequences // 2
+        trade_indices = random_integers(0, number_trades,
+                            size=(number_sequences - number_pairs,
+                                  number_trades_in_forecast))
+        mirrored = number_trades - 1 - trade_indices[
+                       number_sequences - 2 * number_pairs:]
+        return np.concatenate((trade_indices, mirrored))
+
+
+def check_block_length(
+    name,
+    block_length,
+    number_trades   ):
+
+    """
+    Raise InvalidParameterError unless block_length is a whole
+    number from 1 to number_trades.
+    """
+
+    if not (isinstance(block_length, (int, np.integer)) and
+            1 <= block_length <= number_trades):
+        raise InvalidParameterError(name, block_length,
+                                    f'must be a whole number from 1 to '
+                                    f'the number of trades, {number_trades}')
+
+
+class MovingBlockResampling(ResamplingMethod):
+
+    """
+    Blocks of block_length consecutive trades are drawn, with
+    replacement, and joined to form each sequence.  Blocks never
+    run past the last trade.  Preserves ordering within blocks,
+    such as streaks of wins or losses.  The trades must be in the
+    order they occurred.
+    """
+
+    def __init__(self, block_length):
+        self.block_length = block_length
+
+    def draw_indices(self,
+                     number_trades,
+                     number_sequences,
+                     number_trades_in_forecast):
+        check_block_length('block_length', self.block_length, number_trades)
+        return draw_block_indices(number_trades,
+                                  number_sequences,
+                                  number_trades_in_forecast,
+                                  self.block_length,
+                                  False)
+
+
+class CircularBlockResampling(ResamplingMethod):
+
+    """
+    As MovingBlockResampling, except that a block may wrap from the
+    last trade to the first, so trades near the ends of the set are
+    drawn as often as those in the middle.
+    """
+
+    def __init__(self, block_length):
+        self.block_length = block_length
+
+    def draw_indices(self,
+                     number_trades,
+                     number_sequences,
+                     number_trades_in_forecast):
+        check_block_length('block_length', self.block_length, number_trades)
+        return draw_block_indices(number_trades,
+                                  number_sequences,
+                                  number_trades_in_forecast,
+                                  self.block_length,
+                                  True)
+
+
+class StationaryBootstrapResampling(ResamplingMethod):
+
+    """
+    The stationary bootstrap of Politis and Romano.  As
+    CircularBlockResampling, except that the length of each block is
+    random, with mean mean_block_length.  After each trade a new
+    block starts with probability 1 / mean_block_length.
+    """
+
+    def __init__(self, mean_block_length):
+        self.mean_block_length = mean_block_length
+
+    def draw_indices(self,
+                     number_trades,
+                     number_sequences,
+                     number_trades_in_forecast):
+        if not (isinstance(self.mean_block_length, (int, float)) and
+                1.0 <= self.mean_block_length <= number_trades):
+            raise InvalidParameterError('mean_block_length',
+                                        self.mean_block_length,
+                                        f'must be from 1 to the number of '
+                                        f'trades, {number_trades}')
+        new_block = (random_floats(
+                         (number_sequences, number_trades_in_forecast))
+                     < 1.0 / self.mean_block_length)
+        block_starts = random_integers(0, number_trades,
+                           size=(number_sequences, number_trades_in_forecast))
+        trade_indices = np.empty(
+                            (number_sequences, number_trades_in_forecast),
+                            dtype=int)
+        trade_indices[:, 0] = block_starts[:, 0]
+        for i in range(1, number_trades_in_forecast):
+            trade_indices[:, i] = np.where(
+                                      new_block[:, i],
+                                      block_starts[:, i],
+                                      (trade_indices[:, i - 1] + 1)
+                                          % number_trades)
+        return trade_indices
+
+
+class WeightedResampling(ResamplingMethod):
+
+    """
+    Each trade is drawn independently, with replacement, trade i
+    with probability weights[i].  Used by blend_trade_samples to
+    give the trades of one sample more say than those of another.
+    """
+
+    def __init__(self, weights):
+        self.weights = weights
+
+    def draw_indices(self,
+                     number_trades,
+                     number_sequences,
+                     number_trades_in_forecast):
+        if len(self.weights) != number_trades:
+            raise InvalidParameterError('weights', len(self.weights),
+                                        f'must have one weight for each '
+                                        f'trade, {number_trades}')
+        return current_random_generator().choice(number_trades,
+                   size=(number_sequences, number_trades_in_forecast),
+                   p=self.weights)
+
+
+def blend_trade_samples(
+    in_sample_trades,
+    out_of_sample_trades,
+    out_of_sample_weight = None,
+    in_sample_discount = 0.5    ):
+
+    """
+    Combine in-sample and out-of-sample trades into one best
+    estimate set, so that the out-of-sample evidence dominates the
+    distribution used for safe-f.
+    
+    Parameters:
+    in_sample_trades:      trades from development testing.
+    out_of_sample_trades:  out-of-sample, paper, or real trades.
+    out_of_sample_weight:  Optional.  The probability that each
+                    trade drawn comes from out_of_sample_trades.
+                    Default = None, set from the sizes of the two
+                    samples, with each in-sample trade counting as
+                    in_sample_discount of an out-of-sample trade.
+    in_sample_discount:    Optional.  Default = 0.5
+    
+    Returns:
+    trades:   numpy array of the in-sample trades followed by the
+              out-of-sample trades.
+    resampling_method:  a WeightedResampling that draws them with
+              the blend weights.  Set it as the config's
+              resampling_method.
+    """
+
+    in_sample_trades = validate_trades(in_sample_trades)
+    out_of_sample_trades = validate_trades(out_of_sample_trades)
+    number_in_sample = len(in_sample_trades)
+    number_out_of_sample = len(out_of_sample_trades)
+
+    if out_of_sample_weight is None:
+        if not (isinstance(in_sample_discount, (int, float)) and
+                0.0 <= in_sample_discount <= 1.0):
+            raise InvalidParameterError('in_sample_discount',
+                                        in_sample_discount,
+                                        'must be from 0 to 1')
+        out_of_sample_weight = (number_out_of_sample /
+                                (number_out_of_sample +
+                                 in_sample_discount * number_in_sample))
+    elif not (isinstance(out_of_sample_weight, (int, float)) and
+              0.0 <= out_of_sample_weight <= 1.0):
+        raise InvalidParameterError('out_of_sample_weight',
+                                    out_of_sample_weight,
+                                    'must be from 0 to 1')
+
+    trades = np.concatenate((in_sample_trades, out_of_sample_trades))
+    weights = ([(1.0 - out_of_sample_weight) / number_in_sample] *
+                   number_in_sample +
+               [out_of_sample_weight / number_out_of_sample] *
+                   number_out_of_sample)
+
+    return (trades, WeightedResampling(weights))
+
+
+def draw_block_indices(
+    number_trades,
+    number_sequences,
+    number_trades_in_forecast,
+    block_length,
+    circular    ):
+
+    """
+    Draw trade indices in blocks of block_length consecutive
+    trades.  If circular, blocks may wrap from the last trade to the
+    first.  Otherwise they start no later than block_length trades
+    from the end.
+    """
+
+    number_blocks = -(-number_trades_in_forecast // block_length)
+    if circular:
+        number_starts = number_trades
+    else:
+        number_starts = number_trades - block_length + 1
+    block_starts = random_integers(0, number_starts,
+                       size=(number_sequences, number_blocks))
+    offsets = np.tile(np.arange(block_length), number_blocks)
+    trade_indices = np.repeat(block_starts, block_length, axis=1) + offsets
+    if circular:
+        trade_indices = trade_indices % number_trades
+
+    return trade_indices[:, :number_trades_in_forecast]
+
+
+def draw_trade_indices(
+    number_trades,
+    number_sequences,
+    number_trades_in_forecast,
+    resampling_method = None):
+
+    """
+    Draw the indices of the trades for a batch of equity sequences
+    in a single call to the random number generator.
+    
+    Parameters:
+    number_trades:    the number of trades in the best estimate set.
+    number_sequences: the number of equity sequences in the batch.
+    number_trades_in_forecast:  Length of each sequence in trades.
+    resampling_method:  Optional.  A ResamplingMethod.
+                      Default = None, IIDResampling.
+    
+    Returns:
+    trade_indices:  numpy array of integers with one row per
+                    equity sequence and one column per trade.
+    """
+
+    if resampling_method is None:
+        resampling_method = IIDResampling()
+
+    trade_indices = resampling_method.draw_indices(
+                        number_trades,
+                        number_sequences,
+                        number_trades_in_forecast)
+
+    return trade_indices
+
+
+class PositionSizing:
+
+    """
+    How the size of each position follows from the fraction being
+    searched for.  Subclasses implement position.  Every sizing is
+    linear in the fraction, so the search for safe-f is unchanged
+    and reports the safe value of the sizing's own parameter.
+    
+    Attributes:
+    parameter_name:  the name of the sizing parameter, such as
+                     'fraction' or 'contracts'.
+    """
+
+    parameter_name = 'fraction'
+
+    def position(self,
+                 equity,
+                 fraction,
+                 initial_capital,
+                 previous_trades):
+
+        """
+        Returns:
+        position:  the dollars at risk in the next trade, given the
+                   equity now and the trades of the sequence so far.
+        """
+
+        raise NotImplementedError
+
+    def sizing_parameter(self,
+                         fraction,
+                         initial_capital):
+
+        """
+        Returns:
+        the value of the sizing parameter at fraction.
+        """
+
+        return fraction
+
+    def __repr__(self):
+        values = ', '.join(f'{name}={value!r}'
+                           for name, value in vars(self).items())
+        return f'{type(self).__name__}({values})'
+
+
+class FixedFractionSizing(PositionSizing):
+
+    """
+    Each position is fraction times the equity at the time, so
+    gains compound.  This is the original sizing and the default.
+    """
+
+    def position(self,
+                 equity,
+                 fraction,
+                 initial_capital,
+                 previous_trades):
+        return equity * fraction
+
+
+class FixedDollarSizing(PositionSizing):
+
+    """
+    Each position is the same number of dollars, fraction times
+    initial_capital, whatever the equity.  Gains do not compound.
+    """
+
+    parameter_name = 'dollars'
+
+    def position(self,
+                 equity,
+                 fraction,
+                 initial_capital,
+                 previous_trades):
+        return fraction * initial_capital
+
+    def sizing_parameter(self,
+                         fraction,
+                         initial_capital):
+        return fraction * initial_capital
+
+
+class FixedContractsSizing(PositionSizing):
+
+    """
+    Each position is the same number of contracts, or shares, each
+    worth contract_value dollars.  The number of contracts is
+    fraction times initial_capital over contract_value, and need
+    not be whole.  Gains do not compound.
+    """
+
+    parameter_name = 'contracts'
+
+    def __init__(self, contract_value):
+        if not (isinstance(contract_value, (int, float)) and
+                contract_value > 0.0):
+            raise InvalidParameterError('contract_value', contract_value,
+                                        'must be greater than 0')
+        self.contract_value = contract_value
+
+    def position(self,
+                 equity,
+                 fraction,
+                 initial_capital,
+                 previous_trades):
+        return fraction * initial_capital
+
+    def sizing_parameter(self,
+                         fraction,
+                         initial_capital):
+        return fraction * initial_capital / self.contract_value
+
+
+class VolatilityScaledSizing(PositionSizing):
+
+    """
+    Each position is fraction times the equity, scaled by
+    target_volatility over the standard deviation of the last
+    lookback trades of the sequence, and by at most max_scale.
+    The first lookback trades are not scaled.  The sizing parameter
+    is the fraction when the trades are at target_volatility.
+    """
+
+    def __init__(self,
+                 target_volatility,
+                 lookback = 20,
+                 max_scale = 3.0):
+        if not (isinstance(target_volatility, (int, float)) and
+                target_volatility > 0.0):
+            raise InvalidParameterError('target_volatility',
+                                        target_volatility,
+                                        'must be greater than 0')
+        if not (isinstance(lookback, int) and lookback >= 2):
+            raise InvalidParameterError('lookback', lookback,
+                                        'must be a whole number, 2 or more')
+        if not (isinstance(max_scale, (int, float)) and max_scale > 0.0):
+            raise InvalidParameterError('max_scale', max_scale,
+                                        'must be greater than 0')
+        self.target_volatility = target_volatility
+        self.lookback = lookback
+        self.max_scale = max_scale
+
+    def position(self,
+                 equity,
+                 fraction,
+                 initial_capital,
+                 previous_trades):
+        if len(previous_trades) < self.lookback:
+            return equity * fraction
+        recent = previous_trades[-self.lookback:]
+        mean = sum(recent) / self.lookback
+        variance = sum((trade - mean) ** 2 for trade in recent) / self.lookback
+        if variance > 0.0:
+            scale = min(self.max_scale,
+                        self.target_volatility / math.sqrt(variance))
+        else:
+            scale = self.max_scale
+        return equity * fraction * scale
+
+
+class PeriodicRebalanceSizing(PositionSizing):
+
+    """
+    Each position is fraction times the equity at the most recent
+    update of position size, made every every_trades trades, rather
+    than the equity now.  Between updates the position stays the
+    same number of dollars.  Used by analyze_update_frequency.
+    
+    The equity at the latest update is kept in the object, so an
+    object must not be shared by sequences formed at the same time.
+    """
+
+    def __init__(self, every_trades):
+        if not (isinstance(every_trades, int) and every_trades >= 1):
+            raise InvalidParameterError('every_trades', every_trades,
+                                        'must be a whole number, 1 or more')
+        self.every_trades = every_trades
+        self.equity_at_update = None
+
+    def position(self,
+                 equity,
+                 fraction,
+                 initial_capital,
+                 previous_trades):
+        if len(previous_trades) % self.every_trades == 0:
+            self.equity_at_update = equity
+        return self.equity_at_update * fraction
+
+    def __repr__(self):
+        return f'PeriodicRebalanceSizing(every_trades={self.every_trades!r})'
+
+
+def make_one_equity_sequence(
+    trades,
+    fraction,
+    number_days_in_forecast,
+    number_trades_in_forecast,
+    initial_capital,
+    drawdown_limit = None,
+    trade_indices = None,
+    resampling_method = None,
+    cash_flows = None,
+    peak_list = None,
+    cost_model = None,
+    drawdown_in_dollars = False,
+    position_sizing = None,
+    size_multipliers = None,
+    equity_path = None  ):
+
+    """
+    Given a set of trades, draw a random sequence of trades
+    and form an equity sequence.
+    
+    Parameters:
+    trades:           the set of trades to be analyzed
+    fraction:         the proportion of the trading account
+                      to be used for each trade.
+    number_days_in_forecast:    Length of forecast in days.                 
+    number_trades_in_forecast:  Length of forecast in trades.
+    initial_capital:  Starting value of the trading account.
+    drawdown_limit:   Optional.  Stop the sequence as soon as the
+                      drawdown exceeds this proportion.
+                      Default = None, always draw every trade.
+    trade_indices:    Optional.  The indices of the trades to use,
+                      one row of the array from draw_trade_indices.
+                      Default = None, draw them here.
+    resampling_method:  Optional.  The ResamplingMethod used to
+                      draw the trades when trade_indices is None.
+                      Default = None, IIDResampling.
+    cash_flows:       Optional.  A CashFlowSchedule.  Trades are
+                      spread evenly over the days of the forecast,
+                      and each cash flow follows the trade on or
+                      after its day.  A sequence that runs out of
+                      money ends with equity 0 and drawdown 1.0.
+                      Default = None, no cash flows.
+    peak_list:        Optional.  A list to which the highest equity
+                      of the sequence is appended.
+    cost_model:       Optional.  A CostModel applied to each trade.
+                      Default = None, no costs.
+    drawdown_in_dollars:  Optional.  If True, measure drawdown in
+                      dollars from the highest equity to date, as a
+                      proportion of initial_capital, rather than as
+                      a proportion of the highest equity.
+                      Default = False.
+    position_sizing:  Optional.  A PositionSizing.  A sequence
+                      whose equity falls to 0 ends with equity 0
+                      and drawdown 1.0.
+                      Default = None, FixedFractionSizing.
+    size_multipliers: Optional.  The proportion of each position
+                      filled, one row of the array from
+                      ExecutionNoise.draw_multipliers.
+                      Default = None, every position is filled.
+    equity_path:      Optional.  A list to which the equity after
+                      each trade is appended.
+    
+    Returns:  
+    Two scalars:
+    equity:  The equity at the end of the sequence in dollars.
+            If the sequence was stopped early, the equity at
+            the point it was stopped.
+    max_drawdown:  The maximum drawdown experienced in the sequence
+            as a proportion of highest equity marked to market
+            after each trade, or of initial_capital when
+            drawdown_in_dollars.
+            If the sequence was stopped early, this is only
+            known to be greater than drawdown_limit.
+    """
+
+    #  initialize sequence
+
+    equity = initial_capital
+    max_equity = equity
+    drawdown = 0.0
+    max_drawdown = 0.0
+
+    #  form sequence
+    #  drawdown is tracked as each trade is applied,
+    #  the equity curve itself is not kept.
+    #  Use make_one_equity_curve to display it.
+
+    if trade_indices is None:
+        trade_indices = draw_trade_indices(
+                            len(trades), 1, number_trades_in_forecast,
+                            resampling_method)[0]
+
+    days_per_trade = number_days_in_forecast / number_trades_in_forecast
+    number_cash_flows = 0
+    previous_trades = []
+    #  only compounding fixed fraction sizing without cash flows
+    #  can never take the equity to 0
+    ruin_possible = cash_flows is not None or position_sizing is not None
+
+    for i, trade_index in enumerate(trade_indices):
+        trade = trades[trade_index]
+        if position_sizing is None:
+            position = equity * fraction
+        else:
+            position = position_sizing.position(equity, fraction,
+                                                initial_capital,
+                                                previous_trades)
+            previous_trades.append(trade)
+        if size_multipliers is not None:
+            position = position * size_multipliers[i]
+        if cost_model is None:
+            equity = equity + position * trade
+        else:
+            equity = equity + cost_model.net_gain(position, 1.0, trade)
+        if cash_flows is not None:
+            day = (i + 1) * days_per_trade
+            while (number_cash_flows + 1) * cash_flows.every_days <= day:
+                number_cash_flows = number_cash_flows + 1
+                equity = cash_flows.apply(equity)
+        if ruin_possible and equity <= 0.0:
+            #  ruined
+            equity = 0.0
+            if drawdown_in_dollars:
+                max_drawdown = max_equity / initial_capital
+            else:
+                max_drawdown = 1.0
+            break
+        if equity_path is not None:
+            equity_path.append(equity)
+        if equity >= max_equity:
+            #  new high -- no drawdown
+            max_equity = equity
+            continue
+        if drawdown_in_dollars:
+            drawdown = (max_equity - equity) / initial_capital
+        else:
+            drawdown = (max_equity - equity) / max_equity
+        if drawdown > max_drawdown:
+            max_drawdown = drawdown
+            #  the rest of the sequence cannot bring max_drawdown
+            #  back under the limit -- no need to draw it
+            if drawdown_limit is not None and max_drawdown > drawdown_limit:
+                break
+
+    if peak_list is not None:
+        peak_list.append(max_equity)
+
+    return (equity, max_drawdown)
+
+
+def make_one_equity_curve(
+    trades,
+    fraction,
+    number_days_in_forecast,
+    number_trades_in_forecast,
+    initial_capital,
+    resampling_method = None    ):
+
+    """
+    Given a set of trades, draw a random sequence of trades
+    and form the day by day equity curve, for display.
+    
+    Returns:
+    daily_equity:  numpy array of number_days_in_forecast values.
+            If there are fewer trades than days, the final
+            equity is carried forward to fill the remaining days.
+    """
+
+    equity = initial_capital
+    daily_equity = np.zeros(number_days_in_forecast)
+    trade_indices = draw_trade_indices(
+                        len(trades), 1, number_trades_in_forecast,
+                        resampling_method)[0]
+
+    for i in range(number_trades_in_forecast):
+        trade = trades[trade_indices[i]]
+        equity = equity + equity * fraction * trade
+        daily_equity[i] = equity
+    #  if necessary, fill remaining days    
+    for i in range(number_trades_in_forecast,number_days_in_forecast):
+        daily_equity[i] = equity
+
+#    plt.plot(daily_equity)
+#    plt.show()
+
+    return daily_equity
+
+
+SIMULATION_BACKENDS = ('loop', 'array', 'gpu')
+
+
+def get_array_module(
+    backend ):
+
+    """
+    The module that computes the equity sequences of backend:
+    CuPy for 'gpu' when it can be imported and sees a GPU, numpy
+    otherwise.
+    """
+
+    if backend == 'gpu':
+        try:
+            import cupy
+            if cupy.cuda.runtime.getDeviceCount() > 0:
+                return cupy
+        except Exception:
+            pass
+
+    return np
+
+
+def use_array_backend(
+    backend,
+    cash_flows = None,
+    cost_model = None,
+    position_sizing = None  ):
+
+    """
+    Whether the equity sequences can be computed all at once.  Cash
+    flows, costs, and position sizing depend on the equity one trade
+    at a time, so sequences with them are computed one by one.
+    """
+
+    return (backend != 'loop' and
+            cash_flows is None and
+            cost_model is None and
+            position_sizing is None)
+
+
+def compute_equity_arrays(
+    trades,
+    fraction,
+    initial_capital,
+    all_trade_indices,
+    all_size_multipliers = None,
+    drawdown_in_dollars = False,
+    backend = 'array'   ):
+
+    """
+    Form every equity sequence of a distribution at once, as
+    make_one_equity_sequence forms one, for fixed fraction sizing
+    without cash flows or costs.  Every trade is drawn; there is no
+    drawdown_limit.
+    
+    Parameters:
+    trades:           the set of trades to be analyzed.
+    fraction:         the proportion of the trading account
+                      to be used for each trade.
+    initial_capital:  Starting value of the trading account.
+    all_trade_indices:  array from draw_trade_indices, with a row of
+                      trade indices for each sequence.
+    all_size_multipliers:  Optional.  array of the same shape from
+                      ExecutionNoise.draw_multipliers.
+                      Default = None, every position is filled.
+    drawdown_in_dollars:  Optional.  As for make_one_equity_sequence.
+                      Default = False.
+    backend:          Optional.  'array' or 'gpu'.  Default = 'array'
+    
+    Returns:
+    Three numpy arrays, with a value for each sequence:
+    equity:        the equity at the end of the sequence in dollars.
+    max_drawdown:  the maximum drawdown of the sequence.
+    peak:          the highest equity of the sequence.
+    """
+
+    xp = get_array_module(backend)
+
+    drawn = xp.asarray(np.asarray(trades, dtype = float))[
+                xp.asarray(all_trade_indices)]
+    gains = fraction * drawn
+    if all_size_multipliers is not None:
+        gains = gains * xp.asarray(all_size_multipliers)
+    equity = initial_capital * xp.cumprod(1.0 + gains, axis = 1)
+    peak = xp.maximum(xp.maximum.accumulate(equity, axis = 1),
+                      initial_capital)
+    if drawdown_in_dollars:
+        drawdown = (peak - equity) / initial_capital
+    else:
+        drawdown = (peak - equity) / peak
+    max_drawdown = drawdown.max(axis = 1)
+
+    if xp is not np:
+        return (xp.asnumpy(equity[:, -1]),
+                xp.asnumpy(max_drawdown),
+                xp.asnumpy(peak[:, -1]))
+
+    return (equity[:, -1], max_drawdown, peak[:, -1])
+
+
+def analyze_distribution_of_drawdown(
+    trades,
+    fraction,
+    number_days_in_forecast,
+    number_trades_in_forecast,
+    initial_capital,
+    tail_percentile,
+    number_equity_in_CDF,
+    drawdown_limit = None,
+    resampling_method = None,
+    percentile_method = 'linear',
+    cash_flows = None,
+    cost_model = None,
+    drawdown_in_dollars = False,
+    position_sizing = None,
+    execution_noise = None,
+    backend = 'loop'    ):
+
+    """
+    drawdown_limit:  Optional.  Passed to make_one_equity_sequence
+                    so that sequences stop once their drawdown
+                    exceeds it.  Whenever the tail risk is below
+                    drawdown_limit, it is the same Monte Carlo
+                    estimate, from the same draws, as without a
+                    limit.  When it is not, it is a lower bound of
+                    that estimate.
+    resampling_method:  Optional.  A ResamplingMethod.
+                    Default = None, IIDResampling.
+    percentile_method:  Optional.  One of PERCENTILE_METHODS.
+                    Default = 'linear'.
+    cash_flows:     Optional.  A CashFlowSchedule.
+                    Default = None, no cash flows.
+    cost_model:     Optional.  A CostModel.
+                    Default = None, no costs.
+    drawdown_in_dollars:  Optional.  Passed to
+                    make_one_equity_sequence.  Default = False.
+    position_sizing:  Optional.  A PositionSizing.
+                    Default = None, FixedFractionSizing.
+    execution_noise:  Optional.  An ExecutionNoise.
+                    Default = None, no noise.
+    backend:        Optional.  One of SIMULATION_BACKENDS.  The
+                    array backends ignore drawdown_limit, and draw
+                    every trade.
+                    Default = 'loop'.
+
+    Returns:
+    tail_risk:  The maximum drawdown at the tail_percentile
+                    of the distribution using the 
+                    current value of the position size.
+    """
+    equity_list = []
+    max_dd_list = []
+    all_trade_indices = draw_trade_indices(len(trades),
+                                           number_equity_in_CDF,
+                                           number_trades_in_forecast,
+                                           resampling_method)
+    if execution_noise is not None:
+        all_size_multipliers = execution_noise.draw_multipliers(
+                                   number_equity_in_CDF,
+                                   number_trades_in_forecast)
+    else:
+        all_size_multipliers = None
+
+    if use_array_backend(backend, cash_flows, cost_model, position_sizing):
+        equity_list, max_dd_list, _ = compute_equity_arrays(
+                                          trades,
+                                          fraction,
+                                          initial_capital,
+                                          all_trade_indices,
+                                          all_size_multipliers,
+                                          drawdown_in_dollars,
+                                          backend)
+    else:
+        for i in range(number_equity_in_CDF):
+            if execution_noise is not None:
+                size_multipliers = all_size_multipliers[i]
+            else:
+                size_multipliers = None
+            equity, max_drawdown = make_one_equity_sequence(
+                                    trades, 
+                                    fraction, 
+                                    number_days_in_forecast,
+                                    number_trades_in_forecast,
+                                    initial_capital,
+                                    drawdown_limit,
+                                    all_trade_indices[i],
+                                    None,
+                                    cash_flows,
+                                    None,
+                                    cost_model,
+                                    drawdown_in_dollars,
+                                    position_sizing,
+                                    size_multipliers)
+            equity_list.append(equity)
+            max_dd_list.append(max_drawdown)
+
+    sorted_max_dd = np.sort(max_dd_list)
+#    plt.plot(sorted_max_dd)
+#    plt.show()
+    tail_risk = compute_percentile(sorted_max_dd, 100 - tail_percentile,
+                                   percentile_method)
+
+    return tail_risk
+
+
+def analyze_probability_of_exceedance(
+    trades,
+    fraction,
+    number_days_in_forecast,
+    number_trades_in_forecast,
+    initial_capital,
+    drawdown_tolerance,
+    number_equity_in_CDF,
+    resampling_method = None    ):
+
+    """
+    Estimate the probability that the maximum drawdown of an
+    equity sequence exceeds the trader's drawdown tolerance.
+    Each sequence stops as soon as its drawdown exceeds the
+    tolerance, so aggressive fractions are cheap to evaluate.
+
+    Returns:
+    probability:  The proportion of equity sequences whose
+                    maximum drawdown exceeded drawdown_tolerance.
+    """
+    number_exceeding = 0
+    all_trade_indices = draw_trade_indices(len(trades),
+                                           number_equity_in_CDF,
+                                           number_trades_in_forecast,
+                                           resampling_method)
+
+    for i in range(number_equity_in_CDF):
+        equity, max_drawdown = make_one_equity_sequence(
+                                trades, 
+                                fraction, 
+                                number_days_in_forecast,
+                                number_trades_in_forecast,
+                                initial_capital,
+                                drawdown_tolerance,
+                                all_trade_indices[i])
+        if max_drawdown > drawdown_tolerance:
+            number_exceeding = number_exceeding + 1
+
+    probability = number_exceeding / number_equity_in_CDF
+
+    return probability
+
+
+class StreamingQuantileBands:
+
+    """
+    Estimates percentiles of equity at each point of many equity
+    curves, one curve at a time, without keeping the curves, by
+    the P-squared algorithm of Jain and Chlamtac.  Each percentile
+    keeps five markers per point, so memory depends on the number
+    of points, not on the number of curves.
+    
+    The first five curves are kept and give the markers their
+    starting heights.  With fewer than five curves, the percentiles
+    are computed exactly.
+    
+    Parameters:
+    percentiles:    the percentiles, each between 0 and 100.
+    number_points:  the number of points of each curve.
+    """
+
+    def __init__(self, percentiles, number_points):
+        self.percentiles = tuple(percentiles)
+        self.number_points = number_points
+        self.number_paths = 0
+        self.first_paths = []
+        #  for each percentile, the heights and positions of its five
+        #  markers, each a numpy array over the points
+        self.heights = {}
+        self.positions = {}
+
+    def add(self, path):
+
+        """
+        Add the equity at each point of one curve, a numpy array of
+        number_points values.
+        """
+
+        path = np.asarray(path, dtype = float)
+        self.number_paths = self.number_paths + 1
+        if self.number_paths <= 5:
+            self.first_paths.append(path)
+            if self.number_paths == 5:
+                first = list(np.sort(np.array(self.first_paths), axis = 0))
+                for percentile in self.percentiles:
+                    self.heights[percentile] = [row.copy() for row in first]
+                    self.positions[percentile] = [
+                        np.full(self.number_points, float(position))
+                        for position in range(1, 6)]
+            return
+
+        for percentile in self.percentiles:
+            p = percentile / 100
+            q = self.heights[percentile]
+            n = self.positions[percentile]
+            q[0] = np.minimum(q[0], path)
+            q[4] = np.maximum(q[4], path)
+            #  the cell of the markers the value falls in, from 0 to 3
+            k = ((path >= q[1]).astype(int) + (path >= q[2]).astype(int) +
+                 (path >= q[3]).astype(int))
+            for j in range(1, 5):
+                n[j] = n[j] + (k < j).astype(float)
+            #  where each marker should be after number_paths values
+            desired = [1.0,
+                       1.0 + (self.number_paths - 1) * p / 2,
+                       1.0 + (self.number_paths - 1) * p,
+                       1.0 + (self.number_paths - 1) * (1.0 + p) / 2,
+                       float(self.number_paths)]
+            for i in (1, 2, 3):
+                d = desired[i] - n[i]
+                up = (d >= 1.0) & (n[i + 1] - n[i] > 1.0)
+                down = (d <= -1.0) & (n[i - 1] - n[i] < -1.0)
+                s = np.where(up, 1.0, -1.0)
+                parabolic = q[i] + s / (n[i + 1] - n[i - 1]) * (
+                    (n[i] - n[i - 1] + s) * (q[i + 1] - q[i]) /
+                        (n[i + 1] - n[i]) +
+                    (n[i + 1] - n[i] - s) * (q[i] - q[i - 1]) /
+                        (n[i] - n[i - 1]))
+                neighbour_height = np.where(up, q[i + 1], q[i - 1])
+                neighbour_position = np.where(up, n[i + 1], n[i - 1])
+                linear = q[i] + s * (neighbour_height - q[i]) / (
+                             neighbour_position - n[i])
+                moved = np.where((q[i - 1] < parabolic) &
+                                 (parabolic < q[i + 1]),
+                                 parabolic, linear)
+                q[i] = np.where(up | down, moved, q[i])
+                n[i] = n[i] + np.where(up | down, s, 0.0)
+
+    def bands(self):
+
+        """
+        Returns:
+        dictionary from each percentile to a numpy array of its
+        estimate at each point.
+        """
+
+        if self.number_paths >= 5:
+            return {percentile: self.heights[percentile][2].copy()
+                    for percentile in self.percentiles}
+
+        return {percentile: np.array([
+                    compute_percentile([path[point]
+                                        for path in self.first_paths],
+                                       percentile)
+                    for point in range(self.number_points)])
+                for percentile in self.percentiles}
+
+
+def form_distribution_of_equity(
+    trades,
+    fraction,
+    number_days_in_forecast,
+    number_trades_in_forecast,
+    initial_capital,
+    number_equity_in_CDF,
+    resampling_method = None,
+    ratio_lists = None,
+    trading_days_per_year = 252,
+    drawdown_list = None,
+    cash_flows = None,
+    peak_list = None,
+    cost_model = None,
+    position_sizing = None,
+    execution_noise = None,
+    curve_percentiles = (),
+    curve_points = 100,
+    curve_list = None,
+    draws_list = None,
+    backend = 'loop',
+    band_percentiles = (),
+    band_list = None,
+    streak_lists = None,
+    benchmark = None,
+    benchmark_lists = None  ):
+
+    """
+    ratio_lists:  Optional.  A dictionary to which the Sharpe,
+                    Sortino, Calmar, Ulcer, and Martin ratios of
+                    each equity sequence are appended, under those
+                    names.
+                    See compute_path_ratios.
+    trading_days_per_year:  Optional.  Used to annualize the ratios.
+                    Default = 252.
+    drawdown_list:  Optional.  A list to which the maximum drawdown
+                    of each equity sequence is appended.
+    cash_flows:     Optional.  A CashFlowSchedule.
+                    Default = None, no cash flows.
+    peak_list:      Optional.  A list to which the highest equity
+                    of each equity sequence is appended.
+    cost_model:     Optional.  A CostModel.
+                    Default = None, no costs.
+    position_sizing:  Optional.  A PositionSizing.
+                    Default = None, FixedFractionSizing.
+    execution_noise:  Optional.  An ExecutionNoise.
+                    Default = None, no noise.
+    curve_percentiles:  Optional.  Percentiles of terminal equity
+                    whose equity curves, thinned to curve_points
+                    points, are appended to curve_list as
+                    EquityCurve.  Default = (), none.
+    draws_list:     Optional.  A list to which the trade indices
+                    drawn, with a row for each equity sequence, and
+                    the size multipliers of execution_noise, or None,
+                    are appended as a pair.
+    backend:        Optional.  One of SIMULATION_BACKENDS.  The
+                    sequences of ratio_lists, band_percentiles,
+                    streak_lists, and benchmark_lists are always
+                    formed one by one.  Default = 'loop'.
+    band_percentiles:  Optional.  Percentiles of equity at each
+                    point, over every sequence, estimated by
+                    StreamingQuantileBands on the sequences thinned
+                    to curve_points points and appended to band_list
+                    as EquityBands.  Default = (), none.
+    streak_lists:   Optional.  A dictionary to which the longest
+                    losing streak in trades and in days, and the
+                    days to recover from the maximum drawdown, of
+                    each equity sequence are appended, under
+                    'losing_trades', 'losing_days', and
+                    'recovery_days'.  See compute_path_streaks.
+    benchmark:      Optional.  The benchmark's return during each
+                    trade, drawn with the trades.
+    benchmark_lists:  Optional.  A dictionary to which the alpha,
+                    beta, and relative drawdown of each equity
+                    sequence against benchmark are appended, under
+                    'alpha', 'beta', and 'relative_drawdown'.
+                    See compute_path_benchmark_metrics.
+
+    Returns:
+    sorted_equity:  The equity at the end of each sequence, sorted.
+    """
+    
+#    plt.hist(trades,bins=50)
+#    plt.show()
+    equity_list = []
+    max_dd_list = []
+    all_trade_indices = draw_trade_indices(len(trades),
+                                           number_equity_in_CDF,
+                                           number_trades_in_forecast,
+                                           resampling_method)
+    if execution_noise is not None:
+        all_size_multipliers = execution_noise.draw_multipliers(
+                                   number_equity_in_CDF,
+                                   number_trades_in_forecast)
+    else:
+        all_size_multipliers = None
+    if draws_list is not None:
+        draws_list.append((all_trade_indices, all_size_multipliers))
+
+    days_per_trade = number_days_in_forecast / number_trades_in_forecast
+    points = [round(k * number_trades_in_forecast / (curve_points - 1))
+              for k in range(curve_points)]
+    if len(band_percentiles) > 0:
+        band_accumulator = StreamingQuantileBands(band_percentiles,
+                                                  curve_points)
+    else:
+        band_accumulator = None
+
+    if (ratio_lists is None and band_accumulator is None and
+            streak_lists is None and benchmark_lists is None and
+            use_array_backend(backend, cash_flows, cost_model,
+                              position_sizing)):
+        equity_list, max_dd_list, peaks = compute_equity_arrays(
+                                              trades,
+                                              fraction,
+                                              initial_capital,
+                                              all_trade_indices,
+                                              all_size_multipliers,
+                                              False,
+                                              backend)
+        if peak_list is not None:
+            peak_list.extend(peaks)
+    else:
+        for i in range(number_equity_in_CDF):
+            if execution_noise is not None:
+                size_multipliers = all_size_multipliers[i]
+            else:
+                size_multipliers = None
+            if (ratio_lists is not None or band_accumulator is not None or
+                    streak_lists is not None or benchmark_lists is not None):
+                equity_path = [initial_capital]
+            else:
+                equity_path = None
+            equity, max_drawdown = make_one_equity_sequence(
+                                    trades, 
+                                    fraction, 
+                                    number_days_in_forecast,
+                                    number_trades_in_forecast,
+                                    initial_capital,
+                                    None,
+                                    all_trade_indices[i],
+                                    None,
+                                    cash_flows,
+                                    peak_list,
+                                    cost_model,
+                                    False,
+                                    position_sizing,
+                                    size_multipliers,
+                                    equity_path)
+            equity_list.append(equity)
+            max_dd_list.append(max_drawdown)
+            if ratio_lists is not None:
+                ratios = compute_path_ratios(
+                             trades,
+                             fraction,
+                             all_trade_indices[i],
+                             equity,
+                             max_drawdown,
+                             number_days_in_forecast,
+                             number_trades_in_forecast,
+                             initial_capital,
+                             trading_days_per_year,
+                             cost_model,
+                             position_sizing,
+                             size_multipliers,
+                             equity_path)
+                for name in ratios:
+                    ratio_lists.setdefault(name, []).append(ratios[name])
+            if streak_lists is not None:
+                #  a ruined sequence ends with its loss to 0
+                if len(equity_path) < number_trades_in_forecast + 1:
+                    streaks = compute_path_streaks(equity_path + [equity],
+                                                   days_per_trade)
+                else:
+                    streaks = compute_path_streaks(equity_path,
+                                                   days_per_trade)
+                for name, value in zip(('losing_trades', 'losing_days',
+                                        'recovery_days'), streaks):
+                    streak_lists.setdefault(name, []).append(value)
+            if benchmark_lists is not None:
+                if len(equity_path) < number_trades_in_forecast + 1:
+                    path = equity_path + [equity]
+                else:
+                    path = equity_path
+                metrics = compute_path_benchmark_metrics(
+                              path,
+                              [benchmark[trade_index] for trade_index
+                               in all_trade_indices[i]],
+                              trading_days_per_year / days_per_trade)
+                for name, value in zip(('alpha', 'beta',
+                                        'relative_drawdown'), metrics):
+                    benchmark_lists.setdefault(name, []).append(value)
+            if band_accumulator is not None:
+                #  a ruined sequence stays at 0 to the end
+                equity_path = equity_path + [equity] * (
+                    number_trades_in_forecast + 1 - len(equity_path))
+                band_accumulator.add([equity_path[point]
+                                      for point in points])
+
+    if band_accumulator is not None:
+        band_list.append(EquityBands(np.array([point * days_per_trade
+                                               for point in points]),
+                                     band_accumulator.bands(),
+                                     number_equity_in_CDF))
+
+    if drawdown_list is not None:
+        drawdown_list.extend(max_dd_list)
+
+    #  The curves are formed again, from the same trades, for the
+    #  sequences whose terminal equity has the rank of each percentile.
+    #  A ruined sequence stays at 0 to the end.
+
+    order = np.argsort(equity_list)
+    for percentile in curve_percentiles:
+        i = order[round(percentile / 100 * (number_equity_in_CDF - 1))]
+        if execution_noise is not None:
+            size_multipliers = all_size_multipliers[i]
+        else:
+            size_multipliers = None
+        equity_path = [initial_capital]
+        equity, _ = make_one_equity_sequence(trades,
+                                             fraction,
+                                             number_days_in_forecast,
+                                             number_trades_in_forecast,
+                                             initial_capital,
+                                             None,
+                                             all_trade_indices[i],
+                                             None,
+                                             cash_flows,
+                                             None,
+                                             cost_model,
+                                             False,
+                                             position_sizing,
+                                             size_multipliers,
+                                             equity_path)
+        equity_path.extend([equity] *
+                           (number_trades_in_forecast + 1 - len(equity_path)))
+        curve_list.append(EquityCurve(
+                              percentile,
+                              np.array([point * days_per_trade
+                                        for point in points]),
+                              np.array([equity_path[point]
+                                        for point in points])))
+
+    sorted_equity = np.sort(equity_list)
+#    plt.plot(sorted_equity)
+#    plt.show()
+
+    return sorted_equity
+
+
+def compute_path_streaks(
+    equity_path,
+    days_per_trade = 1.0    ):
+
+    """
+    The longest losing streak of an equity sequence, and the time
+    it took to recover from its maximum drawdown.
+    
+    Parameters:
+    equity_path:      the initial equity followed by the equity after
+                      each trade.
+    days_per_trade:   Optional.  Default = 1.0
+    
+    Returns:
+    losing_trades:    the longest run of trades after each of which
+                      the equity fell.
+    losing_days:      that run in days.
+    recovery_days:    the days from the lowest point of the maximum
+                      drawdown until the equity was back at the
+                      high before it, 0.0 if there was no drawdown,
+                      or None if it had not recovered by the end.
+    """
+
+    losing_trades = 0
+    streak = 0
+    max_equity = equity_path[0]
+    peak_before_trough = max_equity
+    trough = 0
+    max_drawdown = 0.0
+    for i in range(1, len(equity_path)):
+        if equity_path[i] < equity_path[i - 1]:
+            streak = streak + 1
+            losing_trades = max(losing_trades, streak)
+        else:
+            streak = 0
+        if equity_path[i] >= max_equity:
+            max_equity = equity_path[i]
+        elif (max_equity - equity_path[i]) / max_equity > max_drawdown:
+            max_drawdown = (max_equity - equity_path[i]) / max_equity
+            peak_before_trough = max_equity
+            trough = i
+
+    if max_drawdown == 0.0:
+        recovery_days = 0.0
+    else:
+        recovery_days = None
+        for i in range(trough + 1, len(equity_path)):
+            if equity_path[i] >= peak_before_trough:
+                recovery_days = (i - trough) * days_per_trade
+                break
+
+    return (losing_trades, losing_trades * days_per_trade, recovery_days)
+
+
+def compute_path_ratios(
+    trades,
+    fraction,
+    trade_indices,
+    equity,
+    max_drawdown,
+    number_days_in_forecast,
+    number_trades_in_forecast,
+    initial_capital,
+    trading_days_per_year = 252,
+    cost_model = None,
+    position_sizing = None,
+    size_multipliers = None,
+    equity_path = None  ):
+
+    """
+    Compute the risk adjusted returns of one equity sequence.
+    The return of each trade to the account is the trade times the
+    position that position_sizing, if any, or else fraction, sets
+    for an account of initial_capital, times its size_multipliers,
+    if any, less the costs of cost_model,
+    if any, as a proportion of initial_capital.  There are trading_days_per_year *
+    number_trades_in_forecast / number_days_in_forecast
+    trades per year.  The risk free rate is taken to be 0.
+    
+    Returns:
+    dictionary with
+    Sharpe:   mean trade return over its standard deviation,
+              annualized.
+    Sortino:  mean trade return over its downside deviation,
+              annualized.
+    Calmar:   compound annual rate of return over the maximum
+              drawdown.
+    and, if equity_path, the equity before the first trade and after
+    each trade, is given,
+    Ulcer:    the Ulcer Index, the root mean square of the drawdown,
+              as a proportion of the highest equity to date, before
+              the first trade and after each trade.  Long drawdowns
+              raise it more than quick recoveries do.
+    Martin:   compound annual rate of return over the Ulcer Index.
+    A ratio whose denominator is 0 is math.inf, or 0.0 if its
+    numerator is also 0.
+    """
+
+    def ratio(numerator, denominator):
+        if denominator > 0.0:
+            return numerator / denominator
+        if numerator == 0.0:
+            return 0.0
+        return math.copysign(math.inf, numerator)
+
+    if position_sizing is None:
+        positions = [fraction * initial_capital] * len(trade_indices)
+    else:
+        positions = []
+        previous_trades = []
+        for trade_index in trade_indices:
+            positions.append(position_sizing.position(initial_capital,
+                                                      fraction,
+                                                      initial_capital,
+                                                      previous_trades))
+            previous_trades.append(trades[trade_index])
+    if size_multipliers is not None:
+        positions = [position * multiplier
+                     for position, multiplier in zip(positions,
+                                                     size_multipliers)]
+    if (cost_model is None and position_sizing is None and
+            size_multipliers is None):
+        returns = [fraction * trades[trade_index]
+                   for trade_index in trade_indices]
+    elif cost_model is None:
+        returns = [position * trades[trade_index] / initial_capital
+                   for position, trade_index in zip(positions,
+                                                    trade_indices)]
+    else:
+        returns = [cost_model.net_gain(position, 1.0,
+                                       trades[trade_index]) /
+                   initial_capital
+                   for position, trade_index in zip(positions,
+                                                    trade_indices)]
+    trades_per_year = (trading_days_per_year * number_trades_in_forecast /
+                       number_days_in_forecast)
+    mean_return = statistics.mean(returns)
+    if len(returns) > 1:
+        stdev_return = statistics.stdev(returns)
+    else:
+        stdev_return = 0.0
+    downside_deviation = math.sqrt(
+                             statistics.mean([min(r, 0.0) ** 2
+                                              for r in returns]))
+    CAR = compute_CAR(equity, initial_capital,
+                      number_days_in_forecast,
+                      trading_days_per_year) / 100.0
+
+    ratios = {
+        'Sharpe': ratio(mean_return, stdev_return) *
+                      math.sqrt(trades_per_year),
+        'Sortino': ratio(mean_return, downside_deviation) *
+                       math.sqrt(trades_per_year),
+        'Calmar': ratio(CAR, max_drawdown)}
+
+    if equity_path is not None:
+        #  a ruined sequence stops early, and stays at its equity, 0,
+        #  to the end
+        path = (list(equity_path) +
+                [equity] * (number_trades_in_forecast + 1 - len(equity_path)))
+        max_equity = path[0]
+        squared_drawdowns = []
+        for point in path:
+            max_equity = max(max_equity, point)
+            squared_drawdowns.append(((max_equity - point) / max_equity) ** 2)
+        ulcer_index = math.sqrt(statistics.mean(squared_drawdowns))
+        ratios['Ulcer'] = ulcer_index
+        ratios['Martin'] = ratio(CAR, ulcer_index)
+
+    return ratios
+
+
+def compute_path_benchmark_metrics(
+    equity_path,
+    benchmark_returns,
+    trades_per_year = 252.0 ):
+
+    """
+    Compare an equity sequence with a benchmark over the same
+    trades.
+    
+    Parameters:
+    equity_path:      the initial equity followed by the equity after
+                      each trade.  A sequence that was ruined ends
+                      with its fall to 0.
+    benchmark_returns:  the benchmark's return during each trade.
+    trades_per_year:  Optional.  Used to annualize alpha.
+                      Default = 252.0
+    
+    Returns:
+    alpha:    the mean return per trade not explained by the
+              benchmark, annualized.
+    beta:     the covariance of the sequence's returns with the
+              benchmark's, over the variance of the benchmark's.
+              0.0 if the benchmark's returns do not vary.
+    relative_drawdown:  the maximum drawdown of the equity relative
+              to the benchmark, that is of the equity divided by
+              what the initial equity would have grown to in the
+              benchmark.
+    """
+
+    returns = [after / before - 1.0
+               for before, after in zip(equity_path[:-1], equity_path[1:])]
+    benchmark_returns = [float(value)
+                         for value in benchmark_returns[:len(returns)]]
+
+    mean_return = statistics.mean(returns)
+    mean_benchmark = statistics.mean(benchmark_returns)
+    variance = statistics.mean([(value - mean_benchmark) ** 2
+                                for value in benchmark_returns])
+    covariance = statistics.mean([(r - mean_return) * (b - mean_benchmark)
+                                  for r, b in zip(returns,
+                                                  benchmark_returns)])
+    if variance > 0.0:
+        beta = covariance / variance
+    else:
+        beta = 0.0
+    alpha = (mean_return - beta * mean_benchmark) * trades_per_year
+
+    benchmark_equity = equity_path[0]
+    max_relative = 1.0
+    relative_drawdown = 0.0
+    for equity, benchmark_return in zip(equity_path[1:], benchmark_returns):
+        benchmark_equity = benchmark_equity * (1.0 + benchmark_return)
+        relative = equity / benchmark_equity
+        if relative >= max_relative:
+            max_relative = relative
+        else:
+            relative_drawdown = max(relative_drawdown,
+                                    (max_relative - relative) / max_relative)
+
+    return (alpha, beta, relative_drawdown)
+
+
+TRADING_DAYS_PER_YEAR = {'futures': 252, 'crypto': 365, 'forex': 260}
+
+
+def compute_CAR(
+    equity,
+    initial_capital,
+    number_days_in_forecast,
+    trading_days_per_year = 252 ):
+
+    """
+    Convert the equity at the end of the forecast period to a
+    compound annual rate of return, in percent, with
+    trading_days_per_year days in a year.
+    equity may be a scalar or a numpy array.
+    An account that has lost everything, or more, has a
+    rate of return of -100 percent.
+    """
+
+    relative_equity = np.maximum(equity / initial_capital, 0.0)
+    CAR = 100.0 * (relative_equity ** (trading_days_per_year /
+                                       number_days_in_forecast)
+                   - 1.0)
+
+    return CAR
+
+
+class SummaryStats:
+
+    """
+    Summary statistics of a list of values, such as safe-f from
+    each repetition.
+    
+    Unpacks as mean and stdev, so that
+        mean, stdev = compute_statistics(values)
+    works.
+    
+    Attributes:
+    mean:     the mean.
+    stdev:    the standard deviation, 0.0 for fewer than 3 values.
+    min:      the smallest value.
+    max:      the largest value.
+    median:   the median.
+    n:        the number of values.
+    """
+
+    def __init__(self,
+                 mean,
+                 stdev,
+                 min,
+                 max,
+                 median,
+                 n):
+        self.mean = mean
+        self.stdev = stdev
+        self.min = min
+        self.max = max
+        self.median = median
+        self.n = n
+
+    def __iter__(self):
+        return iter((self.mean, self.stdev))
+
+    def __repr__(self):
+        return (f'SummaryStats(mean={self.mean:0.4f}, '
+                f'stdev={self.stdev:0.4f}, min={self.min:0.4f}, '
+                f'max={self.max:0.4f}, median={self.median:0.4f}, '
+                f'n={self.n})')
+
+
+def compute_statistics(
+    values  ):
+
+    """
+    The summary statistics of the values from each repetition.
+    The standard deviation is not meaningful for fewer than 3
+    repetitions, and is 0.0 then.
+    
+    Returns:
+    SummaryStats
+    """
+
+    mean = statistics.mean(values)
+    if len(values) > 2:
+        stdev = statistics.stdev(values)
+    else:
+        stdev = 0.0
+
+    return SummaryStats(mean, stdev, min(values), max(values),
+                        statistics.median(values), len(values))
+
+
+def compute_mean_and_stdev(
+    values  ):
+
+    """
+    Deprecated, and to be removed in the next release.  Use
+    compute_statistics, which unpacks the same way.
+    
+    Returns:
+    mean, stdev
+    """
+
+    warnings.warn('compute_mean_and_stdev is deprecated, use '
+                  'compute_statistics', DeprecationWarning, stacklevel = 2)
+
+    return tuple(compute_statistics(values))
+
+
+PERCENTILE_METHODS = ('linear', 'nearest_rank', 'hazen', 'weibull')
+
+
+def compute_percentile(
+    values,
+    percentile,
+    method = 'linear'   ):
+
+    """
+    Estimate a percentile of a sample.
+    
+    Parameters:
+    values:     the sample, in any order.
+    percentile: from 0 to 100.
+    method:     one of PERCENTILE_METHODS.  See percentile_method
+                in RiskNormalizationConfig.
+    
+    Returns:
+    the estimated value at percentile.
+    """
+
+    sorted_values = np.sort(values)
+    number_values = len(sorted_values)
+    p = percentile / 100.0
+
+    #  position is the zero based rank of the estimate
+    if method == 'nearest_rank':
+        return sorted_values[max(math.ceil(p * number_values) - 1, 0)]
+    elif method == 'linear':
+        position = p * (number_values - 1)
+    elif method == 'hazen':
+        position = p * number_values - 0.5
+    elif method == 'weibull':
+        position = p * (number_values + 1) - 1.0
+    else:
+        raise InvalidParameterError('percentile_method', method,
+                  f'must be one of {", ".join(PERCENTILE_METHODS)}')
+
+    position = min(max(position, 0.0), number_values - 1)
+    lower = math.floor(position)
+    upper = min(lower + 1, number_values - 1)
+    weight = position - lower
+    low_value = sorted_values[lower]
+    high_value = sorted_values[upper]
+
+    #  Values may be infinite, such as the ratios of an equity curve
+    #  with no drawdown.  Between a finite value and an infinite one
+    #  the estimate is the infinite one, rather than the nan that
+    #  inf - inf or 0 * inf would give.
+    if weight == 0.0 or low_value == high_value:
+        return low_value
+    if math.isinf(low_value) and math.isinf(high_value):
+        return low_value if weight < 0.5 else high_value
+    if math.isinf(low_value):
+        return low_value
+    if math.isinf(high_value):
+        return high_value
+    
+    return low_value + weight * (high_value - low_value)
+
+
+def compute_histogram(
+    values,
+    number_bins = None  ):
+
+    """
+    Bin a sample, such as the CAR or maximum drawdown of every
+    equity curve.
+    
+    Parameters:
+    values:       the sample, in any order.
+    number_bins:  Optional.  Default = None, chosen by the
+                  Freedman-Diaconis rule, bins of width twice the
+                  interquartile range over the cube root of the
+                  number of values, and no more bins than values.
+    
+    Returns:
+    Histogram
+    """
+
+    if number_bins is None:
+        low = min(values)
+        high = max(values)
+        width = (2.0 * (compute_percentile(values, 75) -
+                        compute_percentile(values, 25)) /
+                 len(values) ** (1.0 / 3.0))
+        if width > 0.0:
+            number_bins = min(max(math.ceil((high - low) / width), 1),
+                              len(values))
+        else:
+            number_bins = 1
+
+    counts, edges = np.histogram(values, bins=number_bins)
+
+    return Histogram(edges, counts)
+
+
+def choose_number_equity_in_CDF(
+    sample_size_schedule,
+    distance_from_tolerance,
+    number_equity_in_CDF    ):
+
+    """
+    Choose how many equity curves to use for the next pass of
+    the search for safe-f.
+    
+    Parameters:
+    sample_size_schedule:  list of (distance, number_equity) pairs,
+                    ordered from largest distance to smallest.
+                    While the tail risk of the previous pass was
+                    farther than distance from the drawdown
+                    tolerance, use number_equity curves.
+                    None always uses number_equity_in_CDF.
+    distance_from_tolerance:  absolute difference between the tail
+                    risk of the previous pass and the drawdown
+                    tolerance.  math.inf before the first pass.
+    number_equity_in_CDF:  the full number of equity curves.
+    
+    Returns:
+    number_equity:  the number of equity curves for the next pass.
+                    Never more than number_equity_in_CDF.
+    """
+
+    if sample_size_schedule is not None:
+        for distance, number_equity in sample_size_schedule:
+            if distance_from_tolerance > distance:
+                return min(number_equity, number_equity_in_CDF)
+
+    return number_equity_in_CDF
+
+
+RANDOM_ALGORITHMS = ('MT19937', 'PCG64', 'Philox', 'SFC64')
+
+#  The generators the simulation draws from, one for each thread,
+#  set by seed_random_generator, so that runs in different threads,
+#  such as those of risk_normalization_async, do not draw from each
+#  other's sequences.  A thread that has not seeded one draws from
+#  numpy's global MT19937 generator.
+random_generators = threading.local()
+
+
+def current_random_generator():
+
+    """
+    The random number generator of this thread: numpy's global
+    generator, a numpy.random.RandomState, or a
+    numpy.random.Generator.
+    """
+
+    return getattr(random_generators, 'generator', np.random)
+
+
+def seed_random_generator(
+    seed,
+    algorithm = 'MT19937',
+    *keys   ):
+
+    """
+    Seed the random number generator of this thread.
+    
+    Parameters:
+    seed:       the seed.  With keys, the seed is
+                derive_seed(seed, *keys).  None draws from numpy's
+                global generator as it is, or starts another
+                algorithm from fresh entropy.
+    algorithm:  one of RANDOM_ALGORITHMS.  MT19937 is a
+                numpy.random.RandomState, the generator behind
+                numpy's global one, whose sequence for a seed numpy
+                keeps the same from release to release.  The others are numpy
+                bit generators, drawn through a numpy.random.Generator:
+                PCG64 and SFC64 are faster, and Philox is a counter
+                based generator.  Needs numpy 1.17 or later.
+    """
+
+    if seed is not None and len(keys) > 0:
+        seed = derive_seed(seed, *keys)
+    if algorithm == 'MT19937':
+        if seed is None:
+            random_generators.generator = np.random
+        else:
+            random_generators.generator = np.random.RandomState(seed)
+    else:
+        bit_generator = getattr(np.random, algorithm)(seed)
+        random_generators.generator = np.random.Generator(bit_generator)
+
+
+def random_integers(
+    low,
+    high,
+    size = None ):
+
+    """
+    Random integers from low to high, excluding high, from
+    the random number generator of this thread, whichever its
+    algorithm.
+    """
+
+    generator = current_random_generator()
+    if isinstance(generator, np.random.Generator):
+        return generator.integers(low, high, size=size)
+
+    return generator.randint(low, high, size=size)
+
+
+def random_floats(
+    size = None ):
+
+    """
+    Random numbers from 0.0 to 1.0, excluding 1.0, from the random
+    number generator of this thread, whichever its algorithm.
+    """
+
+    generator = current_random_generator()
+    if isinstance(generator, np.random.Generator):
+        return generator.random(size)
+
+    return generator.random_sample(size)
+
+
+def derive_seed(
+    *keys   ):
+
+    """
+    Derive a seed for the random number generator from a master
+    seed and the place of a task within a run, such as
+    derive_seed(seed, pass_number, candidate).
+    The same keys always give the same seed, on any platform, so
+    results do not depend on which process runs which task.
+    """
+
+    digest = hashlib.sha256(repr(keys).encode('utf-8')).digest()
+
+    return int.from_bytes(digest[:4], 'little') & 0x7fffffff
+
+
+def analyze_drawdown_with_seed(
+    seed,
+    trades,
+    fraction,
+    number_days_in_forecast,
+    number_trades_in_forecast,
+    initial_capital,
+    tail_percentile,
+    number_equity_in_CDF,
+    drawdown_limit,
+    resampling_method = None,
+    percentile_method = 'linear',
+    cash_flows = None,
+    cost_model = None,
+    drawdown_in_dollars = False,
+    position_sizing = None,
+    execution_noise = None,
+    random_algorithm = 'MT19937',
+    backend = 'loop'    ):
+
+    """
+    Seed the random number generator, with random_algorithm, then
+    call analyze_distribution_of_drawdown.
+    Worker processes start with a copy of the same generator
+    state, so each task is given its own seed.
+    """
+
+    seed_random_generator(seed, random_algorithm)
+
+    return analyze_distribution_of_drawdown(
+               trades,
+               fraction,
+               number_days_in_forecast,
+               number_trades_in_forecast,
+               initial_capital,
+               tail_percentile,
+               number_equity_in_CDF,
+               drawdown_limit,
+               resampling_method,
+               percentile_method,
+               cash_flows,
+               cost_model,
+               drawdown_in_dollars,
+               position_sizing,
+               execution_noise,
+               backend)
+
+
+def analyze_several_fractions(
+    executor,
+    fractions,
+    trades,
+    number_days_in_forecast,
+    number_trades_in_forecast,
+    initial_capital,
+    tail_percentile,
+    number_equity_in_CDF,
+    drawdown_limit,
+    resampling_method = None,
+    percentile_method = 'linear',
+    seeds = None,
+    cash_flows = None,
+    cost_model = None,
+    drawdown_in_dollars = False,
+    position_sizing = None,
+    execution_noise = None,
+    random_algorithm = 'MT19937',
+    backend = 'loop',
+    compute_usage = None    ):
+
+    """
+    Compute the tail risk of several candidate fractions.
+    
+    Parameters:
+    executor:   a concurrent.futures executor used to evaluate the
+                fractions at the same time.  None evaluates them
+                one after another in this process.
+    fractions:  list of candidate fractions.
+    seeds:      Optional.  A seed for each fraction.  Default = None,
+                draw them from the random number generator, except
+                that fractions evaluated in this process continue
+                its sequence.
+    compute_usage:  Optional.  A ComputeUsage to which the processor
+                time of the executor's tasks is added.  Fractions
+                evaluated in this process count in its own time.
+    The remaining parameters are those of
+    analyze_distribution_of_drawdown.
+    
+    Returns:
+    tail_risks:  list with the tail risk of each fraction.
+    """
+
+    if executor is None and seeds is not None:
+        return [analyze_drawdown_with_seed(
+                    seeds[i],
+                    trades,
+                    fractions[i],
+                    number_days_in_forecast,
+                    number_trades_in_forecast,
+                    initial_capital,
+                    tail_percentile,
+                    number_equity_in_CDF,
+                    drawdown_limit,
+                    resampling_method,
+                    percentile_method,
+                    cash_flows,
+                    cost_model,
+                    drawdown_in_dollars,
+                    position_sizing,
+                    execution_noise,
+                    random_algorithm,
+                    backend) for i in range(len(fractions))]
+
+    if executor is None:
+        return [analyze_distribution_of_drawdown(
+                    trades,
+                    fraction,
+                    number_days_in_forecast,
+                    number_trades_in_forecast,
+                    initial_capital,
+                    tail_percentile,
+                    number_equity_in_CDF,
+                    drawdown_limit,
+                    resampling_method,
+                    percentile_method,
+                    cash_flows,
+                    cost_model,
+                    drawdown_in_dollars,
+                    position_sizing,
+                    execution_noise,
+                    backend) for fraction in fractions]
+
+    if seeds is None:
+        seeds = random_integers(0, 2**31 - 1, size=len(fractions))
+    futures = []
+    for i in range(len(fractions)):
+        futures.append(executor.submit(
+                           run_timed,
+                           analyze_drawdown_with_seed,
+                           int(seeds[i]),
+                           trades,
+                           fractions[i],
+                           number_days_in_forecast,
+                           number_trades_in_forecast,
+                           initial_capital,
+                           tail_percentile,
+                           number_equity_in_CDF,
+                           drawdown_limit,
+                           resampling_method,
+                           percentile_method,
+                           cash_flows,
+                           cost_model,
+                           drawdown_in_dollars,
+                           position_sizing,
+                           execution_noise,
+                           random_algorithm,
+                           backend))
+
+    tail_risks = []
+    for future in futures:
+        tail_risk, cpu_seconds = future.result()
+        tail_risks.append(tail_risk)
+        if compute_usage is not None:
+            compute_usage.cpu_seconds = (compute_usage.cpu_seconds +
+                                         cpu_seconds)
+
+    return tail_risks
+
+
+def run_timed(
+    function,
+    *arguments  ):
+
+    """
+    Call function, in whatever process runs it.
+    
+    Returns:
+    the value of the function, and the processor time it took in
+    seconds, as a pair.
+    """
+
+    started = time.process_time()
+    value = function(*arguments)
+
+    return (value, time.process_time() - started)
+
+
+def choose_candidate_fractions(
+    fraction,
+    number_candidates,
+    distance_from_tolerance,
+    drawdown_tolerance  ):
+
+    """
+    Spread number_candidates fractions around the current estimate
+    of safe-f.  The spread is at most 20 percent either side and
+    narrows as the tail risk approaches the drawdown tolerance.
+    A single candidate is the current estimate itself.
+    """
+
+    if number_candidates <= 1:
+        return [fraction]
+
+    spread = min(0.20, distance_from_tolerance / drawdown_tolerance)
+    multipliers = np.linspace(1.0 - spread, 1.0 + spread,
+                              number_candidates)
+
+    return [fraction * multiplier for multiplier in multipliers]
+
+
+def next_fraction_estimate(
+    fractions,
+    tail_risks,
+    drawdown_tolerance  ):
+
+    """
+    Estimate the fraction whose tail risk equals the drawdown
+    tolerance from the candidates evaluated in one pass.
+    If two neighboring candidates bracket the tolerance, 
+    interpolate between them.  Otherwise scale the candidate
+    closest to the tolerance in proportion, as the single
+    fraction search does.
+    """
+
+    for i in range(len(fractions) - 1):
+        low_risk = tail_risks[i]
+        high_risk = tail_risks[i + 1]
+        if low_risk < drawdown_tolerance < high_risk:
+            weight = (drawdown_tolerance - low_risk) / (high_risk - low_risk)
+            return fractions[i] + weight * (fractions[i + 1] - fractions[i])
+
+    distances = [abs(tail_risk - drawdown_tolerance) 
+                    for tail_risk in tail_risks]
+    closest = distances.index(min(distances))
+
+    return fractions[closest] * drawdown_tolerance / tail_risks[closest]
+
+
+def risk_normalization(
+        trades, 
+        number_days_in_forecast, 
+        number_trades_in_forecast,
+        initial_capital, 
+        tail_percentile, 
+        drawdown_tolerance, 
+        number_equity_in_CDF,
+        number_repetitions,
+        **options
+        ):
+
+    #  The original calling sequence.
+    #  The optional parameters are given by name in options.
+
+    config = RiskNormalizationConfig.from_options(
+                 number_days_in_forecast = number_days_in_forecast,
+                 number_trades_in_forecast = number_trades_in_forecast,
+                 initial_capital = initial_capital,
+                 tail_percentile = tail_percentile,
+                 drawdown_tolerance = drawdown_tolerance,
+                 number_equity_in_CDF = number_equity_in_CDF,
+                 number_repetitions = number_repetitions,
+                 **options)
+
+    return risk_normalization_with_config(trades, config)
+
+
+class DeferredFuture(concurrent.futures.Future):
+
+    """
+    A future whose task runs, in this process, when its result is
+    first asked for.  One that is cancelled before then never runs.
+    """
+
+    def __init__(self, function, args, kwargs):
+        super().__init__()
+        self.task = (function, args, kwargs)
+
+    def run(self):
+        if self.task is None:
+            return
+        function, args, kwargs = self.task
+        self.task = None
+        if not self.set_running_or_notify_cancel():
+            return
+        try:
+            self.set_result(function(*args, **kwargs))
+        except Exception as error:
+            self.set_exception(error)
+
+    def result(self, timeout = None):
+        self.run()
+        return super().result(timeout)
+
+    def exception(self, timeout = None):
+        self.run()
+        return super().exception(timeout)
+
+
+class SequentialExecutor(concurrent.futures.Executor):
+
+    """
+    A concurrent.futures executor that runs each task in this
+    process, when its result is asked for.  The default executor
+    for the repetitions of risk_normalization.
+    
+    The tasks are not run by submit, so the repetitions can all be
+    submitted at once and still be reported as each one finishes.
+    """
+
+    def submit(self, function, *args, **kwargs):
+        return DeferredFuture(function, args, kwargs)
+
+
+class RepetitionResult:
+
+    """
+    The results of one repetition of risk_normalization.
+    
+    Attributes:
+    safe_f:           safe-f.
+    TWR25:            terminal wealth at the 25th percentile.
+    CAR_percentiles:  dictionary from percentile to CAR.
+    CAR_distribution: None, or the sorted CAR of every equity curve.
+    tail_risk_curve:  list of (fraction, tail_risk) pairs evaluated.
+    number_ruined:    number of equity curves at safe-f that lost
+                      the whole account.
+    ratio_percentiles:  None, or dictionary from ratio name to a
+                      dictionary from percentile to ratio.
+    drawdown_profile: DrawdownProfile at safe-f.
+    search_trace:     None, or list of SearchPass.
+    goal_probabilities:  dictionary from wealth target to the
+                      probabilities of reaching it at the end, and at
+                      any time.
+    value_at_risk:    dictionary from confidence level to the VaR and
+                      CVaR of terminal wealth, in dollars.
+    drawdown_distribution:  None, or the sorted maximum drawdown of
+                      every equity curve.
+    equity_curves:    list of EquityCurve.
+    trade_indices:    None, or the indices of the trades drawn, with
+                      a row for each equity curve at safe-f.
+    size_multipliers: None, or the execution noise size multipliers,
+                      of the same shape.
+    CAR_histogram, drawdown_histogram:  None, or Histogram of the
+                      CAR and maximum drawdown of every equity curve.
+    compute_usage:    None, or ComputeUsage of the repetition.
+    no_drawdown_risk: True if no trade is a loss, so that no equity
+                      curve draws down, and safe-f is MAX_FRACTION,
+                      or the config's fraction_limit.
+    all_losing_trades:  True if no trade is a gain, and safe-f is 0.
+    safe_f_capped:    True if safe-f is the config's fraction_limit,
+                      or MAX_FRACTION, short of the tolerance.
+    equity_bands:     None, or EquityBands at safe-f.
+    streak_lists:     None, or dictionary from 'losing_trades',
+                      'losing_days', and 'recovery_days' to a list
+                      with the value of each equity curve at safe-f.
+                      See compute_path_streaks.
+    benchmark_lists:  None, or dictionary from 'alpha', 'beta', and
+                      'relative_drawdown' to a list with the value of
+                      each equity curve at safe-f.
+                      See compute_path_benchmark_metrics.
+    number_trades:    None, or the number of trades analyzed, after
+                      the config's transforms, set by
+                      run_repetition_shard.
+    """
+
+    def __init__(self,
+                 safe_f,
+                 TWR25,
+                 CAR_percentiles,
+                 CAR_distribution,
+                 tail_risk_curve,
+                 number_ruined = 0,
+                 ratio_percentiles = None,
+                 drawdown_profile = None,
+                 search_trace = None,
+                 goal_probabilities = None,
+                 value_at_risk = None,
+                 drawdown_distribution = None,
+                 equity_curves = None,
+                 trade_indices = None,
+                 size_multipliers = None,
+                 CAR_histogram = None,
+                 drawdown_histogram = None,
+                 compute_usage = None,
+                 no_drawdown_risk = False,
+                 all_losing_trades = False,
+                 equity_bands = None,
+                 streak_lists = None,
+                 benchmark_lists = None,
+                 safe_f_capped = False,
+                 number_trades = None):
+        self.safe_f = safe_f
+        self.TWR25 = TWR25
+        self.CAR_percentiles = CAR_percentiles
+        self.CAR_distribution = CAR_distribution
+        self.tail_risk_curve = tail_risk_curve
+        self.number_ruined = number_ruined
+        self.ratio_percentiles = ratio_percentiles
+        self.drawdown_profile = drawdown_profile
+        self.search_trace = search_trace
+        self.goal_probabilities = goal_probabilities
+        self.value_at_risk = value_at_risk
+        self.drawdown_distribution = drawdown_distribution
+        self.equity_curves = equity_curves
+        self.trade_indices = trade_indices
+        self.size_multipliers = size_multipliers
+        self.CAR_histogram = CAR_histogram
+        self.drawdown_histogram = drawdown_histogram
+        self.compute_usage = compute_usage
+        self.no_drawdown_risk = no_drawdown_risk
+        self.all_losing_trades = all_losing_trades
+        self.equity_bands = equity_bands
+        self.streak_lists = streak_lists
+        self.benchmark_lists = benchmark_lists
+        self.safe_f_capped = safe_f_capped
+        self.number_trades = number_trades
+
+
+def candidate_seeds(
+        seed,
+        iterations,
+        number_candidates,
+        variance_reduction = ()
+        ):
+
+    """
+    The seed of each candidate fraction of a pass of the search.
+    Each candidate of each pass has its own, unless
+    variance_reduction includes 'common_random_numbers', when all
+    share one, so that every fraction is evaluated on the same draws.
+    """
+
+    if 'common_random_numbers' in variance_reduction:
+        return [derive_seed(seed, 'common')] * number_candidates
+
+    return [derive_seed(seed, iterations, candidate)
+            for candidate in range(number_candidates)]
+
+
+def estimate_search_progress(
+        distance_from_tolerance,
+        first_distance,
+        desired_accuracy,
+        converged
+        ):
+
+    """
+    How far the search for safe-f has come, from 0 to 1.  The
+    distance of the tail risk from the tolerance shrinks by about
+    the same factor each pass, so progress is measured on a log
+    scale from the first pass's distance to desired_accuracy.
+    1 only once the search has converged.
+    """
+
+    if converged:
+        return 1.0
+    if (distance_from_tolerance <= desired_accuracy or
+            first_distance <= desired_accuracy):
+        return 0.99
+    progress = (math.log(first_distance / distance_from_tolerance) /
+                math.log(first_distance / desired_accuracy))
+
+    return min(max(progress, 0.0), 0.99)
+
+
+#  The largest fraction the search considers, reported as safe-f
+#  when the drawdown tolerance sets no lower limit, such as for
+#  trades none of which is a loss.
+MAX_FRACTION = 10.0
+
+
+def find_safe_f(
+        trades,
+        config,
+        executor,
+        tail_risk_curve,
+        seed,
+        search_trace = None,
+        on_search_pass = None,
+        compute_usage = None
+        ):
+
+    """
+    Search for the fraction whose tail risk equals the drawdown
+    tolerance, or config.trailing_drawdown_dollars when it is set.
+    
+    Parameters:
+    trades:     the set of trades to be analyzed.
+    config:     RiskNormalizationConfig.
+    executor:   None, or a concurrent.futures executor used to
+                evaluate the candidate fractions of each pass.
+    tail_risk_curve:  list to which each (fraction, tail_risk)
+                pair evaluated is appended.
+    seed:       the repetition's seed, from which the seed of each
+                candidate is derived.
+    search_trace:  Optional.  A list to which a SearchPass is
+                appended for each pass.
+    on_search_pass:  Optional.  Called after each pass with the
+                iteration, the candidate closest to the tolerance,
+                its tail risk, and an estimate from 0 to 1 of how
+                far the search has come.
+    compute_usage:  Optional.  A ComputeUsage to which the equity
+                sequences of each pass, and the processor time of
+                the executor's workers, are added.
+    
+    Returns:
+    safe_f, or MAX_FRACTION if no trade is a loss, or 0.0 if no
+    trade is a gain.  Never more than MAX_FRACTION, or the config's
+    fraction_limit, which replaces it when it is set.
+    """
+
+    #  A trailing dollar drawdown is searched for as a proportion
+    #  of initial capital, so the search itself is unchanged.
+
+    drawdown_in_dollars = config.trailing_drawdown_dollars is not None
+    if drawdown_in_dollars:
+        drawdown_tolerance = (config.trailing_drawdown_dollars /
+                              config.initial_capital)
+    else:
+        drawdown_tolerance = config.drawdown_tolerance
+    number_equity_in_CDF = config.number_equity_in_CDF
+
+    #  While searching for safe-f, equity sequences stop as soon as
+    #  their drawdown exceeds drawdown_limit_multiple times the
+    #  drawdown tolerance.  Whenever the tail risk is below that
+    #  limit, which includes every pass near convergence, it is the
+    #  same estimate the same draws give without a limit, so safe-f
+    #  is still found within desired_accuracy of the tolerance.
+    #  Passes with a fraction that is too aggressive finish much
+    #  sooner, with a lower bound of their tail risk, which may
+    #  steer the search along another path.
+    #  Set drawdown_limit_multiple to None to draw every trade.
+
+    if config.drawdown_limit_multiple is None:
+        drawdown_limit = None
+    else:
+        drawdown_limit = config.drawdown_limit_multiple * drawdown_tolerance
+
+    #  Each pass of the search evaluates number_candidates
+    #  fractions, by default one per worker.
+
+    if config.number_candidates is not None:
+        number_candidates = config.number_candidates
+    elif config.number_workers is not None:
+        number_candidates = config.number_workers
+    else:
+        number_candidates = 1
+
+    desired_accuracy = config.desired_accuracy
+    max_iterations = config.max_iterations
+    largest_fraction = config.fraction_limit()
+    if largest_fraction is None:
+        largest_fraction = MAX_FRACTION
+
+    #  Withdrawals draw the account down even without trading.
+    #  If they alone reach the tolerance, no fraction will do.
+
+    if config.cash_flows is not None:
+        equity, cash_flow_drawdown = make_one_equity_sequence(
+                                         trades,
+                                         0.0,
+                                         config.number_days_in_forecast,
+                                         config.number_trades_in_forecast,
+                                         config.initial_capital,
+                                         None,
+                                         [0] * config.number_trades_in_forecast,
+                                         None,
+                                         config.cash_flows,
+                                         None,
+                                         None,
+                                         drawdown_in_dollars)
+        if cash_flow_drawdown >= drawdown_tolerance:
+            raise SearchDidNotConvergeError(
+                      0, 0.0, cash_flow_drawdown,
+                      'the cash flows alone draw down more than the '
+                      'drawdown tolerance')
+
+    #  If no trade is a gain, every position loses money, and the
+    #  search would only shrink the fraction toward 0.  If no trade
+    #  is a loss, and neither costs nor cash flows draw the account
+    #  down, no equity curve draws down at any fraction, and there
+    #  is nothing to search for.
+
+    if np.all(trades <= 0.0) and np.any(trades < 0.0):
+        return 0.0
+    if (np.all(trades >= 0.0) and config.cost_model is None and
+            (config.cash_flows is None or cash_flow_drawdown == 0.0)):
+        return largest_fraction
+
+    #  Fraction is initially set to use all available funds
+    #  It will be adjusted in response to the risk of drawdown.
+    #  The final value of fraction is safe-f
+    
+    #  Early passes may use fewer equity curves, as set by
+    #  sample_size_schedule.  safe-f is only accepted from a
+    #  pass that used all number_equity_in_CDF curves.
+    
+    fraction = min(1.0, largest_fraction)
+    distance_from_tolerance = math.inf
+    first_distance = None
+    search_progress = 0.0
+    iterations = 0
+    confirming_limit = False
+    while True:
+        if config.cancel_event is not None and config.cancel_event.is_set():
+            raise RunCancelledError()
+        if iterations == max_iterations:
+            closest = distances.index(distance_from_tolerance)
+            raise SearchDidNotConvergeError(iterations,
+                                            candidates[closest],
+                                            tail_risks[closest])
+        iterations = iterations + 1
+        # print(f"fraction this pass:  {fraction:0.3f}")
+        if confirming_limit:
+            number_equity_this_pass = number_equity_in_CDF
+        else:
+            number_equity_this_pass = choose_number_equity_in_CDF(
+                                          config.sample_size_schedule,
+                                          distance_from_tolerance,
+                                          number_equity_in_CDF)
+        candidates = choose_candidate_fractions(
+                         fraction,
+                         number_candidates,
+                         distance_from_tolerance,
+                         drawdown_tolerance)
+        candidates = [min(candidate, largest_fraction)
+                      for candidate in candidates]
+        tail_risks = analyze_several_fractions(
+                         executor,
+                         candidates,
+                         trades, 
+                         config.number_days_in_forecast,
+                         config.number_trades_in_forecast,
+                         config.initial_capital,
+                         config.tail_percentile,
+                         number_equity_this_pass,
+                         drawdown_limit,
+                         config.resampling_method,
+                         config.percentile_method,
+                         candidate_seeds(seed, iterations, len(candidates),
+                                         config.variance_reduction),
+                         config.cash_flows,
+                         config.cost_model,
+                         drawdown_in_dollars,
+                         config.position_sizing,
+                         config.execution_noise,
+                         config.random_algorithm,
+                         config.backend,
+                         compute_usage)
+        if compute_usage is not None:
+            compute_usage.number_paths = (compute_usage.number_paths +
+                                          number_equity_this_pass *
+                                          len(candidates))
+    
+        # print(f"tail_risks this pass: {tail_risks}")
+        tail_risk_curve.extend(zip(candidates, tail_risks))
+        distances = [abs(tail_risk - drawdown_tolerance)
+                        for tail_risk in tail_risks]
+        distance_from_tolerance = min(distances)
+        converged = (distance_from_tolerance < desired_accuracy and
+                     number_equity_this_pass == number_equity_in_CDF)
+        if converged:
+            fraction = candidates[distances.index(distance_from_tolerance)]
+        elif max(tail_risks) > 0.0:
+            fraction = next_fraction_estimate(
+                           candidates,
+                           tail_risks,
+                           drawdown_tolerance)
+            fraction = min(fraction, largest_fraction)
+        else:
+            #  No equity curve at the tail percentile drew down, so
+            #  there is nothing to scale the fraction by.  Some trades
+            #  are losses, as checked above, but too few curves draw
+            #  one to reach the tail percentile.  Larger fractions
+            #  make those drawdowns deeper, so they are tried next.
+            fraction = min(2.0 * max(candidates), largest_fraction)
+        if search_trace is not None:
+            below = [f for f, risk in tail_risk_curve
+                     if risk < drawdown_tolerance]
+            above = [f for f, risk in tail_risk_curve
+                     if risk > drawdown_tolerance]
+            search_trace.append(SearchPass(
+                                    iterations,
+                                    number_equity_this_pass,
+                                    candidates,
+                                    tail_risks,
+                                    max(below) if below else None,
+                                    min(above) if above else None,
+                                    fraction))
+        if on_search_pass is not None:
+            closest = distances.index(distance_from_tolerance)
+            if first_distance is None:
+                first_distance = distance_from_tolerance
+            #  a pass may land farther away than the one before, but
+            #  a progress bar should not go back
+            search_progress = max(search_progress,
+                                  estimate_search_progress(
+                                      distance_from_tolerance,
+                                      first_distance,
+                                      desired_accuracy,
+                                      converged))
+            on_search_pass(iterations,
+                           candidates[closest],
+                           tail_risks[closest],
+                           search_progress)
+        if converged:
+            return fraction
+        #  safe-f would be more than the account allows, or more
+        #  than MAX_FRACTION, once a pass with every equity curve
+        #  agrees
+        confirming_limit = (largest_fraction in candidates and
+                            tail_risks[candidates.index(largest_fraction)] <
+                                drawdown_tolerance)
+        if (confirming_limit and
+                number_equity_this_pass == number_equity_in_CDF):
+            return largest_fraction
+
+
+def run_one_repetition(
+        seed,
+        trades,
+        config,
+        executor = None,
+        on_search_pass = None
+        ):
+
+    """
+    Compute safe-f, then the distribution of terminal equity and
+    CAR at safe-f, once.
+    
+    Parameters:
+    seed:       seed for the random number generator.
+    trades:     the set of trades to be analyzed.
+    config:     RiskNormalizationConfig.
+    executor:   None, or a concurrent.futures executor used to
+                evaluate the candidate fractions of the search.
+    on_search_pass:  Optional.  Called after each pass of the
+                search, as find_safe_f describes.
+    
+    Returns:
+    RepetitionResult
+    """
+
+    started = time.process_time()
+    compute_usage = ComputeUsage()
+    tail_risk_curve = []
+    if config.keep_search_trace:
+        search_trace = []
+    else:
+        search_trace = None
+    fraction = find_safe_f(trades, config, executor, tail_risk_curve,
+                           seed, search_trace, on_search_pass,
+                           compute_usage)
+
+    seed_random_generator(seed, config.random_algorithm, 'equity')
+    
+    #  print(f'final value: safe_f: {fraction:0.3f}')
+    
+    #  Compute CAR25
+    #  fraction == safe_f
+    #  Compute CDF of equity
+    #  TWR25 is 25th percentile
+    #  CAR25 is 25th percentile
+    
+    if config.compute_ratios:
+        ratio_lists = {}
+    else:
+        ratio_lists = None
+    if config.compute_streaks:
+        streak_lists = {}
+    else:
+        streak_lists = None
+    if config.benchmark is not None:
+        benchmark_lists = {}
+    else:
+        benchmark_lists = None
+    drawdown_list = []
+    peak_list = []
+    equity_curves = []
+    band_list = []
+    if config.keep_trade_indices:
+        draws_list = []
+    else:
+        draws_list = None
+    CDF_equity = form_distribution_of_equity(
+                     trades, 
+                     fraction,
+                     config.number_days_in_forecast,
+                     config.number_trades_in_forecast,
+                     config.initial_capital,
+                     config.number_equity_in_CDF,
+                     config.resampling_method,
+                     ratio_lists,
+                     config.trading_days_per_year,
+                     drawdown_list,
+                     config.cash_flows,
+                     peak_list,
+                     config.cost_model,
+                     config.position_sizing,
+                     config.execution_noise,
+                     config.equity_curve_percentiles,
+                     config.equity_curve_points,
+                     equity_curves,
+                     draws_list,
+                     config.backend,
+                     config.equity_band_percentiles,
+                     band_list,
+                     streak_lists,
+                     config.benchmark,
+                     benchmark_lists)
+    compute_usage.number_paths = (compute_usage.number_paths +
+                                  config.number_equity_in_CDF)
+    
+    TWR25 = compute_percentile(CDF_equity, 25, config.percentile_method)
+    # print(f'terminal wealth: {TWR25:9.0f}')
+    
+    CAR_percentiles = {}
+    for percentile in sorted(set(config.CAR_percentiles) | {25}):
+        CAR_percentiles[percentile] = compute_CAR(
+                                          compute_percentile(
+                                              CDF_equity,
+                                              percentile,
+                                              config.percentile_method),
+                                          config.initial_capital,
+                                          config.number_days_in_forecast,
+                                          config.trading_days_per_year)
+    
+    if config.keep_CAR_distributions:
+        CAR_distribution = compute_CAR(CDF_equity,
+                                       config.initial_capital,
+                                       config.number_days_in_forecast,
+                                       config.trading_days_per_year)
+        drawdown_distribution = np.sort(drawdown_list)
+    else:
+        CAR_distribution = None
+        drawdown_distribution = None
+
+    number_ruined = int(np.sum(CDF_equity <= 0.0))
+
+    goal_probabilities = {
+        target: (sum(equity >= target for equity in CDF_equity) /
+                     len(CDF_equity),
+                 sum(peak >= target for peak in peak_list) /
+                     len(peak_list))
+        for target in config.wealth_targets}
+
+    #  the losses beyond VaR are those of the equity curves at or
+    #  below the 1 - confidence_level percentile of terminal wealth
+    value_at_risk = {}
+    for level in config.VaR_confidence_levels:
+        tail_equity = compute_percentile(CDF_equity, 100 * (1.0 - level),
+                                         config.percentile_method)
+        value_at_risk[level] = (
+            config.initial_capital - tail_equity,
+            config.initial_capital -
+                statistics.mean(equity for equity in CDF_equity
+                                if equity <= tail_equity))
+
+    drawdown_profile = DrawdownProfile(
+                           statistics.mean(drawdown_list),
+                           compute_percentile(drawdown_list, 50,
+                                              config.percentile_method),
+                           compute_percentile(drawdown_list, 95,
+                                              config.percentile_method),
+                           {level: sum(drawdown > level
+                                       for drawdown in drawdown_list) /
+                                   len(drawdown_list)
+                            for level in config.drawdown_levels})
+
+    if ratio_lists is not None:
+        ratio_percentiles = {}
+        for name in ratio_lists:
+            ratio_percentiles[name] = {
+                percentile: compute_percentile(ratio_lists[name],
+                                               percentile,
+                                               config.percentile_method)
+                for percentile in CAR_percentiles}
+    else:
+        ratio_percentiles = None
+
+    if draws_list is not None:
+        trade_indices, size_multipliers = draws_list[0]
+    else:
+        trade_indices = None
+        size_multipliers = None
+
+    if config.keep_histograms:
+        CAR_histogram = compute_histogram(
+                            compute_CAR(CDF_equity,
+                                        config.initial_capital,
+                                        config.number_days_in_forecast,
+                                        config.trading_days_per_year),
+                            config.histogram_bins)
+        drawdown_histogram = compute_histogram(drawdown_list,
+                                               config.histogram_bins)
+    else:
+        CAR_histogram = None
+        drawdown_histogram = None
+
+    compute_usage.cpu_seconds = (compute_usage.cpu_seconds +
+                                 time.process_time() - started)
+
+    #  the search makes no pass at all when no trade is a gain, or
+    #  when none is a loss
+    searched = len(tail_risk_curve) > 0
+    all_losing_trades = not searched and fraction == 0.0
+    no_drawdown_risk = not searched and fraction > 0.0
+    largest_fraction = config.fraction_limit()
+    if largest_fraction is None:
+        largest_fraction = MAX_FRACTION
+    safe_f_capped = fraction == largest_fraction
+
+    return RepetitionResult(fraction, TWR25, CAR_percentiles,
+                            CAR_distribution, tail_risk_curve,
+                            number_ruined, ratio_percentiles,
+                            drawdown_profile, search_trace,
+                            goal_probabilities, value_at_risk,
+                            drawdown_distribution, equity_curves,
+                            trade_indices, size_multipliers,
+                            CAR_histogram, drawdown_histogram,
+                            compute_usage,
+                            no_drawdown_risk,
+                            all_losing_trades,
+                            band_list[0] if band_list else None,
+                            streak_lists,
+                            benchmark_lists,
+                            safe_f_capped)
+
+
+def estimate_memory_bytes(
+        config
+        ):
+
+    """
+    Roughly estimate the memory a run of config needs, in bytes,
+    beyond the trades themselves.
+    
+    The largest part is the array of trade indices drawn for each
+    batch of equity sequences, number_equity_in_CDF by
+    number_trades_in_forecast, with room for the temporary arrays
+    of the block resampling methods.  One batch is in use for each
+    worker.  Kept CAR and drawdown distributions add
+    2 * number_equity_in_CDF values per repetition, and kept trade
+    indices one more batch, or two with execution noise.
+    """
+
+    bytes_per_value = 8
+    batch = (config.number_equity_in_CDF *
+             config.number_trades_in_forecast * bytes_per_value * 3)
+    if config.number_workers is not None:
+        batch = batch * config.number_workers
+    if config.keep_CAR_distributions:
+        CAR_distributions = (config.number_repetitions *
+                             config.number_equity_in_CDF * bytes_per_value * 2)
+    else:
+        CAR_distributions = 0
+    if config.keep_trade_indices:
+        trade_indices = (config.number_equity_in_CDF *
+                         config.number_trades_in_forecast * bytes_per_value)
+        if config.execution_noise is not None:
+            trade_indices = trade_indices * 2
+    else:
+        trade_indices = 0
+
+    return batch + CAR_distributions + trade_indices
+
+
+def apply_resource_limits(
+        config
+        ):
+
+    """
+    Check config against its max_memory_bytes and max_paths.
+    
+    Returns:
+    config, or a copy that does not keep the CAR distributions
+    if that brings it within max_memory_bytes.
+    
+    Raises ResourceLimitError if the run does not fit.
+    """
+
+    number_paths = config.number_equity_in_CDF * config.number_repetitions
+    if config.max_paths is not None and number_paths > config.max_paths:
+        raise ResourceLimitError('max_paths', number_paths,
+                                 config.max_paths)
+
+    if config.max_memory_bytes is None:
+        return config
+    estimate = estimate_memory_bytes(config)
+    if estimate <= config.max_memory_bytes:
+        return config
+    if config.keep_CAR_distributions:
+        thinned_config = config.replace(keep_CAR_distributions = False)
+        if estimate_memory_bytes(thinned_config) <= config.max_memory_bytes:
+            #  merge_repetition_shards warns that they were dropped
+            return thinned_config
+    raise ResourceLimitError('max_memory_bytes', estimate,
+                             config.max_memory_bytes)
+
+
+def count_available_processors():
+
+    """
+    The number of processors this process may run on, which may be
+    fewer than the machine has, as in a container.
+    """
+
+    if hasattr(os, 'sched_getaffinity'):
+        return len(os.sched_getaffinity(0))
+
+    return os.cpu_count() or 1
+
+
+def count_fraction_processes(
+    config  ):
+
+    """
+    The number of worker processes that evaluate the candidate
+    fractions of each pass of the search for safe-f.
+    
+    On a machine with few processors, worker processes would only
+    compete with each other and with the calling program, so with
+    2 or fewer available the candidates are evaluated here.  So
+    they are when the config has an executor, which runs each
+    repetition whole.  number_workers still sets the number of
+    candidates, so the results do not change.
+    
+    Returns:
+    the number of worker processes, or 0 when the candidates are
+    evaluated in the process that runs the repetition.
+    """
+
+    if config.executor is not None or config.number_workers is None:
+        return 0
+    number_processes = min(config.number_workers,
+                           count_available_processors())
+    if number_processes <= 2:
+        return 0
+
+    return number_processes
+
+
+def run_repetition_shard(
+        trades,
+        config,
+        first_repetition = 0,
+        stop_repetition = None
+        ):
+
+    """
+    Run repetitions first_repetition up to, but not including,
+    stop_repetition of the run described by config.
+    
+    Each repetition has the same seed it has in a run of all
+    config.number_repetitions, so the shards of a run may be
+    computed on different machines, then combined with
+    merge_repetition_shards to give the same results as one run.
+    risk_normalization_in_shards does both.
+    
+    Parameters:
+    trades:     the set of trades to be analyzed.
+    config:     RiskNormalizationConfig, with a seed, so that every
+                shard is part of the same run.
+    first_repetition:  index of the first repetition to run.
+    stop_repetition:   None, meaning number_repetitions, or the
+                index after the last repetition to run.
+    
+    Returns:
+    list of RepetitionResult, one per repetition, in order.
+    """
+
+    #  without a seed, each shard would draw a run of its own
+    if config.seed is None:
+        raise InvalidParameterError('seed', None,
+                                    'must be given to run in shards, so '
+                                    'that every shard is part of the '
+                                    'same run')
+    number_repetitions = config.number_repetitions
+    if stop_repetition is None:
+        stop_repetition = number_repetitions
+    if not 0 <= first_repetition < stop_repetition <= number_repetitions:
+        raise InvalidParameterError(
+                  'repetitions', (first_repetition, stop_repetition),
+                  f'must be a range within 0 to {number_repetitions}')
+
+    trades = transform_trades(trades, config)
+    if len(trades) < config.minimum_number_trades:
+        raise TooFewTradesError(len(trades), config.minimum_number_trades)
+    #  before a regime mix repeats them
+    number_trades = len(trades)
+    if config.benchmark is not None:
+        benchmark = np.array(config.benchmark, dtype = float)
+        if len(benchmark) != len(trades):
+            raise InvalidParameterError('benchmark', len(benchmark),
+                                        f'must have one return for each '
+                                        f'of the {len(trades)} trades')
+    config = apply_resource_limits(config)
+    if config.scenario is not None:
+        trades, resampling_method = config.scenario.apply(
+                                        trades, config.resampling_method)
+        config = config.replace(resampling_method = resampling_method)
+        #  a regime mix repeats the trades once for each regime
+        if config.benchmark is not None:
+            benchmark = np.concatenate([benchmark] *
+                                       (len(trades) // len(benchmark)))
+    if 'antithetic' in config.variance_reduction:
+        if config.benchmark is not None:
+            benchmark = benchmark[np.argsort(trades, kind = 'stable')]
+        trades = np.sort(trades)
+        config = config.replace(resampling_method = AntitheticResampling())
+    if config.benchmark is not None:
+        config = config.replace(benchmark = benchmark)
+
+    #  With a seed, the same config and trades give the same results.
+    #  Each repetition is given its own seed from this generator,
+    #  so the results do not depend on the executor or the shard.
+
+    seed_random_generator(config.seed, config.random_algorithm)
+    seeds = random_integers(0, 2**31 - 1, size=number_repetitions)
+
+    if config.progress_sink is None:
+        progress_sink = ProgressSink()
+    else:
+        progress_sink = config.progress_sink
+
+    #  The repetitions are submitted to config.executor.
+    #  When they run here, in sequence, the candidate fractions of
+    #  each pass of the search are evaluated by number_workers
+    #  worker processes, as count_fraction_processes decides.
+    #  When they run elsewhere, each repetition evaluates its
+    #  candidates itself.
+
+    if config.executor is None:
+        executor = SequentialExecutor()
+    else:
+        executor = config.executor
+    number_processes = count_fraction_processes(config)
+    if number_processes > 0:
+        fraction_executor = concurrent.futures.ProcessPoolExecutor(
+                                number_processes)
+    else:
+        fraction_executor = None
+
+    #  the executor and progress sink stay here, and so does the
+    #  cancel event unless the repetitions do too
+    if config.executor is None:
+        repetition_config = config.replace(executor = None,
+                                           progress_sink = None)
+    else:
+        repetition_config = config.replace(executor = None,
+                                           progress_sink = None,
+                                           cancel_event = None)
+    #  only the final repetition keeps its trade indices
+    earlier_config = repetition_config.replace(keep_trade_indices = False)
+
+    #  Passes of the search are reported only from repetitions run
+    #  here, where the progress sink is.
+    number_in_shard = stop_repetition - first_repetition
+
+    def report_search_passes(rep):
+        if config.executor is not None:
+            return None
+        def on_search_pass(iteration, fraction, tail_risk, progress):
+            progress_sink.on_search_pass(
+                rep, iteration, fraction, tail_risk,
+                100.0 * (rep - first_repetition + progress) /
+                    number_in_shard)
+        return on_search_pass
+
+    futures = [executor.submit(run_one_repetition,
+                               int(seeds[rep]),
+                               trades,
+                               repetition_config
+                                   if rep == number_repetitions - 1
+                                   else earlier_config,
+                               fraction_executor,
+                               report_search_passes(rep))
+               for rep in range(first_repetition, stop_repetition)]
+
+    repetitions = []
+    try:
+        for rep, future in zip(range(first_repetition, stop_repetition),
+                               futures):
+            if (config.cancel_event is not None and
+                    config.cancel_event.is_set()):
+                raise RunCancelledError()
+            repetition = future.result()
+            repetition.number_trades = number_trades
+            progress_sink.on_repetition_complete(rep, repetition.safe_f,
+                                                 repetition.CAR_percentiles[25])
+            repetitions.append(repetition)
+    except RunCancelledError:
+        for future in futures:
+            future.cancel()
+        raise RunCancelledError(repetitions)
+    finally:
+        if fraction_executor is not None:
+            fraction_executor.shutdown()
+
+    return repetitions
+
+
+def risk_normalization_with_config(
+        trades,
+        config
+        ):
+
+    started_at = datetime.datetime.now(datetime.timezone.utc)
+    #  a run without a seed draws one, so that it can be reproduced
+    if config.seed is None:
+        config = config.replace(seed = int(np.random.randint(0, 2**31 - 1)))
+    try:
+        repetitions = run_repetition_shard(trades, config)
+    except RunCancelledError as error:
+        if len(error.repetitions) > 0:
+            error.partial_result = merge_repetition_shards(
+                                       [error.repetitions],
+                                       trades,
+                                       config.replace(
+                                           number_repetitions =
+                                               len(error.repetitions),
+                                           sample_size_bootstraps = 0,
+                                           confidence_method =
+                                               'repetitions'))
+        raise error
+    result = merge_repetition_shards([repetitions], trades, config)
+    result.started_at = started_at
+    result.finished_at = datetime.datetime.now(datetime.timezone.utc)
+    return result
+
+
+async def risk_normalization_async(
+        trades,
+        config
+        ):
+
+    """
+    risk_normalization_with_config, run in a thread so that an
+    asyncio event loop, such as that of a user interface, is not
+    blocked.  Cancelling the task stops the run at its next check
+    of the cancel event.  Each thread has its own random number
+    generator, so runs in flight at the same time give the same
+    results as run one after another.
+    
+    Returns:
+    RiskNormalizationResult
+    """
+
+    if config.cancel_event is None:
+        config = config.replace(cancel_event = threading.Event())
+    loop = asyncio.get_running_loop()
+    try:
+        return await loop.run_in_executor(None,
+                                          risk_normalization_with_config,
+                                          trades,
+                                          config)
+    except asyncio.CancelledError:
+        config.cancel_event.set()
+        raise
+
+
+def merge_repetition_shards(
+        shards,
+        trades,
+        config
+        ):
+
+    """
+    Combine the shards of a run into its results.
+    
+    Parameters:
+    shards:     list of the lists returned by run_repetition_shard,
+                in order of their first repetition, covering all
+                config.number_repetitions.
+    trades:     the set of trades analyzed.
+    config:     RiskNormalizationConfig the shards were run with.
+    
+    Returns:
+    RiskNormalizationResult
+    """
+
+    repetitions = [repetition for shard in shards for repetition in shard]
+    number_repetitions = len(repetitions)
+    if number_repetitions != config.number_repetitions:
+        raise InvalidParameterError(
+                  'shards', number_repetitions,
+                  f'must hold all {config.number_repetitions} repetitions')
+
+    safe_fs = [repetition.safe_f for repetition in repetitions]
+    TWR25s = [repetition.TWR25 for repetition in repetitions]
+    CAR_percentile_lists = {}
+    for percentile in repetitions[0].CAR_percentiles:
+        CAR_percentile_lists[percentile] = [
+            repetition.CAR_percentiles[percentile]
+            for repetition in repetitions]
+    CAR25s = CAR_percentile_lists[25]
+    tail_risk_curve = []
+    for repetition in repetitions:
+        tail_risk_curve.extend(repetition.tail_risk_curve)
+    #  apply_resource_limits may have dropped the CAR distributions
+    if repetitions[0].CAR_distribution is not None:
+        CAR_distributions = [repetition.CAR_distribution
+                             for repetition in repetitions]
+        drawdown_distributions = [repetition.drawdown_distribution
+                                  for repetition in repetitions]
+    else:
+        CAR_distributions = None
+        drawdown_distributions = None
+       
+    # print(safe_fs)
+    # print(TWR25s)
+    # print(CAR25s)
+    
+    safe_f_stats = compute_statistics(safe_fs)
+    TWR25_stats = compute_statistics(TWR25s)
+    CAR25_stats = compute_statistics(CAR25s)
+    safe_f_mean = safe_f_stats.mean
+    
+    if repetitions[0].ratio_percentiles is not None:
+        ratio_percentiles = {}
+        for name, percentiles in repetitions[0].ratio_percentiles.items():
+            ratio_percentiles[name] = {
+                percentile: statistics.mean(
+                                repetition.ratio_percentiles[name][percentile]
+                                for repetition in repetitions)
+                for percentile in percentiles}
+    else:
+        ratio_percentiles = None
+
+    if repetitions[0].streak_lists is not None:
+        streak_lists = {name: [value for repetition in repetitions
+                               for value in repetition.streak_lists[name]]
+                        for name in repetitions[0].streak_lists}
+        recovery_days = [days for days in streak_lists['recovery_days']
+                         if days is not None]
+        streaks = StreakStatistics(
+                      compute_statistics(streak_lists['losing_trades']),
+                      compute_statistics(streak_lists['losing_days']),
+                      compute_statistics(recovery_days)
+                          if len(recovery_days) > 0 else None,
+                      1.0 - len(recovery_days) /
+                          len(streak_lists['recovery_days']))
+    else:
+        streaks = None
+
+    if repetitions[0].benchmark_lists is not None:
+        benchmark_metrics = BenchmarkStatistics(*[
+            compute_statistics([value for repetition in repetitions
+                                for value in repetition.benchmark_lists[name]])
+            for name in ('alpha', 'beta', 'relative_drawdown')])
+    else:
+        benchmark_metrics = None
+
+    if repetitions[0].search_trace is not None:
+        search_traces = [repetition.search_trace
+                         for repetition in repetitions]
+    else:
+        search_traces = None
+
+    goal_probabilities = [
+        GoalProbability(target,
+                        statistics.mean(repetition.goal_probabilities[target][0]
+                                        for repetition in repetitions),
+                        statistics.mean(repetition.goal_probabilities[target][1]
+                                        for repetition in repetitions))
+        for target in config.wealth_targets]
+
+    value_at_risk = []
+    for level in config.VaR_confidence_levels:
+        VaR = statistics.mean(repetition.value_at_risk[level][0]
+                              for repetition in repetitions)
+        CVaR = statistics.mean(repetition.value_at_risk[level][1]
+                               for repetition in repetitions)
+        value_at_risk.append(ValueAtRisk(level, VaR, CVaR,
+                                         VaR / config.initial_capital,
+                                         CVaR / config.initial_capital))
+
+    if config.position_sizing is not None:
+        safe_sizing = config.position_sizing.sizing_parameter(
+                          safe_f_mean, config.initial_capital)
+    else:
+        safe_sizing = safe_f_mean
+
+    run_warnings = []
+
+    def issue(code, message):
+        warning = RiskNormalizationWarning(code, message)
+        run_warnings.append(warning)
+        warnings.warn(warning)
+
+    number_trades = repetitions[0].number_trades
+    if number_trades < config.number_trades_in_forecast:
+        issue('few_trades',
+              format_message('few_trades', config.language,
+                             number_trades_in_forecast =
+                                 config.number_trades_in_forecast,
+                             number_trades = number_trades))
+
+    fraction_limit = config.fraction_limit()
+    if fraction_limit is None:
+        fraction_limit = MAX_FRACTION
+
+    if any(repetition.no_drawdown_risk for repetition in repetitions):
+        issue('no_drawdown_risk',
+              format_message('no_drawdown_risk', config.language,
+                             number_trades = number_trades,
+                             max_fraction = fraction_limit))
+
+    #  trades none of which is a loss are warned of above
+    number_capped = sum(repetition.safe_f_capped and
+                            not repetition.no_drawdown_risk
+                        for repetition in repetitions)
+    if number_capped > 0:
+        issue('safe_f_capped',
+              format_message('safe_f_capped', config.language,
+                             number_capped = number_capped,
+                             number_repetitions = number_repetitions,
+                             max_fraction = fraction_limit))
+
+    if any(repetition.all_losing_trades for repetition in repetitions):
+        issue('all_losing_trades',
+              format_message('all_losing_trades', config.language,
+                             number_trades = number_trades))
+
+    number_ruined = sum(repetition.number_ruined
+                        for repetition in repetitions)
+    if number_ruined > 0:
+        issue('ruined_equity_curves',
+              format_message('ruined_equity_curves', config.language,
+                             number_ruined = number_ruined,
+                             number_equity_curves =
+                                 number_repetitions *
+                                 config.number_equity_in_CDF))
+
+    if config.keep_CAR_distributions and CAR_distributions is None:
+        issue('CAR_distributions_dropped',
+              format_message('CAR_distributions_dropped', config.language,
+                             memory_bytes = estimate_memory_bytes(config),
+                             max_memory_bytes = config.max_memory_bytes))
+
+    if config.backend == 'gpu' and get_array_module('gpu') is np:
+        issue('gpu_unavailable',
+              format_message('gpu_unavailable', config.language))
+
+    if config.sample_size_bootstraps > 0:
+        sample_size_advisory = analyze_sample_size_variability(trades,
+                                                               config)
+        if not sample_size_advisory.is_stable:
+            issue('unstable_sample_size', sample_size_advisory.message)
+    else:
+        sample_size_advisory = None
+
+    if config.confidence_method == 'nested':
+        interval_safe_fs = sample_size_advisory.safe_f_list
+        interval_CAR25s = sample_size_advisory.CAR25_list
+    else:
+        interval_safe_fs = safe_fs
+        interval_CAR25s = CAR25s
+    safe_f_interval = compute_confidence_interval(
+                          interval_safe_fs,
+                          config.confidence_level,
+                          config.confidence_method,
+                          config.confidence_bootstraps,
+                          derive_seed(config.seed, 'confidence', 'safe_f'),
+                          config.percentile_method)
+    CAR25_interval = compute_confidence_interval(
+                         interval_CAR25s,
+                         config.confidence_level,
+                         config.confidence_method,
+                         config.confidence_bootstraps,
+                         derive_seed(config.seed, 'confidence', 'CAR25'),
+                         config.percentile_method)
+    
+    return RiskNormalizationResult(safe_f_stats.mean, safe_f_stats.stdev,
+                                   CAR25_stats.mean, CAR25_stats.stdev,
+                                   tail_risk_curve,
+                                   safe_f_list = safe_fs,
+                                   TWR25_list = TWR25s,
+                                   CAR25_list = CAR25s,
+                                   CAR_distributions = CAR_distributions,
+                                   sample_size_advisory = sample_size_advisory,
+                                   CAR_percentile_lists = CAR_percentile_lists,
+                                   warnings = run_warnings,
+                                   ratio_percentiles = ratio_percentiles,
+                                   strategy = config.strategy,
+                                   tags = dict(config.tags),
+                                   drawdown_profiles = [
+                                       repetition.drawdown_profile
+                                       for repetition in repetitions],
+                                   probability_of_ruin = number_ruined /
+                                       (number_repetitions *
+                                        config.number_equity_in_CDF),
+                                   search_traces = search_traces,
+                                   goal_probabilities = goal_probabilities,
+                                   safe_sizing = safe_sizing,
+                                   scenario = (config.scenario.name
+                                               if config.scenario is not None
+                                               else None),
+                                   trade_transforms = [
+                                       repr(transform) for transform
+                                       in config.trade_transforms],
+                                   value_at_risk = value_at_risk,
+                                   drawdown_distributions =
+                                       drawdown_distributions,
+                                   equity_curves = [repetition.equity_curves
+                                                    for repetition
+                                                    in repetitions],
+                                   CAR_histograms = [
+                                       repetition.CAR_histogram
+                                       for repetition in repetitions
+                                       if repetition.CAR_histogram
+                                           is not None],
+                                   drawdown_histograms = [
+                                       repetition.drawdown_histogram
+                                       for repetition in repetitions
+                                       if repetition.drawdown_histogram
+                                           is not None],
+                                   trade_indices =
+                                       repetitions[-1].trade_indices,
+                                   size_multipliers =
+                                       repetitions[-1].size_multipliers,
+                                   simulation_seed = SimulationSeed(
+                                       config.seed,
+                                       config.random_algorithm),
+                                   trades_sha256 = hash_trades(trades),
+                                   config = config,
+                                   compute_usage = ComputeUsage(
+                                       sum(repetition.compute_usage
+                                               .cpu_seconds
+                                           for repetition in repetitions),
+                                       sum(repetition.compute_usage
+                                               .number_paths
+                                           for repetition in repetitions)),
+                                   safe_f_interval = safe_f_interval,
+                                   CAR25_interval = CAR25_interval,
+                                   safe_f_stats = safe_f_stats,
+                                   TWR25_stats = TWR25_stats,
+                                   CAR25_stats = CAR25_stats,
+                                   equity_bands = [
+                                       repetition.equity_bands
+                                       for repetition in repetitions
+                                       if repetition.equity_bands
+                                           is not None],
+                                   streaks = streaks,
+                                   benchmark_metrics = benchmark_metrics,
+                                   safe_f_capped =
+                                       any(repetition.safe_f_capped
+                                           for repetition in repetitions))
+
+
+def risk_normalization_in_shards(
+        trades,
+        config,
+        number_shards,
+        executor
+        ):
+
+    """
+    Split the repetitions of a run into shards, run each shard as a
+    task of executor, and merge them.
+    
+    The executor carries the shards to the workers: a
+    ProcessPoolExecutor for the processors of this machine, or any
+    concurrent.futures executor that sends its tasks to other
+    machines.  Each task is given the trades and the config, which
+    must be able to be pickled to go to another process.
+    
+    Parameters:
+    trades:         the set of trades to be analyzed.
+    config:         RiskNormalizationConfig, with a seed.
+    number_shards:  the number of shards, from 1 to
+                    config.number_repetitions.
+    executor:       concurrent.futures executor that runs the shards.
+    
+    Returns:
+    RiskNormalizationResult, the same as
+    risk_normalization_with_config(trades, config) gives.
+    """
+
+    started_at = datetime.datetime.now(datetime.timezone.utc)
+    if config.seed is None:
+        raise InvalidParameterError('seed', None,
+                                    'must be given to run in shards, so '
+                                    'that every shard is part of the '
+                                    'same run')
+    number_repetitions = config.number_repetitions
+    if not (isinstance(number_shards, int) and
+            not isinstance(number_shards, bool) and
+            1 <= number_shards <= number_repetitions):
+        raise InvalidParameterError('number_shards', number_shards,
+                                    f'must be a whole number from 1 to '
+                                    f'{number_repetitions}')
+
+    #  the progress sink, executor, and cancel event stay here
+    shard_config = config.replace(executor = None,
+                                  progress_sink = None,
+                                  cancel_event = None)
+    bounds = [round(shard * number_repetitions / number_shards)
+              for shard in range(number_shards + 1)]
+    futures = [executor.submit(run_repetition_shard,
+                               trades,
+                               shard_config,
+                               bounds[shard],
+                               bounds[shard + 1])
+               for shard in range(number_shards)]
+    shards = [future.result() for future in futures]
+
+    result = merge_repetition_shards(shards, trades, config)
+    result.started_at = started_at
+    result.finished_at = datetime.datetime.now(datetime.timezone.utc)
+    return result
+
+
+def solve_tolerance_for_fraction(
+        trades,
+        fraction,
+        config
+        ):
+
+    """
+    The inverse of risk_normalization: the drawdown that must be
+    tolerated in order to trade at a given fraction.
+    
+    Forms number_equity_in_CDF equity sequences at fraction and
+    takes the maximum drawdown at config.tail_percentile of their
+    distribution, once per repetition.  config.drawdown_tolerance
+    is not used.
+    
+    Parameters:
+    trades:     the set of trades to be analyzed.
+    fraction:   the fraction of the account to use for each trade.
+    config:     RiskNormalizationConfig.
+    
+    Returns:
+    SummaryStats of the drawdown, as a proportion, at the tail
+    percentile, over the repetitions.  Unpacks as tolerance_mean
+    and tolerance_stdev.
+    """
+
+    if (not isinstance(fraction, (int, float, np.floating)) or
+            isinstance(fraction, bool) or
+            not math.isfinite(fraction) or fraction <= 0.0):
+        raise InvalidParameterError('fraction', fraction,
+                                    'must be a number greater than 0')
+    trades = validate_trades(trades)
+    if len(trades) < config.minimum_number_trades:
+        raise TooFewTradesError(len(trades), config.minimum_number_trades)
+
+    seed_random_generator(config.seed, config.random_algorithm)
+
+    tolerances = []
+    for rep in range(config.number_repetitions):
+        tolerances.append(analyze_distribution_of_drawdown(
+                              trades,
+                              fraction,
+                              config.number_days_in_forecast,
+                              config.number_trades_in_forecast,
+                              config.initial_capital,
+                              config.tail_percentile,
+                              config.number_equity_in_CDF,
+                              None,
+                              config.resampling_method,
+                              config.percentile_method,
+                              config.cash_flows,
+                              config.cost_model,
+                              False,
+                              config.position_sizing,
+                              config.execution_noise,
+                              config.backend))
+
+    return compute_statistics(tolerances)
+
+
+def solve_mean_improvement_for_CAR25(
+        trades,
+        target_CAR25,
+        config,
+        accuracy = 0.1,
+        max_iterations = 30
+        ):
+
+    """
+    How much better the trades would need to be for CAR25 to reach
+    target_CAR25, at the config's drawdown tolerance.
+    
+    The improvement is an amount added to every trade, so it raises
+    the mean trade by that amount and leaves the spread unchanged.
+    It is found by bisection, computing safe-f and CAR25 afresh for
+    each trial amount, so it takes as long as max_iterations runs
+    of risk_normalization.  Every run uses the same seed, so the
+    comparisons are not blurred by random variation.
+    
+    Parameters:
+    trades:        the set of trades to be analyzed.
+    target_CAR25:  the desired CAR25, in percent.
+    config:        RiskNormalizationConfig.
+    accuracy:      Optional.  Stop when CAR25 is within this many
+                   percentage points of target_CAR25.
+                   Default = 0.1
+    max_iterations:  Optional.  Default = 30
+    
+    Returns:
+    mean_improvement:  the amount to add to each trade, 0.0 if the
+                   trades already reach target_CAR25.
+    improved_mean:     the mean trade after the improvement.
+    CAR25_mean:        CAR25 of the improved trades.
+    
+    Raises SearchDidNotConvergeError if target_CAR25 is not reached
+    within max_iterations.
+    """
+
+    if (not isinstance(target_CAR25, (int, float, np.floating)) or
+            isinstance(target_CAR25, bool) or
+            not math.isfinite(target_CAR25)):
+        raise InvalidParameterError('target_CAR25', target_CAR25,
+                                    'must be a number, in percent')
+    if (not isinstance(accuracy, (int, float, np.floating)) or
+            isinstance(accuracy, bool) or not accuracy > 0.0):
+        raise InvalidParameterError('accuracy', accuracy,
+                                    'must be a number greater than 0')
+    if (not isinstance(max_iterations, int) or
+            isinstance(max_iterations, bool) or max_iterations < 1):
+        raise InvalidParameterError('max_iterations', max_iterations,
+                                    'must be a whole number greater '
+                                    'than 0')
+    trades = validate_trades(trades)
+    if config.seed is None:
+        config = config.replace(seed = int(np.random.randint(0, 2**31 - 1)))
+
+    def CAR25_with(improvement):
+        #  trades improved until they show no drawdown at all give
+        #  CAR25 at MAX_FRACTION
+        return risk_normalization_with_config(trades + improvement,
+                                              config).CAR25_mean
+
+    CAR25_mean = CAR25_with(0.0)
+    if CAR25_mean >= target_CAR25:
+        return (0.0, statistics.mean(trades), CAR25_mean)
+
+    #  find an improvement large enough, then bisect
+    low = 0.0
+    if len(trades) > 1:
+        high = max(statistics.stdev(trades) * 0.1, 1e-6)
+    else:
+        high = 1e-6
+    iterations = 0
+    while True:
+        iterations = iterations + 1
+        CAR25_high = CAR25_with(high)
+        if CAR25_high >= target_CAR25:
+            break
+        if iterations == max_iterations:
+            raise SearchDidNotConvergeError(
+                      max_iterations, high, CAR25_high,
+                      f'CAR25 of {target_CAR25} was not reached')
+        low = high
+        high = high * 2.0
+
+    if abs(CAR25_high - target_CAR25) < accuracy:
+        return (high, statistics.mean(trades) + high, CAR25_high)
+    while iterations < max_iterations:
+        iterations = iterations + 1
+        middle = (low + high) / 2.0
+        CAR25_middle = CAR25_with(middle)
+        if abs(CAR25_middle - target_CAR25) < accuracy:
+            return (middle, statistics.mean(trades) + middle, CAR25_middle)
+        if CAR25_middle < target_CAR25:
+            low = middle
+        else:
+            high = middle
+            CAR25_high = CAR25_middle
+
+    #  the smallest improvement known to reach the target
+    return (high, statistics.mean(trades) + high, CAR25_high)
+
+
+def run_one_evaluation(
+        trades,
+        fraction,
+        trade_indices,
+        initial_capital,
+        rules,
+        cost_model = None
+        ):
+
+    """
+    Trade one sequence of trades under a prop firm's rules,
+    less the costs of cost_model, if any.
+    
+    Returns:
+    'pass', 'fail', or 'neither'.
+    """
+
+    equity = initial_capital
+    max_equity = equity
+    for trade_index in trade_indices:
+        if cost_model is None:
+            gain = equity * fraction * trades[trade_index]
+        else:
+            gain = cost_model.net_gain(equity, fraction, trades[trade_index])
+        if rules.daily_loss_limit is not None and -gain > rules.daily_loss_limit:
+            return 'fail'
+        equity = equity + gain
+        if equity > max_equity:
+            max_equity = equity
+        if (rules.trailing_drawdown is not None and
+                max_equity - equity > rules.trailing_drawdown):
+            return 'fail'
+        if equity - initial_capital >= rules.profit_target:
+            return 'pass'
+
+    return 'neither'
+
+
+def analyze_prop_firm_evaluation(
+        trades,
+        fractions,
+        rules,
+        config
+        ):
+
+    """
+    Estimate the chances of passing a prop firm's evaluation at
+    each of several fractions.
+    
+    Each fraction is tried on number_equity_in_CDF sequences of
+    number_trades_in_forecast trades, the length of the evaluation
+    in days, starting from initial_capital, the size of the
+    evaluation account.  The sequences stop as soon as they pass
+    or fail.  The same sequences are used for every fraction.
+    
+    Parameters:
+    trades:     the set of trades to be analyzed.
+    fractions:  list of fractions to try.
+    rules:      PropFirmRules.
+    config:     RiskNormalizationConfig.
+    
+    Returns:
+    list of PropFirmOutcome, one per fraction.
+    """
+
+    trades = validate_trades(trades)
+    seed_random_generator(config.seed, config.random_algorithm)
+    all_trade_indices = draw_trade_indices(len(trades),
+                                           config.number_equity_in_CDF,
+                                           config.number_trades_in_forecast,
+                                           config.resampling_method)
+
+    outcomes = []
+    for fraction in fractions:
+        counts = {'pass': 0, 'fail': 0, 'neither': 0}
+        for i in range(config.number_equity_in_CDF):
+            counts[run_one_evaluation(trades,
+                                      fraction,
+                                      all_trade_indices[i],
+                                      config.initial_capital,
+                                      rules,
+                                      config.cost_model)] += 1
+        outcomes.append(PropFirmOutcome(
+                            fraction,
+                            counts['pass'] / config.number_equity_in_CDF,
+                            counts['fail'] / config.number_equity_in_CDF,
+                            counts['neither'] / config.number_equity_in_CDF))
+
+    return outcomes
+
+
+def analyze_update_frequency(
+        trades,
+        config,
+        update_every_days = (1, 21, 63)
+        ):
+
+    """
+    Estimate the cost of updating position size only every few
+    days, such as monthly or quarterly, rather than after every
+    trade.
+    
+    safe-f and CAR25 are first computed with continuous updates.
+    Then, for each number of days between updates, the position is
+    fraction times the equity at the latest update, as by
+    PeriodicRebalanceSizing, and the tail risk and CAR25 of trading
+    the continuous safe-f that way are estimated, along with the
+    safe-f and CAR25 found when the search allows for the delay.
+    config's position_sizing is replaced.
+    
+    Parameters:
+    trades:     the set of trades to be analyzed.
+    config:     RiskNormalizationConfig.
+    update_every_days:  Optional.  list of days between updates.
+                Default = (1, 21, 63), daily, monthly, and quarterly.
+    
+    Returns:
+    list of UpdateFrequencyOutcome, one per entry of
+    update_every_days.
+    """
+
+    trades = validate_trades(trades)
+    continuous = risk_normalization_with_config(
+                     trades, config.replace(position_sizing = None))
+    days_per_trade = (config.number_days_in_forecast /
+                      config.number_trades_in_forecast)
+
+    outcomes = []
+    for every_days in update_every_days:
+        every_trades = max(1, round(every_days / days_per_trade))
+        position_sizing = PeriodicRebalanceSizing(every_trades)
+        seed_random_generator(config.seed, config.random_algorithm,
+                              'update', every_days)
+        tail_risk = analyze_distribution_of_drawdown(
+                        trades,
+                        continuous.safe_f_mean,
+                        config.number_days_in_forecast,
+                        config.number_trades_in_forecast,
+                        config.initial_capital,
+                        config.tail_percentile,
+                        config.number_equity_in_CDF,
+                        None,
+                        config.resampling_method,
+                        config.percentile_method,
+                        config.cash_flows,
+                        config.cost_model,
+                        False,
+                        position_sizing,
+                        config.execution_noise)
+        CDF_equity = form_distribution_of_equity(
+                         trades,
+                         continuous.safe_f_mean,
+                         config.number_days_in_forecast,
+                         config.number_trades_in_forecast,
+                         config.initial_capital,
+                         config.number_equity_in_CDF,
+                         config.resampling_method,
+                         None,
+                         config.trading_days_per_year,
+                         None,
+                         config.cash_flows,
+                         None,
+                         config.cost_model,
+                         position_sizing,
+                         config.execution_noise)
+        CAR25_at_safe_f = compute_CAR(compute_percentile(
+                                          CDF_equity, 25,
+                                          config.percentile_method),
+                                      config.initial_capital,
+                                      config.number_days_in_forecast,
+                                      config.trading_days_per_year)
+        delayed = risk_normalization_with_config(
+                      trades,
+                      config.replace(position_sizing =
+                                         PeriodicRebalanceSizing(every_trades)))
+        outcomes.append(UpdateFrequencyOutcome(
+                            every_days,
+                            every_trades,
+                            tail_risk,
+                            CAR25_at_safe_f,
+                            delayed.safe_f_mean,
+                            delayed.CAR25_mean,
+                            continuous.CAR25_mean - delayed.CAR25_mean))
+
+    return outcomes
+
+
+def risk_normalization_dynamic(
+        trades,
+        config,
+        recompute_every_days = 21,
+        window_trades = None,
+        number_paths = 100
+        ):
+
+    """
+    Simulate dynamic position sizing, trading the equity curve:
+    as each forecast progresses, recompute safe-f every
+    recompute_every_days from the most recent window_trades trades,
+    the best estimate set followed by the trades of the forecast so
+    far, and trade at the new fraction until the next
+    recomputation.  A run of losses lowers safe-f, and with it the
+    position, before the drawdown grows.
+    
+    The same forecasts are traded at the static safe-f of all the
+    trades, so the distributions of CAR and drawdown can be compared.
+    Each forecast is a fixed fraction of equity, without the
+    config's cash_flows, cost_model, position_sizing, or
+    execution_noise.
+    
+    Each recomputation is a run of risk normalization with a single
+    repetition, so a call costs about number_paths times the number
+    of recomputations per forecast repetitions.  Lower number_paths,
+    or the config's number_equity_in_CDF, to go faster.
+    
+    Parameters:
+    trades:     the set of trades to be analyzed.
+    config:     RiskNormalizationConfig.
+    recompute_every_days:  Optional.  Days between recomputations.
+                Default = 21, monthly.
+    window_trades:  Optional.  The number of most recent trades
+                safe-f is recomputed from.
+                Default = None, config.number_trades_in_forecast.
+    number_paths:  Optional.  The number of forecasts.  Default = 100
+    
+    Returns:
+    DynamicSizingResult
+    """
+
+    trades = validate_trades(trades)
+    if not (isinstance(number_paths, int) and
+            not isinstance(number_paths, bool) and number_paths >= 1):
+        raise InvalidParameterError('number_paths', number_paths,
+                                    'must be a whole number, 1 or more')
+    if window_trades is None:
+        window_trades = config.number_trades_in_forecast
+    if not (isinstance(window_trades, int) and
+            not isinstance(window_trades, bool) and window_trades >= 2):
+        raise InvalidParameterError('window_trades', window_trades,
+                                    'must be a whole number, 2 or more')
+    if not (isinstance(recompute_every_days, (int, float)) and
+            not isinstance(recompute_every_days, bool) and
+            math.isfinite(recompute_every_days) and
+            recompute_every_days > 0):
+        raise InvalidParameterError('recompute_every_days',
+                                    recompute_every_days,
+                                    'must be a number greater than 0')
+
+    static_safe_f = risk_normalization_with_config(trades,
+                                                   config).safe_f_mean
+    days_per_trade = (config.number_days_in_forecast /
+                      config.number_trades_in_forecast)
+    recompute_every_trades = max(1, round(recompute_every_days /
+                                          days_per_trade))
+    recompute_config = config.replace(number_repetitions = 1,
+                                      sample_size_bootstraps = 0,
+                                      confidence_method = 'repetitions',
+                                      keep_CAR_distributions = False,
+                                      progress_sink = None,
+                                      minimum_number_trades = 1)
+
+    #  draw every forecast before any recomputation reseeds the
+    #  generator
+    seed_random_generator(config.seed, config.random_algorithm, 'dynamic')
+    all_trade_indices = draw_trade_indices(len(trades), number_paths,
+                                           config.number_trades_in_forecast,
+                                           config.resampling_method)
+
+    equity_static = []
+    equity_dynamic = []
+    drawdowns_static = []
+    drawdowns_dynamic = []
+    fractions = []
+    for path, trade_indices in enumerate(all_trade_indices):
+        equity, max_drawdown = make_one_equity_sequence(
+                                   trades,
+                                   static_safe_f,
+                                   config.number_days_in_forecast,
+                                   config.number_trades_in_forecast,
+                                   config.initial_capital,
+                                   trade_indices = trade_indices)
+        equity_static.append(equity)
+        drawdowns_static.append(max_drawdown)
+
+        history = list(trades)
+        fraction = static_safe_f
+        path_fractions = [fraction]
+        equity = config.initial_capital
+        max_equity = equity
+        max_drawdown = 0.0
+        for i, trade_index in enumerate(trade_indices):
+            if i > 0 and i % recompute_every_trades == 0:
+                if config.seed is not None:
+                    seed = derive_seed(config.seed, 'dynamic', path, i)
+                else:
+                    seed = None
+                try:
+                    with warnings.catch_warnings():
+                        warnings.simplefilter('ignore',
+                                              RiskNormalizationWarning)
+                        fraction = risk_normalization_with_config(
+                                       history[-window_trades:],
+                                       recompute_config.replace(
+                                           seed = seed)).safe_f_mean
+                except SearchDidNotConvergeError:
+                    pass
+                path_fractions.append(fraction)
+            trade = trades[trade_index]
+            history.append(trade)
+            equity = equity + equity * fraction * trade
+            if equity >= max_equity:
+                max_equity = equity
+            else:
+                max_drawdown = max(max_drawdown,
+                                   (max_equity - equity) / max_equity)
+        equity_dynamic.append(equity)
+        drawdowns_dynamic.append(max_drawdown)
+        fractions.append(path_fractions)
+
+    CARs_static = compute_CAR(np.array(equity_static),
+                              config.initial_capital,
+                              config.number_days_in_forecast,
+                              config.trading_days_per_year)
+    CARs_dynamic = compute_CAR(np.array(equity_dynamic),
+                               config.initial_capital,
+                               config.number_days_in_forecast,
+                               config.trading_days_per_year)
+
+    return DynamicSizingResult(
+               static_safe_f,
+               recompute_every_trades,
+               window_trades,
+               CARs_static,
+               CARs_dynamic,
+               np.array(drawdowns_static),
+               np.array(drawdowns_dynamic),
+               compute_percentile(CARs_static, 25, config.percentile_method),
+               compute_percentile(CARs_dynamic, 25, config.percentile_method),
+               compute_percentile(drawdowns_static,
+                                  100 - config.tail_percentile,
+                                  config.percentile_method),
+               compute_percentile(drawdowns_dynamic,
+                                  100 - config.tail_percentile,
+                                  config.percentile_method),
+               np.array(fractions))
+
+
+def required_capital_for(
+        dollar_drawdown_limit,
+        result,
+        unit_value
+        ):
+
+    """
+    The account size needed to trade one unit of a system, such as
+    one futures contract, within a limit on drawdown in dollars.
+    
+    At safe-f the position is safe_f times the account, so one unit
+    needs an account of at least unit_value / safe_f.  The drawdown
+    at the 95th percentile is a proportion of the account, taken
+    from the result's drawdown profiles, so the dollar limit allows
+    an account of at most dollar_drawdown_limit over that proportion.
+    
+    Parameters:
+    dollar_drawdown_limit:  the largest drawdown, in dollars, the
+                trader will accept.
+    result:     RiskNormalizationResult for the system's trades,
+                whose trades are returns on the value of one unit.
+    unit_value: the value of one unit, such as the notional value
+                of one contract, in dollars.
+    
+    Returns:
+    AccountSizePlan, which is not feasible if safe-f is 0.
+    """
+
+    if not dollar_drawdown_limit > 0.0:
+        raise InvalidParameterError('dollar_drawdown_limit',
+                                    dollar_drawdown_limit,
+                                    'must be greater than 0')
+    if not unit_value > 0.0:
+        raise InvalidParameterError('unit_value', unit_value,
+                                    'must be greater than 0')
+
+    tail_drawdown = statistics.mean(profile.percentile_95
+                                    for profile in result.drawdown_profiles)
+    if tail_drawdown > 0.0:
+        maximum_capital = dollar_drawdown_limit / tail_drawdown
+    else:
+        maximum_capital = math.inf
+    #  at safe-f 0, such as for trades that are all losses, no
+    #  account trades even one unit
+    if result.safe_f_mean > 0.0:
+        minimum_capital = unit_value / result.safe_f_mean
+        tail_dollar_drawdown = minimum_capital * tail_drawdown
+    else:
+        minimum_capital = math.inf
+        tail_dollar_drawdown = math.nan
+
+    return AccountSizePlan(minimum_capital,
+                           maximum_capital,
+                           tail_dollar_drawdown)
+
+
+def analyze_portfolio(
+        strategy_trades,
+        weights,
+        config,
+        aligned = False,
+        number_portfolio_trades = None
+        ):
+
+    """
+    Compute safe-f and CAR25 for several strategies traded at the
+    same time in one account.
+    
+    Each day of the portfolio, every strategy has a trade and the
+    portfolio's trade is their sum, weighted by the capital
+    allocated to each.  If aligned, the trade lists are the same
+    days, and day i of the portfolio combines trade i of every
+    strategy, keeping the correlation between them.  Otherwise each
+    strategy's trade is drawn independently of the others, to form
+    a set of number_portfolio_trades portfolio trades.  The set is
+    then analyzed as the trades of a single system with config.
+    
+    Parameters:
+    strategy_trades:  list with one list of trades per strategy.
+    weights:    list with the capital allocation weight of each
+                strategy.  They are scaled to sum to 1.
+    config:     RiskNormalizationConfig.
+    aligned:    Optional.  Default = False
+    number_portfolio_trades:  Optional.  Used when not aligned.
+                Default = None, 10 times the longest trade list.
+    
+    Returns:
+    PortfolioResult
+    """
+
+    strategy_trades = [validate_trades(trades) for trades in strategy_trades]
+    number_strategies = len(strategy_trades)
+    if number_strategies == 0:
+        raise EmptyTradesError('strategy_trades')
+    if len(weights) != number_strategies:
+        raise InvalidParameterError('weights', len(weights),
+                                    f'must have one weight for each '
+                                    f'strategy, {number_strategies}')
+    if not (all(isinstance(weight, (int, float)) and weight >= 0.0
+                for weight in weights) and sum(weights) > 0.0):
+        raise InvalidParameterError('weights', weights,
+                                    'must be 0 or more, and not all 0')
+    weights = [weight / sum(weights) for weight in weights]
+
+    if aligned:
+        lengths = {len(trades) for trades in strategy_trades}
+        if len(lengths) != 1:
+            raise InvalidParameterError('strategy_trades', sorted(lengths),
+                                        'must all be the same length '
+                                        'when aligned')
+        components = [list(trades) for trades in strategy_trades]
+    else:
+        if number_portfolio_trades is None:
+            number_portfolio_trades = 10 * max(len(trades)
+                                               for trades in strategy_trades)
+        seed_random_generator(config.seed, config.random_algorithm,
+                              'portfolio')
+        components = [list(trades[random_integers(
+                                      0, len(trades),
+                                      size=number_portfolio_trades)])
+                      for trades in strategy_trades]
+
+    weighted = [[weight * trade for trade in component]
+                for weight, component in zip(weights, components)]
+    portfolio_trades = [sum(day) for day in zip(*weighted)]
+
+    #  Each strategy's share of the variance of the portfolio is
+    #  the covariance of its weighted returns with the portfolio's.
+
+    portfolio_mean = statistics.mean(portfolio_trades)
+    portfolio_variance = sum((trade - portfolio_mean) ** 2
+                             for trade in portfolio_trades)
+    risk_contributions = []
+    for component in weighted:
+        component_mean = statistics.mean(component)
+        covariance = sum((trade - component_mean) *
+                         (portfolio_trade - portfolio_mean)
+                         for trade, portfolio_trade in zip(component,
+                                                           portfolio_trades))
+        if portfolio_variance > 0.0:
+            risk_contributions.append(covariance / portfolio_variance)
+        else:
+            risk_contributions.append(0.0)
+
+    result = risk_normalization_with_config(portfolio_trades, config)
+
+    return PortfolioResult(result, weights, risk_contributions)
+
+
+def select_results(
+        results,
+        **tags
+        ):
+
+    """
+    Choose the results whose tags match.
+    
+    Parameters:
+    results:    iterable of RiskNormalizationResult.
+    tags:       key = value pairs.  A result is chosen if it has
+                every key with that value.  A value of None matches
+                any value, as long as the key is present.
+    
+    Returns:
+    list of the chosen results, in their original order.
+    
+    Example:
+    select_results(results, variant = 'bollinger_v3', data = None)
+    """
+
+    def matches(result):
+        for key, value in tags.items():
+            if key not in result.tags:
+                return False
+            if value is not None and result.tags[key] != value:
+                return False
+        return True
+
+    return [result for result in results if matches(result)]
+
+
+def write_CAR_distributions(
+        result,
+        path
+        ):
+
+    """
+    Write every compound annual rate of return simulated at safe-f,
+    for analysis with other statistical tools.
+    
+    One row per equity curve, with columns repetition, counting
+    from 0, and CAR, in percent.  Within each repetition the CARs
+    are sorted.  A path ending in .parquet is written as Parquet,
+    which needs pyarrow or fastparquet installed.  Any other path
+    is written as csv.
+    
+    Parameters:
+    result:     RiskNormalizationResult from a config that asked to
+                keep_CAR_distributions.
+    path:       file to write.
+    """
+
+    if result.CAR_distributions is None:
+        raise InvalidParameterError('result.CAR_distributions', None,
+                  'run with keep_CAR_distributions = True to export them')
+
+    repetitions = []
+    CARs = []
+    for repetition, CAR_distribution in enumerate(result.CAR_distributions):
+        repetitions.extend([repetition] * len(CAR_distribution))
+        CARs.extend(float(CAR) for CAR in CAR_distribution)
+    table = pd.DataFrame({'repetition': repetitions, 'CAR': CARs})
+
+    if str(path).lower().endswith('.parquet'):
+        try:
+            table.to_parquet(path, index = False)
+        except ImportError:
+            raise InvalidParameterError('path', path,
+                      'writing Parquet needs pyarrow or fastparquet installed')
+    else:
+        table.to_csv(path, index = False)
+
+
+def compare_trade_sets(
+        trades_a,
+        trades_b,
+        config,
+        number_bootstraps = 20
+        ):
+
+    """
+    Run risk normalization on two sets of trades with the same
+    config and seed, and report the differences in safe-f, CAR25,
+    and drawdown at safe-f.
+    
+    Whether the difference in CAR25 is more than chance is tested
+    by bootstrap.  Both sets are resampled, with replacement, to
+    new sets of the same sizes number_bootstraps times, and CAR25
+    computed from each with a single repetition, as for
+    analyze_sample_size_variability.  Each bootstrap costs about
+    two repetitions.
+    
+    Parameters:
+    trades_a:   the first set of trades, such as 'before'.
+    trades_b:   the second set of trades, such as 'after'.
+    config:     RiskNormalizationConfig.
+    number_bootstraps:  Optional.  Default = 20.  0 skips the test.
+    
+    Returns:
+    TradeSetComparison
+    """
+
+    trades_a = validate_trades(trades_a)
+    trades_b = validate_trades(trades_b)
+    result_a = risk_normalization_with_config(trades_a, config)
+    result_b = risk_normalization_with_config(trades_b, config)
+
+    bootstrap_config = config.replace(number_repetitions = 1,
+                                      sample_size_bootstraps = 0,
+                                      confidence_method = 'repetitions',
+                                      keep_CAR_distributions = False,
+                                      progress_sink = None,
+                                      seed = None,
+                                      minimum_number_trades = 1)
+    seed_random_generator(config.seed, config.random_algorithm, 'compare')
+
+    CAR25_deltas = []
+    for bootstrap in range(number_bootstraps):
+        resampled = [trades[draw_trade_indices(len(trades), 1, len(trades),
+                                               config.resampling_method)[0]]
+                     for trades in (trades_a, trades_b)]
+        try:
+            with warnings.catch_warnings():
+                warnings.simplefilter('ignore', RiskNormalizationWarning)
+                CAR25_a, CAR25_b = [
+                    risk_normalization_with_config(trades,
+                                                   bootstrap_config).CAR25_mean
+                    for trades in resampled]
+        except SearchDidNotConvergeError:
+            continue
+        CAR25_deltas.append(CAR25_b - CAR25_a)
+
+    if len(CAR25_deltas) > 0:
+        CAR25_delta_low = compute_percentile(CAR25_deltas, 5,
+                                             config.percentile_method)
+        CAR25_delta_high = compute_percentile(CAR25_deltas, 95,
+                                              config.percentile_method)
+        #  the proportion of resampled differences on the other
+        #  side of 0 from the median, counted in both tails
+        below = sum(delta <= 0.0 for delta in CAR25_deltas)
+        above = sum(delta >= 0.0 for delta in CAR25_deltas)
+        p_value = min(1.0, 2.0 * min(below, above) / len(CAR25_deltas))
+    else:
+        CAR25_delta_low = 0.0
+        CAR25_delta_high = 0.0
+        p_value = None
+
+    return TradeSetComparison(result_a, result_b, len(CAR25_deltas),
+                              CAR25_delta_low, CAR25_delta_high, p_value)
+
+
+TRADE_FILE_SUFFIXES = ('.csv', '.txt', '.json', '.parquet', '.arrow',
+                       '.feather', '.ipc')
+
+
+def list_trade_files(
+        paths
+        ):
+
+    """
+    The files of trades named by paths.  A file is listed as given.
+    A directory gives its files whose names end in one of
+    TRADE_FILE_SUFFIXES, in order of name.
+    """
+
+    files = []
+    for path in paths:
+        if os.path.isdir(path):
+            files.extend(os.path.join(path, name)
+                         for name in sorted(os.listdir(path))
+                         if name.lower().endswith(TRADE_FILE_SUFFIXES) and
+                             os.path.isfile(os.path.join(path, name)))
+        else:
+            files.append(path)
+
+    return files
+
+
+def rank_strategies(
+        paths,
+        config,
+        options = None,
+        field = None
+        ):
+
+    """
+    Run risk normalization on several files of trades with the same
+    config and seed, and rank them, best first, by CAR25, then by
+    safe-f, to decide which systems to allocate capital to.
+    
+    Parameters:
+    paths:      list of files of trades and of directories holding
+                them.  See list_trade_files.
+    config:     RiskNormalizationConfig.  A config with no strategy
+                names each result after its file.
+    options:    Optional.  CsvOptions for csv files.
+    field:      Optional.  The field or column of the trades in
+                JSON, Parquet, and Arrow files.
+    
+    Returns:
+    list of StrategyRanking, best first.
+    
+    Raises the errors of read_trades and risk_normalization_with_config,
+    naming the file in OSError.
+    """
+
+    files = list_trade_files(paths)
+    if len(files) == 0:
+        raise InvalidParameterError('paths', paths,
+                                    'must name at least one file of trades')
+
+    runs = []
+    for path in files:
+        name = os.path.splitext(os.path.basename(path))[0]
+        trades = read_trades(path, options, field)
+        if config.strategy is None:
+            run_config = config.replace(strategy = StrategyInfo(name = name))
+        else:
+            run_config = config
+        runs.append((name, path,
+                     risk_normalization_with_config(trades, run_config)))
+
+    #  sorted is stable, so ties keep the order of the files
+    runs.sort(key = lambda run: (-run[2].CAR25_mean, -run[2].safe_f_mean))
+
+    return [StrategyRanking(rank + 1, name, path, result)
+            for rank, (name, path, result) in enumerate(runs)]
+
+
+RANKING_FORMATS = ('text', 'csv', 'json')
+
+
+def format_ranking(
+        rankings,
+        ranking_format = 'text'
+        ):
+
+    """
+    A table of rankings, one row per strategy, best first, with
+    columns rank, strategy, path, CAR25_mean, CAR25_stdev,
+    safe_f_mean, and safe_f_stdev.
+    
+    Parameters:
+    rankings:   list of StrategyRanking from rank_strategies.
+    ranking_format:  one of RANKING_FORMATS.  'text' aligns the
+                columns for reading, 'csv' writes a header line and
+                one line per strategy, and 'json' an array with an
+                object per strategy.
+    
+    Returns:
+    the table as a string.
+    """
+
+    if ranking_format not in RANKING_FORMATS:
+        raise InvalidParameterError('ranking_format', ranking_format,
+                                    f'must be one of {RANKING_FORMATS}')
+
+    rows = [{'rank': ranking.rank,
+             'strategy': ranking.name,
+             'path': str(ranking.path),
+             'CAR25_mean': ranking.result.CAR25_mean,
+             'CAR25_stdev': ranking.result.CAR25_stdev,
+             'safe_f_mean': ranking.result.safe_f_mean,
+             'safe_f_stdev': ranking.result.safe_f_stdev}
+            for ranking in rankings]
+
+    if ranking_format == 'json':
+        return json.dumps(rows, indent = 1) + '\n'
+
+    if ranking_format == 'csv':
+        text = io.StringIO()
+        writer = csv.DictWriter(text, fieldnames = ['rank', 'strategy',
+                                                    'path', 'CAR25_mean',
+                                                    'CAR25_stdev',
+                                                    'safe_f_mean',
+                                                    'safe_f_stdev'],
+                                lineterminator = '\n')
+        writer.writeheader()
+        writer.writerows(rows)
+        return text.getvalue()
+
+    width = max([len('strategy')] + [len(row['strategy']) for row in rows])
+    lines = [f'{"rank":>4}  {"strategy":{width}}  {"CAR25":>8}  '
+             f'{"stdev":>6}  {"safe-f":>6}  {"stdev":>6}']
+    for row in rows:
+        lines.append(f'{row["rank"]:4d}  {row["strategy"]:{width}}  '
+                     f'{row["CAR25_mean"]:7.2f}%  {row["CAR25_stdev"]:6.2f}  '
+                     f'{row["safe_f_mean"]:6.3f}  {row["safe_f_stdev"]:6.3f}')
+
+    return '\n'.join(lines) + '\n'
+
+
+def analyze_sample_size_variability(
+        trades,
+        config
+        ):
+
+    """
+    Estimate how much safe-f and CAR25 depend on which trades
+    happened to be observed.
+    
+    Resample the trades, with replacement, to config.sample_size_bootstraps
+    new sets of the same size, and compute safe-f and CAR25 from
+    each with a single repetition.
+    
+    Returns:
+    advisory:  SampleSizeAdvisory.
+    """
+
+    trades = validate_trades(trades)
+    number_trades = len(trades)
+    bootstrap_config = config.replace(number_repetitions = 1,
+                                      sample_size_bootstraps = 0,
+                                      confidence_method = 'repetitions',
+                                      keep_CAR_distributions = False,
+                                      progress_sink = None,
+                                      seed = None,
+                                      minimum_number_trades = 1)
+
+    safe_fs = []
+    CAR25s = []
+    number_failed = 0
+    for bootstrap in range(config.sample_size_bootstraps):
+        resampled_trades = trades[draw_trade_indices(
+                                      number_trades, 1, number_trades,
+                                      config.resampling_method)[0]]
+        try:
+            #  the advisory speaks for the bootstraps as a whole
+            with warnings.catch_warnings():
+                warnings.simplefilter('ignore', RiskNormalizationWarning)
+                result = risk_normalization_with_config(resampled_trades,
+                                                        bootstrap_config)
+        except SearchDidNotConvergeError:
+            number_failed = number_failed + 1
+            continue
+        safe_fs.append(result.safe_f_mean)
+        CAR25s.append(result.CAR25_mean)
+
+    if len(safe_fs) > 1:
+        safe_f_stdev = statistics.stdev(safe_fs)
+    else:
+        safe_f_stdev = 0.0
+    #  safe-f is never negative, so a mean of 0, such as for trades
+    #  that are all losses, means every bootstrap gave 0
+    if safe_f_stdev > 0.0:
+        safe_f_variation = safe_f_stdev / statistics.mean(safe_fs)
+    else:
+        safe_f_variation = 0.0
+    if len(CAR25s) > 0:
+        CAR25_low = compute_percentile(CAR25s, 5,
+                                       config.percentile_method)
+        CAR25_high = compute_percentile(CAR25s, 95,
+                                        config.percentile_method)
+    else:
+        CAR25_low = 0.0
+        CAR25_high = 0.0
+
+    return SampleSizeAdvisory(number_trades,
+                              config.sample_size_bootstraps,
+                              number_failed,
+                              safe_f_stdev,
+                              safe_f_variation,
+                              CAR25_low,
+                              CAR25_high,
+                              config.language,
+                              safe_f_list = safe_fs,
+                              CAR25_list = CAR25s)
+
+
+#  The layout of the files written by record_session.  Increase it
+#  when Session changes, so that older files are refused rather
+#  than misread.
+SESSION_VERSION = 1
+
+
+def environment_info():
+
+    """
+    Returns:
+    dictionary describing where a run is made: the versions of
+    Python and numpy, the operating system, the processor, and the
+    byte order.
+    """
+
+    return {'python': platform.python_version(),
+            'numpy': np.__version__,
+            'platform': platform.platform(),
+            'machine': platform.machine(),
+            'byte_order': sys.byteorder}
+
+
+class Session:
+
+    """
+    Everything needed to make a run again exactly, such as one a
+    user reports giving unexpected results: the trades, the config
+    with the seed the run used, and the environment it ran in,
+    with the results it gave.
+    
+    Attributes:
+    trades:       numpy array of the trades given to the run.
+    config:       RiskNormalizationConfig of the run, with its seed,
+                  and without its progress_sink, executor, or
+                  cancel_event.
+    environment:  dictionary from environment_info when recorded.
+    arguments:    None, or the list of command line arguments of
+                  the run.
+    recorded_at:  the time, in UTC, at which the session was
+                  recorded.
+    safe_f_list, CAR25_list:  the results of each repetition of
+                  the run.
+    version:      SESSION_VERSION when recorded.
+    """
+
+    def __init__(self,
+                 trades,
+                 config,
+                 environment,
+                 arguments,
+                 recorded_at,
+                 safe_f_list,
+                 CAR25_list,
+                 version = SESSION_VERSION):
+        self.trades = trades
+        self.config = config
+        self.environment = environment
+        self.arguments = arguments
+        self.recorded_at = recorded_at
+        self.safe_f_list = safe_f_list
+        self.CAR25_list = CAR25_list
+        self.version = version
+
+    def environment_differences(self):
+
+        """
+        Returns:
+        dictionary from each entry of environment_info that differs
+        from the recorded one to a (recorded, current) pair.  Empty
+        when the replay is made in the same environment.
+        """
+
+        current = environment_info()
+
+        return {name: (self.environment.get(name), value)
+                for name, value in current.items()
+                if self.environment.get(name) != value}
+
+    def matches(self, result):
+
+        """
+        Returns:
+        True if result, such as from replay_session, has the
+        recorded safe-f and CAR25 of every repetition exactly.
+        """
+
+        return (list(result.safe_f_list) == list(self.safe_f_list) and
+                list(result.CAR25_list) == list(self.CAR25_list))
+
+    def __repr__(self):
+        return (f'Session({len(self.trades)} trades, '
+                f'seed={self.config.seed}, '
+                f'recorded_at={self.recorded_at.isoformat()})')
+
+
+#  The only objects, other than those of this module, that a session
+#  file may hold.  Unpickling anything else could run code from the
+#  file.
+SESSION_GLOBALS = frozenset([
+    ('builtins', 'complex'),
+    ('builtins', 'frozenset'),
+    ('builtins', 'set'),
+    ('builtins', 'slice'),
+    ('collections', 'OrderedDict'),
+    ('datetime', 'datetime'),
+    ('datetime', 'timedelta'),
+    ('datetime', 'timezone'),
+    ('numpy', 'dtype'),
+    ('numpy', 'ndarray'),
+    ('numpy.core.multiarray', '_reconstruct'),
+    ('numpy.core.multiarray', 'scalar'),
+    ('numpy._core.multiarray', '_reconstruct'),
+    ('numpy._core.multiarray', 'scalar')])
+
+
+class SessionUnpickler(pickle.Unpickler):
+
+    """
+    Reads a session file, refusing any object that is not a class
+    of this module or one of SESSION_GLOBALS.
+    """
+
+    def find_class(self, module, name):
+        if (module, name) in SESSION_GLOBALS:
+            return super().find_class(module, name)
+        #  run as a script, this module is __main__
+        if module in ('risk_normalization', '__main__'):
+            value = globals().get(name)
+            if isinstance(value, type):
+                return value
+        raise pickle.UnpicklingError(f'{module}.{name} is not allowed '
+                                     f'in a session')
+
+
+def record_session(
+        path,
+        trades,
+        result,
+        arguments = None
+        ):
+
+    """
+    Write a run to one file, so that it can be made again exactly
+    with read_session and replay_session, such as to attach to a
+    report of unexpected results.
+    
+    The file is gzip compressed and written with pickle, so that
+    every part of the config is kept.  A trade transform, cost
+    model, or position sizing of the caller's own is refused when
+    the file is read.
+    
+    Parameters:
+    path:       file to write, such as 'session.bin'.
+    trades:     the set of trades given to the run.
+    result:     RiskNormalizationResult of the run, which carries
+                its config and seed.
+    arguments:  Optional.  The command line arguments of the run.
+                Default = None
+    """
+
+    if result.config is None or result.simulation_seed is None:
+        raise InvalidParameterError('result', result,
+                                    'must carry the config and '
+                                    'simulation_seed of its run')
+
+    config = result.config.replace(seed = result.simulation_seed.seed,
+                                   progress_sink = None,
+                                   executor = None,
+                                   cancel_event = None)
+    session = Session(np.asarray(trades, dtype = float),
+                      config,
+                      environment_info(),
+                      arguments,
+                      datetime.datetime.now(datetime.timezone.utc),
+                      list(result.safe_f_list),
+                      list(result.CAR25_list))
+
+    with gzip.open(path, 'wb') as f:
+        pickle.dump(session, f, protocol = 4)
+
+
+def read_session(
+        path
+        ):
+
+    """
+    Read a file written by record_session.
+    
+    Only the classes of this module, numpy arrays, and a few types
+    of the standard library are read from the file, so reading one
+    sent by someone else does not run code from it.
+    
+    Returns:
+    Session
+    
+    Raises SessionFileError if the file is not such a session.
+    """
+
+    with open(path, 'rb') as raw:
+        try:
+            with gzip.GzipFile(fileobj = raw) as f:
+                session = SessionUnpickler(f).load()
+        except (OSError, EOFError, pickle.UnpicklingError,
+                AttributeError, TypeError) as error:
+            raise SessionFileError(path,
+                                   str(error) or 'the file is truncated')
+
+    if not isinstance(session, Session):
+        raise SessionFileError(path, 'not a session written by '
+                                     'record_session')
+    if session.version != SESSION_VERSION:
+        raise SessionFileError(path, f'session version {session.version}, '
+                                     f'this program reads version '
+                                     f'{SESSION_VERSION}')
+
+    return session
+
+
+def replay_session(
+        session,
+        progress_sink = None
+        ):
+
+    """
+    Make the run of a session again, with its trades, config, and
+    seed.  Compare the result with session.matches, and look to
+    session.environment_differences for the cause of any
+    difference.
+    
+    Parameters:
+    session:        Session from read_session.
+    progress_sink:  Optional.  A ProgressSink for the replay.
+                    Default = None
+    
+    Returns:
+    RiskNormalizationResult
+    """
+
+    return risk_normalization_with_config(
+               session.trades,
+               session.config.replace(progress_sink = progress_sink))
+
+#-----------------------------------------------------
+
+def main(
+        arguments = None
+        ):
+
+    """
+    Estimate safe-f and CAR25 for a file of trades in any format,
+    with the parameters given on the command line.
+    Run with --help for the list of options.
+    """
+
+    newline = '\n'
+
+    parser = argparse.ArgumentParser(
+                 description = 'Estimate safe-f and CAR25 for a file '
+                               'of trades.')
+    #  RSIDailyGains.csv covers about 28 years of daily trading.
+    #  It contains 1185 trades.
+    #  A 2 year forecast will have about 84 trades.
+    parser.add_argument('--trades', default = './data/RSIDailyGains.csv',
+                        help = 'csv, JSON, Parquet, or Arrow file of '
+                               'trades, default %(default)s')
+    parser.add_argument('--stdin', action = 'store_true',
+                        help = 'read the trades from standard input, '
+                               'as csv, one number per line, or a JSON '
+                               'array, instead of --trades')
+    parser.add_argument('--compare', default = None, metavar = 'PATH',
+                        help = 'second csv or JSON file of trades, run '
+                               'with the same settings, to compare')
+    parser.add_argument('--rank', nargs = '+', default = None,
+                        metavar = 'PATH',
+                        help = 'files of trades, or directories of them, '
+                               'to run with the same settings and rank '
+                               'by CAR25, then safe-f')
+    parser.add_argument('--rank-format', choices = RANKING_FORMATS,
+                        default = 'text',
+                        help = 'format of the --rank table, '
+                               'default %(default)s')
+    parser.add_argument('--strategy-name', default = None,
+                        help = 'name of the trading system, '
+                               'carried in the results')
+    parser.add_argument('--tag', action = 'append', default = [],
+                        metavar = 'KEY=VALUE',
+                        help = 'tag carried in the results, may be '
+                               'repeated')
+    parser.add_argument('--export-car', default = None, metavar = 'PATH',
+                        help = 'write every CAR simulated at safe-f to '
+                               'this csv or .parquet file')
+    parser.add_argument('--scenarios', default = None, metavar = 'PATH',
+                        help = 'TOML file of stress scenarios')
+    parser.add_argument('--scenario', default = None, metavar = 'NAME',
+                        help = 'run under this scenario from --scenarios')
+    parser.add_argument('--json-field', default = None,
+                        help = 'field holding the trade, for a JSON '
+                               'array of objects, or column holding '
+                               'the trades, for a Parquet or Arrow file')
+    parser.add_argument('--preview', action = 'store_true',
+                        help = 'check and summarize the trades, '
+                               'then stop')
+    parser.add_argument('--years-forecast', type = float, default = 2.0,
+                        help = 'length of the forecast in years, '
+                               'default %(default)s')
+    parser.add_argument('--trading-days-per-year', default = '252',
+                        metavar = 'DAYS',
+                        help = 'whole number, or one of '
+                               f'{", ".join(TRADING_DAYS_PER_YEAR)}, '
+                               'default %(default)s')
+    parser.add_argument('--trades-in-forecast', type = int, default = 84,
+                        help = 'number of trades in the forecast, '
+                               'default %(default)s, 2 * (1185 / 28)')
+    parser.add_argument('--initial-capital', type = float,
+                        default = 100000.0,
+                        help = 'default %(default)s')
+    parser.add_argument('--tail-percentile', type = float, default = 5,
+                        help = 'default %(default)s')
+    parser.add_argument('--drawdown-tolerance', type = float,
+                        default = 0.10,
+                        help = 'default %(default)s')
+    parser.add_argument('--max-fraction', type = float, default = None,
+                        help = 'largest fraction, or leverage, safe-f '
+                               'may reach')
+    parser.add_argument('--initial-margin', type = float, default = None,
+                        help = "the broker's initial margin, as a "
+                               'proportion of the value of a position')
+    parser.add_argument('--equity-in-cdf', type = int, default = 100,
+                        help = 'equity curves per distribution, '
+                               'default %(default)s')
+    parser.add_argument('--repetitions', type = int, default = 5,
+                        help = 'default %(default)s')
+    parser.add_argument('--seed', type = int, default = None,
+                        help = 'seed for repeatable results')
+    parser.add_argument('--concurrent', type = int, default = None,
+                        metavar = 'WORKERS',
+                        help = 'evaluate candidate fractions in this '
+                               'many worker processes')
+    parser.add_argument('--record', default = None, metavar = 'PATH',
+                        help = 'write the trades, settings, seed, and '
+                               'environment of the run to this file, '
+                               'to make it again with --replay')
+    parser.add_argument('--replay', default = None, metavar = 'PATH',
+                        help = 'make the run recorded in this file '
+                               'again, and compare the results')
+    parser.add_argument('--language', choices = sorted(MESSAGES),
+                        default = 'en',
+                        help = 'language of the warnings, '
+                               'default %(default)s')
+    options = parser.parse_args(arguments)
+
+    if options.replay is not None:
+        try:
+            session = read_session(options.replay)
+        except OSError as error:
+            parser.error(f'cannot read {options.replay}: {error.strerror}')
+        except RiskNormalizationError as error:
+            parser.error(str(error))
+        print (f'{newline}Replaying the run recorded in {options.replay} '
+               f'at {session.recorded_at.isoformat()}')
+        if session.arguments is not None:
+            print (f'Arguments:  {" ".join(session.arguments)}')
+        for name, (recorded, current) in (
+                session.environment_differences().items()):
+            print (f'{name + ":":12s}recorded {recorded}, now {current}')
+        try:
+            result = replay_session(session, PrintProgress())
+        except RiskNormalizationError as error:
+            parser.error(str(error))
+        print (f'CAR25 mean:   {result.CAR25_mean:.2f}%')
+        print (f'safe-f mean:  {result.safe_f_mean:.2f}')
+        if session.matches(result):
+            print ('The replay matches the recorded results exactly.')
+        else:
+            print ('The replay differs from the recorded results:')
+            print (f'recorded safe-f:  {session.safe_f_list}')
+            print (f'replayed safe-f:  {list(result.safe_f_list)}')
+            print (f'recorded CAR25:   {session.CAR25_list}')
+            print (f'replayed CAR25:   {list(result.CAR25_list)}')
+        return
+
+    if options.trading_days_per_year in TRADING_DAYS_PER_YEAR:
+        trading_days_per_year = TRADING_DAYS_PER_YEAR[
+                                    options.trading_days_per_year]
+    elif options.trading_days_per_year.isdigit():
+        trading_days_per_year = int(options.trading_days_per_year)
+    else:
+        parser.error(f'--trading-days-per-year '
+                     f'{options.trading_days_per_year}: must be a whole '
+                     f'number or one of {", ".join(TRADING_DAYS_PER_YEAR)}')
+
+    tags = {}
+    for tag in options.tag:
+        key, equals, value = tag.partition('=')
+        if equals == '' or key.strip() == '':
+            parser.error(f'--tag {tag}: must be KEY=VALUE')
+        tags[key.strip()] = value.strip()
+
+    scenario = None
+    if options.scenario is not None:
+        if options.scenarios is None:
+            parser.error('--scenario needs --scenarios')
+        try:
+            scenarios = load_scenarios(options.scenarios)
+        except OSError as error:
+            parser.error(f'cannot read {options.scenarios}: '
+                         f'{error.strerror}')
+        except RiskNormalizationError as error:
+            parser.error(str(error))
+        if options.scenario not in scenarios:
+            parser.error(f'--scenario {options.scenario}: not in '
+                         f'{options.scenarios}')
+        scenario = scenarios[options.scenario]
+
+    try:
+        config = RiskNormalizationConfig(
+                     number_days_in_forecast =
+                         round(options.years_forecast *
+                               trading_days_per_year),
+                     number_trades_in_forecast = options.trades_in_forecast,
+                     initial_capital = options.initial_capital,
+                     tail_percentile = options.tail_percentile,
+                     drawdown_tolerance = options.drawdown_tolerance,
+                     max_fraction = options.max_fraction,
+                     initial_margin = options.initial_margin,
+                     number_equity_in_CDF = options.equity_in_cdf,
+                     number_repetitions = options.repetitions,
+                     number_workers = options.concurrent,
+                     seed = options.seed,
+                     keep_CAR_distributions = options.export_car is not None,
+                     progress_sink = PrintProgress(),
+                     strategy = StrategyInfo(name = options.strategy_name),
+                     tags = tags,
+                     trading_days_per_year = trading_days_per_year,
+                     scenario = scenario,
+                     language = options.language)
+    except InvalidParameterError as error:
+        parser.error(str(error))
+
+    if options.rank is not None:
+        try:
+            rankings = rank_strategies(options.rank,
+                                       config.replace(progress_sink = None,
+                                                      strategy = None),
+                                       field = options.json_field)
+        except OSError as error:
+            parser.error(f'cannot read {error.filename}: {error.strerror}')
+        except RiskNormalizationError as error:
+            parser.error(str(error))
+        print (format_ranking(rankings, options.rank_format), end = '')
+        return
+
+    if options.stdin:
+        path_to_trades = '<stdin>'
+        text = sys.stdin.read()
+    else:
+        path_to_trades = options.trades
+        text = None
+    print (f'{newline}The data file being processed is: {path_to_trades}')
 
-print (f'CAR25 mean:   {CAR25_mean:.2f}%')
-print (f'CAR25 stdev:  {CAR25_stdev:.2f}')
-print (f'safe-f mean:  {safe_f_mean:.2f}')
-print (f'safe-f stdev: {safe_f_stdev:.2f}')
+    try:
+        if text is not None:
+            trades = parse_trades_of_any_format(text, path_to_trades,
+                                                field = options.json_field)
+        else:
+            trades = read_trades(path_to_trades, field = options.json_field)
+    except OSError as error:
+        parser.error(f'cannot read {path_to_trades}: {error.strerror}')
+    except RiskNormalizationError as error:
+        parser.error(str(error))
+    print (f'There are {len(trades)} marked-to-market daily trades in the file')
+    print ('Here are the first 10 trades:')
+    print (trades[0:10])
 
+    if options.preview:
+        try:
+            preview = preview_trades(None if text is not None
+                                     else path_to_trades,
+                                     text = text,
+                                     field = options.json_field,
+                                     number_shown = 10)
+        except RiskNormalizationError as error:
+            parser.error(str(error))
+        print ('Here are the last 10 trades:')
+        print (preview.last_trades)
+        print (f'Mean trade:    {preview.mean:0.6f}')
+        print (f'Stdev:         {preview.stdev:0.6f}')
+        print (f'Win rate:      {preview.win_rate * 100:0.2f}%')
+        print (f'Largest loss:  {preview.largest_loss:0.6f}')
+        for warning in preview.warnings:
+            print (f'Warning:  {warning.message}')
+        return
 
+    if options.compare is not None:
+        try:
+            compare_trades = read_trades(options.compare,
+                                         field = options.json_field)
+        except OSError as error:
+            parser.error(f'cannot read {options.compare}: {error.strerror}')
+        except RiskNormalizationError as error:
+            parser.error(str(error))
+        try:
+            comparison = compare_trade_sets(trades, compare_trades,
+                                            config.replace(
+                                                progress_sink = None))
+        except RiskNormalizationError as error:
+            parser.error(str(error))
+        print (f'{newline}{options.compare} less {path_to_trades}:')
+        print (f'safe-f:        {comparison.safe_f_delta:+.3f}')
+        print (f'CAR25:         {comparison.CAR25_delta:+.2f}%  '
+               f'(90% range {comparison.CAR25_delta_low:+.2f}% to '
+               f'{comparison.CAR25_delta_high:+.2f}%, '
+               f'p = {comparison.p_value})')
+        for name, delta in comparison.drawdown_deltas.items():
+            print (f'drawdown {name + ":":16s}{delta:+.4f}')
+        return
 
+    try:
+        result = risk_normalization_with_config(trades, config)
+    except RiskNormalizationError as error:
+        parser.error(str(error))
+    safe_f_mean,safe_f_stdev,CAR25_mean,CAR25_stdev = result
+    if options.export_car is not None:
+        try:
+            write_CAR_distributions(result, options.export_car)
+        except OSError as error:
+            parser.error(f'cannot write {options.export_car}: '
+                         f'{error.strerror}')
+        except RiskNormalizationError as error:
+            parser.error(str(error))
+    if options.record is not None:
+        if arguments is None:
+            arguments = sys.argv[1:]
+        try:
+            record_session(options.record, trades, result, list(arguments))
+        except OSError as error:
+            parser.error(f'cannot write {options.record}: {error.strerror}')
 
 
+    print (f'CAR25 mean:   {CAR25_mean:.2f}%')
+    print (f'CAR25 stdev:  {CAR25_stdev:.2f}')
+    print (f'safe-f mean:  {safe_f_mean:.2f}')
+    print (f'safe-f stdev: {safe_f_stdev:.2f}')
 
 
+if __name__ == '__main__':
+    main()
@@ -0,0 +1,245 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+"""
+sweep.py
+
+Shows how safe-f and CAR25 respond to the parameters of the
+trader's risk statement, by running risk normalization over a grid
+of one or two parameters.
+
+sweep:
+    Runs risk_normalization_with_config for every combination of
+    the values given for one or two parameters, such as
+    drawdown_tolerance from 0.05 to 0.25 and a forecast of 1 to 5
+    years, in parallel, and returns a SweepGrid.
+
+SweepGrid.values_of:
+    A two dimensional array of one result attribute, such as
+    safe_f_mean or CAR25_mean, with a row for each value of the
+    first parameter and a column for each value of the second,
+    ready for a heatmap.
+
+SweepGrid.compute_usage:
+    The processor time and equity sequences of the whole sweep,
+    to attribute its cost.
+
+Every parameter of RiskNormalizationConfig may be swept.  So may
+years_forecast, which sets number_days_in_forecast from the config's
+trading_days_per_year and scales number_trades_in_forecast with it.
+
+Each combination is run with the config's seed, so the differences
+across the grid come from the parameters, not from the random
+numbers.
+
+License:  MIT
+"""
+
+import concurrent.futures
+
+import numpy as np
+
+from risk_normalization import ComputeUsage
+from risk_normalization import InvalidParameterError
+from risk_normalization import SearchDidNotConvergeError
+from risk_normalization import risk_normalization_with_config
+
+
+def parameter_range(
+    start,
+    stop,
+    step    ):
+
+    """
+    The values from start to stop, including stop, step apart,
+    such as parameter_range(0.05, 0.25, 0.05).
+    """
+
+    if not step > 0.0 or stop < start:
+        raise InvalidParameterError('step', step,
+                                    'must be greater than 0, with stop '
+                                    'no less than start')
+    number_values = int(round((stop - start) / step)) + 1
+
+    return [start + i * step for i in range(number_values)]
+
+
+def apply_parameter(
+    config,
+    name,
+    value   ):
+
+    """
+    Return a copy of config with one swept parameter set.
+    """
+
+    if name == 'years_forecast':
+        number_days = round(value * config.trading_days_per_year)
+        number_trades = max(1, round(config.number_trades_in_forecast *
+                                     number_days /
+                                     config.number_days_in_forecast))
+        return config.replace(number_days_in_forecast = number_days,
+                              number_trades_in_forecast = number_trades)
+
+    if not hasattr(config, name):
+        raise InvalidParameterError('parameters', name,
+                                    'is not a parameter of '
+                                    'RiskNormalizationConfig')
+
+    return config.replace(**{name: value})
+
+
+def run_one_cell(
+    trades,
+    config  ):
+
+    """
+    Run one combination of the grid.
+
+    Returns:
+    RiskNormalizationResult, or None if safe-f could not be found.
+    """
+
+    try:
+        return risk_normalization_with_config(trades, config)
+    except SearchDidNotConvergeError:
+        return None
+
+
+class SweepGrid:
+
+    """
+    The results of a sweep.
+
+    Attributes:
+    names:    list of the one or two parameters swept.
+    values:   list with the values of each parameter.
+    results:  list with a row for each value of the first
+              parameter, each a list with a RiskNormalizationResult
+              for each value of the second, or with one result if
+              only one parameter was swept.  None where safe-f
+              could not be found.
+    """
+
+    def __init__(self,
+                 names,
+                 values,
+                 results):
+        self.names = names
+        self.values = values
+        self.results = results
+
+    def values_of(self, attribute):
+
+        """
+        Returns:
+        numpy array of the attribute, such as 'safe_f_mean' or
+        'CAR25_mean', of each result, with one row per value of the
+        first parameter and one column per value of the second.
+        nan where safe-f could not be found.
+        """
+
+        return np.array([[getattr(result, attribute)
+                          if result is not None else float('nan')
+                          for result in row]
+                         for row in self.results])
+
+    def compute_usage(self):
+
+        """
+        Returns:
+        ComputeUsage of every combination whose safe-f was found.
+        """
+
+        usages = [result.compute_usage
+                  for row in self.results
+                  for result in row
+                  if result is not None]
+
+        return ComputeUsage(sum(usage.cpu_seconds for usage in usages),
+                            sum(usage.number_paths for usage in usages))
+
+    def __repr__(self):
+        shape = ' by '.join(f'{len(values)} {name}'
+                            for name, values in zip(self.names,
+                                                    self.values))
+        return f'SweepGrid({shape})'
+
+
+def sweep(
+    trades,
+    config,
+    parameters,
+    number_workers = None   ):
+
+    """
+    Run risk normalization for every combination of the values of
+    one or two parameters.
+
+    Parameters:
+    trades:      the set of trades to be analyzed.
+    config:      RiskNormalizationConfig with the other parameters.
+    parameters:  dictionary from the name of each parameter to the
+                 list of its values, such as
+                 {'drawdown_tolerance': parameter_range(0.05, 0.25, 0.05),
+                  'years_forecast': [1, 2, 3, 4, 5]}
+                 The grid has a row for each value of the first and
+                 a column for each value of the second.
+    number_workers:  Optional.  The number of worker processes that
+                 run the combinations at the same time.  The calling
+                 program must protect its main code with
+                 if __name__ == '__main__':
+                 Default = None, one after another in this process.
+
+    Returns:
+    SweepGrid
+    """
+
+    if not (isinstance(parameters, dict) and 1 <= len(parameters) <= 2):
+        raise InvalidParameterError('parameters', parameters,
+                                    'must be a dictionary naming one or '
+                                    'two parameters')
+    names = list(parameters)
+    values = [list(parameters[name]) for name in names]
+    for name, parameter_values in zip(names, values):
+        if len(parameter_values) == 0:
+            raise InvalidParameterError(name, parameter_values,
+                                        'must have at least one value '
+                                        'to sweep')
+    if len(names) == 1:
+        values.append([None])
+
+    configs = []
+    for first in values[0]:
+        row = []
+        for second in values[1]:
+            cell_config = apply_parameter(config, names[0], first)
+            if second is not None:
+                cell_config = apply_parameter(cell_config, names[1], second)
+            #  the cells run in worker processes of their own, but
+            #  evaluate the same number of candidates per pass
+            if cell_config.number_candidates is None:
+                number_candidates = cell_config.number_workers
+            else:
+                number_candidates = cell_config.number_candidates
+            row.append(cell_config.replace(progress_sink = None,
+                                           number_workers = None,
+                                           number_candidates =
+                                               number_candidates))
+        configs.append(row)
+
+    if number_workers is None:
+        results = [[run_one_cell(trades, cell_config)
+                    for cell_config in row]
+                   for row in configs]
+    else:
+        with concurrent.futures.ProcessPoolExecutor(number_workers) as pool:
+            futures = [[pool.submit(run_one_cell, trades, cell_config)
+                        for cell_config in row]
+                       for row in configs]
+            results = [[future.result() for future in row]
+                       for row in futures]
+
+    if len(names) == 1:
+        values = values[:1]
+
+    return SweepGrid(names, values, results)
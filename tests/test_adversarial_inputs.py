@@ -0,0 +1,163 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+"""
+test_adversarial_inputs.py
+
+Trades and parameters at the edges, which must give results or
+raise a RiskNormalizationError, never another error such as
+ZeroDivisionError or IndexError.
+
+Run from the top of the repository:
+    python3 -m unittest discover tests
+
+License:  MIT
+"""
+
+import math
+import unittest
+import warnings
+
+from risk_normalization import EmptyTradesError
+from risk_normalization import InvalidParameterError
+from risk_normalization import JsonParseError
+from risk_normalization import MAX_FRACTION
+from risk_normalization import RiskNormalizationConfig
+from risk_normalization import RiskNormalizationWarning
+from risk_normalization import TooFewTradesError
+from risk_normalization import parse_trades_from_json
+from risk_normalization import required_capital_for
+from risk_normalization import risk_normalization
+from risk_normalization import risk_normalization_with_config
+
+
+class AdversarialTradesTest(unittest.TestCase):
+
+    def setUp(self):
+        self.config = RiskNormalizationConfig(number_days_in_forecast = 63,
+                                              number_trades_in_forecast = 63,
+                                              number_equity_in_CDF = 50,
+                                              number_repetitions = 2,
+                                              seed = 1)
+
+    def run_recording_warnings(self, trades, config):
+        with warnings.catch_warnings(record = True) as caught:
+            warnings.simplefilter('always', RiskNormalizationWarning)
+            result = risk_normalization_with_config(trades, config)
+        codes = [warning.message.code for warning in caught
+                 if isinstance(warning.message, RiskNormalizationWarning)]
+        return result, codes
+
+    def test_all_losing_trades(self):
+        trades = [-0.001 - 0.0001 * (i % 7) for i in range(200)]
+        result, codes = self.run_recording_warnings(
+                            trades,
+                            self.config.replace(sample_size_bootstraps = 5))
+
+        self.assertEqual(result.safe_f_mean, 0.0)
+        self.assertIn('all_losing_trades', codes)
+        self.assertEqual(result.sample_size_advisory.safe_f_variation, 0.0)
+
+        plan = required_capital_for(10000, result, 50000)
+        self.assertEqual(plan.minimum_capital, math.inf)
+        self.assertFalse(plan.is_feasible)
+
+    def test_trades_without_losses(self):
+        for trades in ([0.001] * 100, [0.0] * 100):
+            result, codes = self.run_recording_warnings(trades, self.config)
+            self.assertEqual(result.safe_f_list, [MAX_FRACTION] * 2)
+            self.assertIn('no_drawdown_risk', codes)
+
+    def test_rare_large_loss(self):
+
+        #  too few equity curves draw the loss to reach the tail
+        #  percentile at small fractions, but the trades are not
+        #  free of drawdown
+
+        trades = [0.001] * 999 + [-0.2]
+        result, codes = self.run_recording_warnings(trades, self.config)
+
+        self.assertNotIn('no_drawdown_risk', codes)
+        self.assertTrue(all(0.0 < safe_f <= MAX_FRACTION
+                            for safe_f in result.safe_f_list))
+
+    def test_ratios_of_flat_trades(self):
+        result, _ = self.run_recording_warnings(
+                        [0.001] * 100,
+                        self.config.replace(compute_ratios = True))
+
+        for percentiles in result.ratio_percentiles.values():
+            for value in percentiles.values():
+                self.assertFalse(math.isnan(value))
+
+    def test_trades_that_cannot_be_analyzed(self):
+        with self.assertRaises(EmptyTradesError):
+            risk_normalization_with_config([], self.config)
+        with self.assertRaises(TooFewTradesError):
+            risk_normalization_with_config([0.01], self.config)
+        for trades in ([0.01] * 50 + [math.nan],
+                       [0.01] * 50 + [math.inf],
+                       [[0.01, 0.02]] * 50,
+                       ['gain'] * 50):
+            with self.assertRaises(InvalidParameterError):
+                risk_normalization_with_config(trades, self.config)
+
+    def test_option_that_is_not_a_parameter(self):
+        with self.assertRaises(InvalidParameterError):
+            risk_normalization([0.001] * 100, 63, 63, 100000.0, 95, 0.10,
+                               50, 2, not_a_parameter = 1)
+
+
+class AdversarialParametersTest(unittest.TestCase):
+
+    def test_parameters_of_the_wrong_type(self):
+        for name, value in (('initial_capital', 'x'),
+                            ('drawdown_tolerance', None),
+                            ('tail_percentile', '5'),
+                            ('CAR_percentiles', 5),
+                            ('CAR_percentiles', [25, '50']),
+                            ('drawdown_levels', None),
+                            ('sample_size_schedule', [(0.05,)]),
+                            ('benchmark', 0.01),
+                            ('wealth_targets', 'x'),
+                            ('VaR_confidence_levels', [None]),
+                            ('desired_accuracy', 'x'),
+                            ('trailing_drawdown_dollars', '500'),
+                            ('tags', 5),
+                            ('tags', 'x'),
+                            ('tags', [('desk', 'rates')]),
+                            ('tags', {'desk': 5})):
+            with self.assertRaises(InvalidParameterError) as raised:
+                RiskNormalizationConfig(**{name: value})
+            self.assertEqual(raised.exception.name, name)
+
+    def test_true_and_false_are_not_numbers(self):
+        for name in ('max_iterations', 'number_repetitions',
+                     'number_equity_in_CDF', 'seed',
+                     'sample_size_bootstraps', 'initial_capital',
+                     'max_fraction'):
+            for value in (True, False):
+                with self.assertRaises(InvalidParameterError):
+                    RiskNormalizationConfig(**{name: value})
+        with self.assertRaises(InvalidParameterError):
+            RiskNormalizationConfig(CAR_percentiles = (True, 25))
+
+
+class AdversarialJsonTest(unittest.TestCase):
+
+    def test_text_that_is_not_trades(self):
+        for text in ('[1' + '0' * 400 + ']',
+                     '[' * 5000 + ']' * 5000,
+                     '{"gain": 0.01}',
+                     '[0.01, "gain"]',
+                     '[0.01,',
+                     '',
+                     '[NaN]',
+                     '[Infinity]',
+                     '[[0.01]]',
+                     '[true]'):
+            with self.assertRaises(JsonParseError):
+                parse_trades_from_json(text)
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,101 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+"""
+test_command_line.py
+
+The command line reports every problem through the argument
+parser, as a message and exit status 2, never as a traceback.
+
+Run from the top of the repository:
+    python3 -m unittest discover tests
+
+License:  MIT
+"""
+
+import contextlib
+import io
+import os
+import tempfile
+import unittest
+import warnings
+
+from risk_normalization import RiskNormalizationWarning
+from risk_normalization import main
+
+TRADES = os.path.join(os.path.dirname(os.path.dirname(
+                          os.path.abspath(__file__))),
+                      'generated_normal_trades.csv')
+
+
+class CommandLineTestCase(unittest.TestCase):
+
+    def setUp(self):
+        self.directory = tempfile.TemporaryDirectory()
+        self.addCleanup(self.directory.cleanup)
+        catcher = warnings.catch_warnings()
+        catcher.__enter__()
+        self.addCleanup(catcher.__exit__, None, None, None)
+        warnings.simplefilter('ignore', RiskNormalizationWarning)
+
+    def run_main(self, *arguments):
+        output = io.StringIO()
+        with contextlib.redirect_stdout(output):
+            main(['--trades', TRADES, '--repetitions', '1',
+                  '--equity-in-cdf', '50', '--seed', '1'] + list(arguments))
+        return output.getvalue()
+
+    def assert_parser_error(self, *arguments):
+        errors = io.StringIO()
+        with contextlib.redirect_stderr(errors), \
+             self.assertRaises(SystemExit) as raised:
+            self.run_main(*arguments)
+        self.assertEqual(raised.exception.code, 2)
+        return errors.getvalue()
+
+
+class ExportCARTest(CommandLineTestCase):
+
+    def test_export_to_csv(self):
+        path = os.path.join(self.directory.name, 'car.csv')
+        output = self.run_main('--export-car', path)
+
+        self.assertIn('safe-f mean', output)
+        with open(path) as f:
+            self.assertGreater(len(f.readlines()), 1)
+
+    def test_export_to_a_directory_that_does_not_exist(self):
+        path = os.path.join(self.directory.name, 'missing', 'car.csv')
+        message = self.assert_parser_error('--export-car', path)
+
+        self.assertIn(f'cannot write {path}', message)
+
+
+class RecordTest(CommandLineTestCase):
+
+    def test_record_and_replay(self):
+        path = os.path.join(self.directory.name, 'session.bin')
+        self.run_main('--record', path)
+        output = self.run_main('--replay', path)
+
+        self.assertIn('The replay matches the recorded results exactly.',
+                      output)
+
+    def test_record_to_a_directory_that_does_not_exist(self):
+        path = os.path.join(self.directory.name, 'missing', 'session.bin')
+        message = self.assert_parser_error('--record', path)
+
+        self.assertIn(f'cannot write {path}', message)
+
+    def test_replay_of_a_file_that_cannot_be_read(self):
+        path = os.path.join(self.directory.name, 'session.bin')
+        message = self.assert_parser_error('--replay', path)
+        self.assertIn(f'cannot read {path}', message)
+
+        with open(path, 'w') as f:
+            f.write('not a session')
+        message = self.assert_parser_error('--replay', path)
+        self.assertIn(path, message)
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,88 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+"""
+test_dynamic_sizing.py
+
+risk_normalization_dynamic trades the equity curve, recomputing
+safe-f as each forecast progresses, and compares it with holding
+the static safe-f over the same draws of trades.
+
+Run from the top of the repository:
+    python3 -m unittest discover tests
+
+License:  MIT
+"""
+
+import unittest
+import warnings
+
+from reproducibility import make_synthetic_trades
+from risk_normalization import InvalidParameterError
+from risk_normalization import RiskNormalizationConfig
+from risk_normalization import RiskNormalizationWarning
+from risk_normalization import risk_normalization_dynamic
+
+
+class DynamicSizingTest(unittest.TestCase):
+
+    def setUp(self):
+        self.trades = make_synthetic_trades(3)
+        self.config = RiskNormalizationConfig(number_days_in_forecast = 126,
+                                              number_trades_in_forecast = 126,
+                                              number_equity_in_CDF = 50,
+                                              number_repetitions = 1,
+                                              seed = 7)
+        catcher = warnings.catch_warnings()
+        catcher.__enter__()
+        self.addCleanup(catcher.__exit__, None, None, None)
+        warnings.simplefilter('ignore', RiskNormalizationWarning)
+
+    def test_without_recomputation_dynamic_is_static(self):
+        result = risk_normalization_dynamic(self.trades, self.config,
+                                            recompute_every_days = 200,
+                                            number_paths = 5)
+
+        self.assertEqual(result.fractions.shape, (5, 1))
+        for static, dynamic in zip(result.CARs_static, result.CARs_dynamic):
+            self.assertAlmostEqual(static, dynamic)
+        for static, dynamic in zip(result.drawdowns_static,
+                                   result.drawdowns_dynamic):
+            self.assertAlmostEqual(static, dynamic)
+
+    def test_recomputation_every_month(self):
+        result = risk_normalization_dynamic(self.trades, self.config,
+                                            recompute_every_days = 21,
+                                            number_paths = 3)
+
+        self.assertEqual(result.recompute_every_trades, 21)
+        self.assertEqual(result.window_trades, 126)
+        self.assertEqual(result.fractions.shape, (3, 6))
+        for path_fractions in result.fractions:
+            self.assertEqual(path_fractions[0], result.static_safe_f)
+            self.assertTrue(all(fraction > 0.0
+                                for fraction in path_fractions))
+
+    def test_same_seed_same_results(self):
+        first, second = [risk_normalization_dynamic(self.trades, self.config,
+                                                    recompute_every_days = 63,
+                                                    number_paths = 3)
+                         for _ in range(2)]
+
+        self.assertEqual(list(first.CARs_dynamic), list(second.CARs_dynamic))
+        self.assertEqual(first.fractions.tolist(), second.fractions.tolist())
+
+    def test_parameters_that_cannot_be_used(self):
+        for options in ({'number_paths': 0},
+                        {'number_paths': True},
+                        {'window_trades': 1},
+                        {'window_trades': 50.0},
+                        {'recompute_every_days': 0},
+                        {'recompute_every_days': float('nan')},
+                        {'recompute_every_days': '21'}):
+            with self.assertRaises(InvalidParameterError):
+                risk_normalization_dynamic(self.trades, self.config,
+                                           **options)
+
+
+if __name__ == '__main__':
+    unittest.main()
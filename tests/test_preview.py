@@ -0,0 +1,58 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+"""
+test_preview.py
+
+preview_trades summarizes a set of trades, with its warnings,
+before a long run.
+
+Run from the top of the repository:
+    python3 -m unittest discover tests
+
+License:  MIT
+"""
+
+import unittest
+
+from risk_normalization import RiskNormalizationConfig
+from risk_normalization import TradePreview
+from risk_normalization import preview_trades
+
+
+class PreviewTest(unittest.TestCase):
+
+    def test_summary(self):
+        preview = preview_trades(text = '0.01\n-0.02\n0.03\n0.04\n',
+                                 number_shown = 2)
+
+        self.assertEqual(preview.number_trades, 4)
+        self.assertEqual(list(preview.first_trades), [0.01, -0.02])
+        self.assertEqual(list(preview.last_trades), [0.03, 0.04])
+        self.assertAlmostEqual(preview.mean, 0.015)
+        self.assertAlmostEqual(preview.win_rate, 0.75)
+        self.assertAlmostEqual(preview.largest_loss, -0.02)
+        self.assertEqual(preview.warnings, [])
+
+    def test_warnings(self):
+        preview = preview_trades(text = '0.01\n0.02\n',
+                                 config = RiskNormalizationConfig())
+        codes = [warning.code for warning in preview.warnings]
+
+        self.assertIn('no_losing_trades', codes)
+        self.assertIn('few_trades', codes)
+        self.assertEqual(preview.largest_loss, 0.0)
+
+        preview = preview_trades(text = '-0.01\n-0.02\n')
+        self.assertEqual([warning.code for warning in preview.warnings],
+                         ['no_winning_trades'])
+
+    def test_previews_do_not_share_warnings(self):
+        first = TradePreview(1, [0.01], [0.01], 0.01, 0.0, 1.0, 0.0)
+        second = TradePreview(1, [0.01], [0.01], 0.01, 0.0, 1.0, 0.0)
+        first.warnings.append('changed')
+
+        self.assertEqual(second.warnings, [])
+
+
+if __name__ == '__main__':
+    unittest.main()
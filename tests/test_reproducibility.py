@@ -0,0 +1,81 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+"""
+test_reproducibility.py
+
+The same config and seed must give bit-identical results however
+the work is spread over worker processes.
+
+Run from the top of the repository:
+    python3 -m unittest discover tests
+
+License:  MIT
+"""
+
+import concurrent.futures
+import unittest
+from unittest import mock
+
+import risk_normalization
+from reproducibility import check_worker_counts_agree
+from reproducibility import make_synthetic_trades
+from risk_normalization import RiskNormalizationConfig
+from risk_normalization import count_fraction_processes
+from risk_normalization import risk_normalization_with_config
+
+
+class WorkerCountsTest(unittest.TestCase):
+
+    def setUp(self):
+        self.trades = make_synthetic_trades(1)
+        self.config = RiskNormalizationConfig(number_days_in_forecast = 252,
+                                              number_trades_in_forecast = 252,
+                                              number_equity_in_CDF = 100,
+                                              number_repetitions = 2)
+
+    def test_candidates_in_worker_processes(self):
+
+        #  On a machine with 2 or fewer processors the candidates
+        #  would be evaluated here, and the check would compare the
+        #  engine with itself, so the pools are forced.
+
+        pool = mock.Mock(wraps = concurrent.futures.ProcessPoolExecutor)
+        with mock.patch.object(risk_normalization,
+                               'count_available_processors',
+                               return_value = 8), \
+             mock.patch.object(concurrent.futures,
+                               'ProcessPoolExecutor', pool):
+            self.assertEqual(count_fraction_processes(
+                                 self.config.replace(number_workers = 4)),
+                             4)
+            results = check_worker_counts_agree(self.trades, self.config,
+                                                seed = 1,
+                                                worker_counts = (None, 3, 4))
+
+        #  one pool for each of the two runs with workers
+        self.assertEqual(pool.call_count, 2)
+        self.assertEqual(results[0].safe_f_list, results[2].safe_f_list)
+        self.assertEqual(results[0].CAR25_list, results[2].CAR25_list)
+
+    def test_repetitions_in_worker_processes(self):
+        config = self.config.replace(seed = 1)
+        sequential = risk_normalization_with_config(self.trades, config)
+        with concurrent.futures.ProcessPoolExecutor(2) as executor:
+            pooled = risk_normalization_with_config(
+                         self.trades, config.replace(executor = executor))
+
+        self.assertEqual(pooled.safe_f_list, sequential.safe_f_list)
+        self.assertEqual(pooled.CAR25_list, sequential.CAR25_list)
+        self.assertEqual(pooled.TWR25_list, sequential.TWR25_list)
+
+    def test_check_fails_without_worker_processes(self):
+        with mock.patch.object(risk_normalization,
+                               'count_available_processors',
+                               return_value = 2):
+            with self.assertRaises(AssertionError):
+                check_worker_counts_agree(self.trades, self.config,
+                                          seed = 1)
+
+
+if __name__ == '__main__':
+    unittest.main()
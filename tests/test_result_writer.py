@@ -0,0 +1,208 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+"""
+test_result_writer.py
+
+The files ResultWriter writes must read back as the result that
+was written.
+
+Run from the top of the repository:
+    python3 -m unittest discover tests
+
+License:  MIT
+"""
+
+import csv
+import json
+import os
+import tempfile
+import unittest
+import warnings
+
+import numpy as np
+
+from reproducibility import make_synthetic_trades
+from result_writer import ResultWriter
+from result_writer import SCHEMA_VERSION
+from result_writer import migrate
+from result_writer import read_json
+from result_writer import read_trade_indices
+from risk_normalization import ExecutionNoise
+from risk_normalization import InvalidParameterError
+from risk_normalization import RiskNormalizationConfig
+from risk_normalization import RiskNormalizationWarning
+from risk_normalization import compute_CAR
+from risk_normalization import replay_equity_sequence
+from risk_normalization import risk_normalization_with_config
+
+
+class ResultWriterTestCase(unittest.TestCase):
+
+    def setUp(self):
+        self.directory = tempfile.TemporaryDirectory()
+        self.addCleanup(self.directory.cleanup)
+        self.trades = make_synthetic_trades(3)
+        self.config = RiskNormalizationConfig(number_days_in_forecast = 126,
+                                              number_trades_in_forecast = 126,
+                                              number_equity_in_CDF = 50,
+                                              number_repetitions = 2,
+                                              seed = 7)
+        catcher = warnings.catch_warnings()
+        catcher.__enter__()
+        self.addCleanup(catcher.__exit__, None, None, None)
+        warnings.simplefilter('ignore', RiskNormalizationWarning)
+
+    def path(self, name):
+        return os.path.join(self.directory.name, name)
+
+    def read_csv(self, path):
+        with open(path, newline = '') as f:
+            return list(csv.DictReader(f))
+
+
+class WriteTest(ResultWriterTestCase):
+
+    def test_json_round_trip(self):
+        result = risk_normalization_with_config(self.trades, self.config)
+        ResultWriter(self.config).write_json(result, self.path('run.json'))
+        written = read_json(self.path('run.json'))
+
+        self.assertEqual(written['schema_version'], SCHEMA_VERSION)
+        self.assertEqual(written['seed'], 7)
+        self.assertEqual(written['safe_f_list'], list(result.safe_f_list))
+        self.assertEqual(written['CAR25_list'], list(result.CAR25_list))
+        self.assertEqual(written['safe_f_mean'], result.safe_f_mean)
+        self.assertEqual(written['config']['number_repetitions'], 2)
+        self.assertIsNone(written['CAR_distributions'])
+
+    def test_json_without_a_config(self):
+        result = risk_normalization_with_config(self.trades, self.config)
+        ResultWriter().write_json(result, self.path('run.json'))
+        written = read_json(self.path('run.json'))
+
+        self.assertIsNone(written['config'])
+        self.assertEqual(written['seed'], 7)
+
+    def test_one_csv_row_per_repetition(self):
+        result = risk_normalization_with_config(self.trades, self.config)
+        ResultWriter().write_csv(result, self.path('run.csv'))
+        rows = self.read_csv(self.path('run.csv'))
+
+        self.assertEqual([row['repetition'] for row in rows], ['0', '1'])
+        self.assertEqual([float(row['safe_f']) for row in rows],
+                         list(result.safe_f_list))
+        self.assertEqual([float(row['CAR25']) for row in rows],
+                         list(result.CAR25_list))
+
+    def test_distributions(self):
+        result = risk_normalization_with_config(
+                     self.trades,
+                     self.config.replace(keep_CAR_distributions = True))
+        ResultWriter().write_distributions_csv(result,
+                                               self.path('CARs.csv'))
+        rows = self.read_csv(self.path('CARs.csv'))
+
+        self.assertEqual(len(rows), 2 * 50)
+        CARs = [float(row['CAR']) for row in rows[:50]]
+        self.assertEqual(CARs, sorted(CARs))
+
+        result = risk_normalization_with_config(self.trades, self.config)
+        with self.assertRaises(InvalidParameterError):
+            ResultWriter().write_distributions_csv(result,
+                                                   self.path('CARs.csv'))
+
+
+class TradeIndicesTest(ResultWriterTestCase):
+
+    def test_round_trip(self):
+        result = risk_normalization_with_config(
+                     self.trades,
+                     self.config.replace(keep_trade_indices = True))
+        ResultWriter().write_trade_indices(result,
+                                           self.path('indices.csv.gz'))
+        trade_indices, size_multipliers = read_trade_indices(
+                                              self.path('indices.csv.gz'))
+
+        self.assertEqual(trade_indices.tolist(),
+                         result.trade_indices.tolist())
+        self.assertIsNone(size_multipliers)
+
+    def test_round_trip_with_execution_noise(self):
+        noise = ExecutionNoise(size_stdev = 0.1)
+        result = risk_normalization_with_config(
+                     self.trades,
+                     self.config.replace(keep_trade_indices = True,
+                                         execution_noise = noise))
+        ResultWriter().write_trade_indices(result,
+                                           self.path('indices.csv.gz'))
+        _, size_multipliers = read_trade_indices(self.path('indices.csv.gz'))
+
+        self.assertEqual(size_multipliers.tolist(),
+                         result.size_multipliers.tolist())
+
+    def test_replayed_curves_are_the_final_repetition(self):
+        result = risk_normalization_with_config(
+                     self.trades,
+                     self.config.replace(keep_trade_indices = True,
+                                         keep_CAR_distributions = True))
+        final_equity = [replay_equity_sequence(self.trades, result,
+                                               sequence)[-1]
+                        for sequence in range(50)]
+        CARs = compute_CAR(np.array(final_equity),
+                           self.config.initial_capital,
+                           self.config.number_days_in_forecast,
+                           self.config.trading_days_per_year)
+
+        for replayed, kept in zip(sorted(CARs),
+                                  result.CAR_distributions[-1]):
+            self.assertAlmostEqual(replayed, kept)
+
+    def test_without_trade_indices(self):
+        result = risk_normalization_with_config(self.trades, self.config)
+        with self.assertRaises(InvalidParameterError):
+            ResultWriter().write_trade_indices(result,
+                                               self.path('indices.csv.gz'))
+        with self.assertRaises(InvalidParameterError):
+            replay_equity_sequence(self.trades, result, 0)
+
+
+class MigrationTest(ResultWriterTestCase):
+
+    def test_file_without_a_schema_version(self):
+
+        #  a file written before schema_version was recorded
+        with open(self.path('old.json'), 'w') as f:
+            json.dump({'safe_f_list': [0.5, 0.7],
+                       'TWR25_list': [1.1, 1.3],
+                       'CAR25_list': [20.0, 30.0]}, f)
+        written = read_json(self.path('old.json'))
+
+        self.assertEqual(written['schema_version'], SCHEMA_VERSION)
+        self.assertAlmostEqual(written['safe_f_stats']['mean'], 0.6)
+        self.assertAlmostEqual(written['CAR25_stats']['mean'], 25.0)
+        self.assertEqual(written['equity_bands'], [])
+        self.assertIsNone(written['streaks'])
+        self.assertIsNone(written['benchmark_metrics'])
+        self.assertFalse(written['safe_f_capped'])
+
+    def test_every_earlier_version_upgrades_to_the_current_one(self):
+        for version in range(1, SCHEMA_VERSION):
+            written = migrate({'schema_version': version,
+                               'safe_f_list': [0.5]})
+            self.assertEqual(written['schema_version'], SCHEMA_VERSION)
+            self.assertIn('safe_f_capped', written)
+
+    def test_current_version_is_unchanged(self):
+        result = risk_normalization_with_config(self.trades, self.config)
+        dictionary = ResultWriter().to_dict(result)
+
+        self.assertEqual(migrate(dict(dictionary)), dictionary)
+
+    def test_versions_that_cannot_be_read(self):
+        for version in (0, SCHEMA_VERSION + 1, '2', True, None):
+            with self.assertRaises(InvalidParameterError):
+                migrate({'schema_version': version})
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,71 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+"""
+test_search.py
+
+The search for safe-f, and the shortcuts it takes, must find
+safe-f within desired_accuracy of the drawdown tolerance.
+
+Run from the top of the repository:
+    python3 -m unittest discover tests
+
+License:  MIT
+"""
+
+import unittest
+import warnings
+
+import numpy as np
+
+from reproducibility import make_synthetic_trades
+from risk_normalization import RiskNormalizationConfig
+from risk_normalization import RiskNormalizationWarning
+from risk_normalization import analyze_drawdown_with_seed
+from risk_normalization import risk_normalization_with_config
+
+
+class DrawdownLimitTest(unittest.TestCase):
+
+    def setUp(self):
+        self.trades = np.array(make_synthetic_trades(1))
+        catcher = warnings.catch_warnings()
+        catcher.__enter__()
+        self.addCleanup(catcher.__exit__, None, None, None)
+        warnings.simplefilter('ignore', RiskNormalizationWarning)
+
+    def tail_risk(self, fraction, drawdown_limit):
+        return analyze_drawdown_with_seed(5, self.trades, fraction,
+                                          126, 126, 100000.0, 95, 100,
+                                          drawdown_limit)
+
+    def test_tail_risk_below_the_limit_is_unchanged(self):
+        self.assertEqual(self.tail_risk(0.3, 0.05),
+                         self.tail_risk(0.3, None))
+
+    def test_tail_risk_above_the_limit_is_a_lower_bound(self):
+        limited = self.tail_risk(3.0, 0.05)
+
+        self.assertGreater(limited, 0.05)
+        self.assertLess(limited, self.tail_risk(3.0, None))
+
+    def test_safe_f_within_desired_accuracy_either_way(self):
+        config = RiskNormalizationConfig(number_days_in_forecast = 126,
+                                         number_trades_in_forecast = 126,
+                                         number_equity_in_CDF = 100,
+                                         number_repetitions = 2,
+                                         keep_search_trace = True,
+                                         seed = 2)
+        for drawdown_limit_multiple in (2.0, None):
+            result = risk_normalization_with_config(
+                         self.trades,
+                         config.replace(drawdown_limit_multiple =
+                                            drawdown_limit_multiple))
+            for trace in result.search_traces:
+                final = trace[-1]
+                closest = min(abs(tail_risk - config.drawdown_tolerance)
+                              for tail_risk in final.tail_risks)
+                self.assertLess(closest, config.desired_accuracy)
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,123 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+"""
+test_session.py
+
+A run recorded with record_session must replay exactly, and a
+file that is not such a session must raise SessionFileError
+without running anything from it.
+
+Run from the top of the repository:
+    python3 -m unittest discover tests
+
+License:  MIT
+"""
+
+import copy
+import gzip
+import os
+import pickle
+import tempfile
+import unittest
+import warnings
+
+from reproducibility import make_synthetic_trades
+from risk_normalization import InvalidParameterError
+from risk_normalization import RiskNormalizationConfig
+from risk_normalization import RiskNormalizationWarning
+from risk_normalization import SESSION_VERSION
+from risk_normalization import SessionFileError
+from risk_normalization import read_session
+from risk_normalization import record_session
+from risk_normalization import replay_session
+from risk_normalization import risk_normalization_with_config
+
+
+class SessionTest(unittest.TestCase):
+
+    def setUp(self):
+        self.directory = tempfile.TemporaryDirectory()
+        self.addCleanup(self.directory.cleanup)
+        self.path = os.path.join(self.directory.name, 'session.bin')
+        self.trades = make_synthetic_trades(3)
+        self.config = RiskNormalizationConfig(number_days_in_forecast = 126,
+                                              number_trades_in_forecast = 126,
+                                              number_equity_in_CDF = 100,
+                                              number_repetitions = 2,
+                                              seed = 7)
+        catcher = warnings.catch_warnings()
+        catcher.__enter__()
+        self.addCleanup(catcher.__exit__, None, None, None)
+        warnings.simplefilter('ignore', RiskNormalizationWarning)
+
+    def write_pickle(self, value):
+        with gzip.open(self.path, 'wb') as f:
+            pickle.dump(value, f, protocol = 4)
+
+    def test_replay_matches_the_recording(self):
+        result = risk_normalization_with_config(self.trades, self.config)
+        record_session(self.path, self.trades, result, ['--seed', '7'])
+        session = read_session(self.path)
+
+        self.assertEqual(list(session.trades), list(self.trades))
+        self.assertEqual(session.config.seed, 7)
+        self.assertEqual(session.arguments, ['--seed', '7'])
+        self.assertEqual(session.environment_differences(), {})
+        self.assertTrue(session.matches(replay_session(session)))
+
+        session.safe_f_list = [0.0] * len(session.safe_f_list)
+        self.assertFalse(session.matches(replay_session(session)))
+
+    def test_unseeded_run_is_recorded_with_its_seed(self):
+        result = risk_normalization_with_config(
+                     self.trades, self.config.replace(seed = None))
+        record_session(self.path, self.trades, result)
+        session = read_session(self.path)
+
+        self.assertIsNotNone(session.config.seed)
+        self.assertTrue(session.matches(replay_session(session)))
+
+    def test_result_without_its_config(self):
+        result = copy.copy(risk_normalization_with_config(self.trades,
+                                                          self.config))
+        result.config = None
+        with self.assertRaises(InvalidParameterError):
+            record_session(self.path, self.trades, result)
+
+    def test_files_that_are_not_sessions(self):
+        with open(self.path, 'wb') as f:
+            f.write(b'not a session')
+        with self.assertRaises(SessionFileError):
+            read_session(self.path)
+
+        result = risk_normalization_with_config(self.trades, self.config)
+        record_session(self.path, self.trades, result)
+        with open(self.path, 'rb') as f:
+            recorded = f.read()
+        with open(self.path, 'wb') as f:
+            f.write(recorded[:len(recorded) // 2])
+        with self.assertRaises(SessionFileError):
+            read_session(self.path)
+
+        self.write_pickle([0.01, 0.02])
+        with self.assertRaises(SessionFileError):
+            read_session(self.path)
+
+        self.write_pickle(os.getcwd)
+        with self.assertRaises(SessionFileError):
+            read_session(self.path)
+
+    def test_session_of_another_version(self):
+        result = risk_normalization_with_config(self.trades, self.config)
+        record_session(self.path, self.trades, result)
+        session = read_session(self.path)
+        session.version = SESSION_VERSION + 1
+        self.write_pickle(session)
+
+        with self.assertRaises(SessionFileError) as raised:
+            read_session(self.path)
+        self.assertIn('session version', raised.exception.reason)
+
+
+if __name__ == '__main__':
+    unittest.main()
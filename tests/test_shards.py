@@ -0,0 +1,90 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+"""
+test_shards.py
+
+A run split into repetition shards and merged must give the same
+results as the run in one piece.
+
+Run from the top of the repository:
+    python3 -m unittest discover tests
+
+License:  MIT
+"""
+
+import concurrent.futures
+import unittest
+import warnings
+
+from reproducibility import make_synthetic_trades
+from risk_normalization import InvalidParameterError
+from risk_normalization import RiskNormalizationConfig
+from risk_normalization import RiskNormalizationWarning
+from risk_normalization import merge_repetition_shards
+from risk_normalization import risk_normalization_in_shards
+from risk_normalization import risk_normalization_with_config
+from risk_normalization import run_repetition_shard
+
+
+class ShardTest(unittest.TestCase):
+
+    def setUp(self):
+        self.trades = make_synthetic_trades(3)
+        self.config = RiskNormalizationConfig(number_days_in_forecast = 126,
+                                              number_trades_in_forecast = 126,
+                                              number_equity_in_CDF = 100,
+                                              number_repetitions = 3,
+                                              seed = 7)
+        catcher = warnings.catch_warnings()
+        catcher.__enter__()
+        self.addCleanup(catcher.__exit__, None, None, None)
+        warnings.simplefilter('ignore', RiskNormalizationWarning)
+
+    def assert_same_results(self, result, expected):
+        self.assertEqual(result.safe_f_list, expected.safe_f_list)
+        self.assertEqual(result.CAR25_list, expected.CAR25_list)
+        self.assertEqual(result.TWR25_list, expected.TWR25_list)
+
+    def test_merged_shards_are_the_whole_run(self):
+        expected = risk_normalization_with_config(self.trades, self.config)
+        shards = [run_repetition_shard(self.trades, self.config, 0, 1),
+                  run_repetition_shard(self.trades, self.config, 1)]
+
+        self.assertEqual([len(shard) for shard in shards], [1, 2])
+        self.assert_same_results(
+            merge_repetition_shards(shards, self.trades, self.config),
+            expected)
+
+    def test_shards_on_an_executor(self):
+        expected = risk_normalization_with_config(self.trades, self.config)
+        with concurrent.futures.ThreadPoolExecutor(2) as executor:
+            for number_shards in (1, 2, 3):
+                self.assert_same_results(
+                    risk_normalization_in_shards(self.trades, self.config,
+                                                 number_shards, executor),
+                    expected)
+
+    def test_shards_that_cannot_be_run(self):
+        unseeded = self.config.replace(seed = None)
+        with self.assertRaises(InvalidParameterError):
+            run_repetition_shard(self.trades, unseeded)
+        for first, stop in ((1, 1), (-1, 2), (0, 4), (2, 1)):
+            with self.assertRaises(InvalidParameterError):
+                run_repetition_shard(self.trades, self.config, first, stop)
+        with concurrent.futures.ThreadPoolExecutor(1) as executor:
+            with self.assertRaises(InvalidParameterError):
+                risk_normalization_in_shards(self.trades, unseeded, 1,
+                                             executor)
+            for number_shards in (0, 4, 1.5, True):
+                with self.assertRaises(InvalidParameterError):
+                    risk_normalization_in_shards(self.trades, self.config,
+                                                 number_shards, executor)
+
+    def test_merge_without_every_repetition(self):
+        shard = run_repetition_shard(self.trades, self.config, 0, 2)
+        with self.assertRaises(InvalidParameterError):
+            merge_repetition_shards([shard], self.trades, self.config)
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,115 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+"""
+test_solvers.py
+
+The solvers that turn risk_normalization around: they must find
+what they are asked for, and refuse parameters they cannot use
+with InvalidParameterError.
+
+Run from the top of the repository:
+    python3 -m unittest discover tests
+
+License:  MIT
+"""
+
+import unittest
+import warnings
+
+from reproducibility import make_synthetic_trades
+from risk_normalization import InvalidParameterError
+from risk_normalization import RiskNormalizationConfig
+from risk_normalization import RiskNormalizationWarning
+from risk_normalization import SearchDidNotConvergeError
+from risk_normalization import risk_normalization_with_config
+from risk_normalization import solve_mean_improvement_for_CAR25
+from risk_normalization import solve_tolerance_for_fraction
+
+
+class SolverTestCase(unittest.TestCase):
+
+    def setUp(self):
+        self.trades = make_synthetic_trades(3)
+        self.config = RiskNormalizationConfig(number_days_in_forecast = 126,
+                                              number_trades_in_forecast = 126,
+                                              number_equity_in_CDF = 100,
+                                              number_repetitions = 1,
+                                              seed = 7)
+        catcher = warnings.catch_warnings()
+        catcher.__enter__()
+        self.addCleanup(catcher.__exit__, None, None, None)
+        warnings.simplefilter('ignore', RiskNormalizationWarning)
+
+
+class ToleranceForFractionTest(SolverTestCase):
+
+    def test_tolerance_at_safe_f_is_the_drawdown_tolerance(self):
+        safe_f = risk_normalization_with_config(self.trades,
+                                                self.config).safe_f_mean
+        tolerance = solve_tolerance_for_fraction(self.trades, safe_f,
+                                                 self.config)
+
+        self.assertAlmostEqual(tolerance.mean, self.config.drawdown_tolerance,
+                               delta = self.config.desired_accuracy)
+
+    def test_tolerance_grows_with_the_fraction(self):
+        tolerances = [solve_tolerance_for_fraction(self.trades, fraction,
+                                                   self.config).mean
+                      for fraction in (0.2, 0.5, 2.0)]
+
+        self.assertEqual(tolerances, sorted(tolerances))
+        self.assertGreater(tolerances[0], 0.0)
+
+    def test_fractions_that_cannot_be_used(self):
+        for fraction in (0, -0.5, float('nan'), float('inf'), True, '0.5'):
+            with self.assertRaises(InvalidParameterError):
+                solve_tolerance_for_fraction(self.trades, fraction,
+                                             self.config)
+
+
+class MeanImprovementTest(SolverTestCase):
+
+    def test_trades_that_already_reach_the_target(self):
+        CAR25 = risk_normalization_with_config(self.trades,
+                                               self.config).CAR25_mean
+        improvement, _, CAR25_mean = solve_mean_improvement_for_CAR25(
+                                         self.trades, CAR25 - 1.0,
+                                         self.config)
+
+        self.assertEqual(improvement, 0.0)
+        self.assertEqual(CAR25_mean, CAR25)
+
+    def test_improvement_reaches_the_target(self):
+        CAR25 = risk_normalization_with_config(self.trades,
+                                               self.config).CAR25_mean
+        target = CAR25 + 5.0
+        improvement, improved_mean, CAR25_mean = \
+            solve_mean_improvement_for_CAR25(self.trades, target,
+                                             self.config, accuracy = 0.5)
+
+        self.assertGreater(improvement, 0.0)
+        self.assertAlmostEqual(improved_mean,
+                               sum(self.trades) / len(self.trades) +
+                                   improvement)
+        self.assertGreater(CAR25_mean, target - 0.5)
+
+    def test_target_not_reached(self):
+        with self.assertRaises(SearchDidNotConvergeError):
+            solve_mean_improvement_for_CAR25(self.trades, 1e9, self.config,
+                                             max_iterations = 1)
+
+    def test_parameters_that_cannot_be_used(self):
+        for options in ({'max_iterations': 0},
+                        {'max_iterations': True},
+                        {'max_iterations': 2.5},
+                        {'accuracy': 0.0},
+                        {'accuracy': 'x'}):
+            with self.assertRaises(InvalidParameterError):
+                solve_mean_improvement_for_CAR25(self.trades, 50.0,
+                                                 self.config, **options)
+        with self.assertRaises(InvalidParameterError):
+            solve_mean_improvement_for_CAR25(self.trades, None, self.config)
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,103 @@
+#!/usr/bin/env python3
+# -*- coding: utf-8 -*-
+"""
+test_sweep.py
+
+A sweep must give, in each cell of its grid, the result of a run
+with that cell's parameters.
+
+Run from the top of the repository:
+    python3 -m unittest discover tests
+
+License:  MIT
+"""
+
+import unittest
+import warnings
+
+from reproducibility import make_synthetic_trades
+from risk_normalization import InvalidParameterError
+from risk_normalization import RiskNormalizationConfig
+from risk_normalization import RiskNormalizationWarning
+from risk_normalization import risk_normalization_with_config
+from sweep import apply_parameter
+from sweep import parameter_range
+from sweep import sweep
+
+
+class SweepTest(unittest.TestCase):
+
+    def setUp(self):
+        self.trades = make_synthetic_trades(3)
+        self.config = RiskNormalizationConfig(number_days_in_forecast = 126,
+                                              number_trades_in_forecast = 126,
+                                              number_equity_in_CDF = 50,
+                                              number_repetitions = 1,
+                                              seed = 7)
+        catcher = warnings.catch_warnings()
+        catcher.__enter__()
+        self.addCleanup(catcher.__exit__, None, None, None)
+        warnings.simplefilter('ignore', RiskNormalizationWarning)
+
+    def test_parameter_range(self):
+        values = parameter_range(0.05, 0.25, 0.05)
+
+        self.assertEqual(len(values), 5)
+        self.assertAlmostEqual(values[-1], 0.25)
+        self.assertEqual(parameter_range(1, 1, 1), [1])
+        for start, stop, step in ((0.05, 0.25, 0.0), (0.05, 0.25, -0.05),
+                                  (0.25, 0.05, 0.05)):
+            with self.assertRaises(InvalidParameterError):
+                parameter_range(start, stop, step)
+
+    def test_years_forecast(self):
+        config = apply_parameter(self.config, 'years_forecast', 2)
+
+        self.assertEqual(config.number_days_in_forecast,
+                         2 * self.config.trading_days_per_year)
+        self.assertEqual(config.number_trades_in_forecast,
+                         2 * self.config.trading_days_per_year)
+
+    def test_one_parameter(self):
+        tolerances = [0.05, 0.10, 0.20]
+        grid = sweep(self.trades, self.config,
+                     {'drawdown_tolerance': tolerances})
+        safe_fs = grid.values_of('safe_f_mean')
+
+        self.assertEqual(grid.names, ['drawdown_tolerance'])
+        self.assertEqual(grid.values, [tolerances])
+        self.assertEqual(safe_fs.shape, (3, 1))
+        self.assertEqual(list(safe_fs[:, 0]), sorted(safe_fs[:, 0]))
+        expected = risk_normalization_with_config(
+                       self.trades,
+                       self.config.replace(drawdown_tolerance = 0.10))
+        self.assertEqual(grid.results[1][0].safe_f_list,
+                         expected.safe_f_list)
+
+    def test_two_parameters(self):
+        grid = sweep(self.trades, self.config,
+                     {'drawdown_tolerance': [0.05, 0.10],
+                      'years_forecast': [0.5, 1]})
+        usage = grid.compute_usage()
+
+        self.assertEqual(grid.values_of('CAR25_mean').shape, (2, 2))
+        self.assertEqual(grid.results[0][1].config.number_days_in_forecast,
+                         self.config.trading_days_per_year)
+        self.assertEqual(usage.number_paths,
+                         sum(result.compute_usage.number_paths
+                             for row in grid.results for result in row))
+
+    def test_parameters_that_cannot_be_swept(self):
+        for parameters in ({},
+                           {'drawdown_tolerance': [0.1],
+                            'tail_percentile': [5],
+                            'initial_capital': [100000.0]},
+                           {'not_a_parameter': [1]},
+                           {'drawdown_tolerance': []},
+                           [('drawdown_tolerance', [0.1])]):
+            with self.assertRaises(InvalidParameterError):
+                sweep(self.trades, self.config, parameters)
+
+
+if __name__ == '__main__':
+    unittest.main()